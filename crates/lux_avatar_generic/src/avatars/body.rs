@@ -0,0 +1,378 @@
+use std::f32::consts::{FRAC_PI_3, FRAC_PI_4, PI, TAU};
+
+use bevy::prelude::*;
+use lux_components::{ComponentEntityRef, LocalUser};
+
+use crate::avatars::{bones::*, AvatarGeneric};
+
+/// Part of the head pitch the spine leans with.
+const LEAN_FROM_HEAD: f32 = 0.5;
+/// Lean of the spine when fully crouched.
+const LEAN_FROM_CROUCH: f32 = FRAC_PI_4;
+const MAX_LEAN: f32 = FRAC_PI_3;
+/// Lowest the hips go, as part of their height at rest.
+const MIN_HIPS_HEIGHT: f32 = 0.35;
+
+/// How the body follows the head, when only the head and hands are tracked.
+#[derive(Reflect, Clone, Debug, PartialEq)]
+pub struct BodySettings {
+    /// How far a foot is left behind before it steps.
+    pub step_distance: f32,
+    /// How far the body turns away from a foot before it steps, in radians.
+    pub step_angle: f32,
+    /// Seconds a step takes.
+    pub step_duration: f32,
+    /// How high a foot lifts while stepping.
+    pub step_height: f32,
+    /// Seconds the hips take to catch up with the head, zero to follow at once.
+    pub smoothing: f32,
+}
+
+impl Default for BodySettings {
+    fn default() -> Self {
+        Self {
+            step_distance: 0.25,
+            step_angle: 40f32.to_radians(),
+            step_duration: 0.25,
+            step_height: 0.08,
+            smoothing: 0.1,
+        }
+    }
+}
+
+/// Positions of the targets when the avatar was set up, standing straight.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BodyRest {
+    pub head: Vec3,
+    pub hips: Vec3,
+    pub feet: [Transform; 2],
+}
+
+impl BodyRest {
+    fn floor(&self) -> f32 {
+        (self.feet[0].translation.y + self.feet[1].translation.y) * 0.5
+    }
+
+    fn leg_height(&self) -> f32 {
+        (self.hips.y - self.floor()).max(f32::EPSILON)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Foot {
+    planted: Vec3,
+    yaw: f32,
+    /// Where the step started and how far it got, from 0 to 1.
+    step: Option<(Vec3, f32)>,
+    position: Vec3,
+}
+
+/// Body estimated so far for an avatar, feet included.
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct BodyState {
+    rest: BodyRest,
+    hips: Vec3,
+    yaw: f32,
+    lean: f32,
+    feet: [Foot; 2],
+}
+
+impl BodyState {
+    /// Body standing under the head, feet planted.
+    pub fn new(rest: BodyRest, spine: f32, head: &Transform) -> Self {
+        let (hips, yaw, lean) = estimate_hips(&rest, spine, head);
+        let mut state = Self {
+            rest,
+            hips,
+            yaw,
+            lean,
+            feet: [Foot {
+                planted: Vec3::ZERO,
+                yaw,
+                step: None,
+                position: Vec3::ZERO,
+            }; 2],
+        };
+        for side in 0..2 {
+            let planted = state.foot_under_hips(side);
+            state.feet[side].planted = planted;
+            state.feet[side].position = planted;
+        }
+        state
+    }
+
+    pub fn update(&mut self, head: &Transform, spine: f32, settings: &BodySettings, dt: f32) {
+        let (hips, yaw, lean) = estimate_hips(&self.rest, spine, head);
+        let follow = if settings.smoothing > 0.0 {
+            1.0 - (-dt / settings.smoothing).exp()
+        } else {
+            1.0
+        };
+        self.hips = self.hips.lerp(hips, follow);
+        self.yaw += angle_between(yaw, self.yaw) * follow;
+        self.lean += (lean - self.lean) * follow;
+        self.step_feet(settings, dt);
+    }
+
+    pub fn hips(&self) -> Transform {
+        Transform::from_translation(self.hips)
+            .with_rotation(Quat::from_rotation_y(self.yaw) * Quat::from_rotation_x(self.lean))
+    }
+
+    pub fn foot(&self, side: usize) -> Transform {
+        let foot = &self.feet[side];
+        Transform::from_translation(foot.position)
+            .with_rotation(Quat::from_rotation_y(foot.yaw) * self.rest.feet[side].rotation)
+    }
+
+    /// Where the foot stands with the hips as they are now.
+    fn foot_under_hips(&self, side: usize) -> Vec3 {
+        let rest = self.rest.feet[side].translation;
+        let mut offset = rest - self.rest.hips;
+        offset.y = 0.0;
+        let mut under = self.hips + Quat::from_rotation_y(self.yaw) * offset;
+        under.y = rest.y;
+        under
+    }
+
+    /// One foot at a time steps, the one furthest from where it should be.
+    fn step_feet(&mut self, settings: &BodySettings, dt: f32) {
+        let wanted = [self.foot_under_hips(0), self.foot_under_hips(1)];
+        if self.feet.iter().all(|f| f.step.is_none()) {
+            let left_behind = |side: usize| {
+                let foot = &self.feet[side];
+                let distance = foot.planted.distance(wanted[side]);
+                let turn = angle_between(self.yaw, foot.yaw).abs();
+                (distance > settings.step_distance || turn > settings.step_angle)
+                    .then_some(distance + turn)
+            };
+            let side = match (left_behind(0), left_behind(1)) {
+                (Some(l), Some(r)) => Some(if l >= r { 0 } else { 1 }),
+                (Some(_), None) => Some(0),
+                (None, Some(_)) => Some(1),
+                (None, None) => None,
+            };
+            if let Some(side) = side {
+                self.feet[side].step = Some((self.feet[side].planted, 0.0));
+            }
+        }
+        for (foot, wanted) in self.feet.iter_mut().zip(wanted) {
+            let Some((from, progress)) = foot.step else {
+                foot.position = foot.planted;
+                continue;
+            };
+            let progress = if settings.step_duration > 0.0 {
+                (progress + dt / settings.step_duration).min(1.0)
+            } else {
+                1.0
+            };
+            if progress >= 1.0 {
+                foot.planted = wanted;
+                foot.yaw = self.yaw;
+                foot.step = None;
+                foot.position = wanted;
+                continue;
+            }
+            let eased = progress * progress * (3.0 - 2.0 * progress);
+            foot.position =
+                from.lerp(wanted, eased) + Vec3::Y * settings.step_height * (PI * progress).sin();
+            foot.step = Some((from, progress));
+        }
+    }
+}
+
+/// Hips under the head, leaning with the head pitch and while crouching.
+fn estimate_hips(rest: &BodyRest, spine: f32, head: &Transform) -> (Vec3, f32, f32) {
+    let (yaw, pitch, _) = head.rotation.to_euler(EulerRot::YXZ);
+    let crouch = ((rest.head.y - head.translation.y) / rest.leg_height()).clamp(0.0, 1.0);
+    let lean = (pitch * LEAN_FROM_HEAD + crouch * LEAN_FROM_CROUCH).clamp(-MAX_LEAN, MAX_LEAN);
+    let spine = Quat::from_rotation_y(yaw) * Quat::from_rotation_x(lean) * Vec3::Y * spine;
+    let mut hips = head.translation - spine;
+    hips.y = hips
+        .y
+        .max(rest.floor() + rest.leg_height() * MIN_HIPS_HEIGHT);
+    (hips, yaw, lean)
+}
+
+/// Shortest turn from `b` to `a`.
+fn angle_between(a: f32, b: f32) -> f32 {
+    (a - b + PI).rem_euclid(TAU) - PI
+}
+
+/// Only the local user is estimated, the others get the bones synced.
+#[allow(clippy::type_complexity)]
+pub(crate) fn estimate_body(
+    mut cmd: Commands,
+    time: Option<Res<Time>>,
+    mut avatars: Query<
+        (
+            Entity,
+            &AvatarGeneric,
+            Option<&mut BodyState>,
+            &ComponentEntityRef<Bone<Hips>>,
+            &ComponentEntityRef<Target<Hips>>,
+            &ComponentEntityRef<Target<Head>>,
+            &ComponentEntityRef<Target<FootL>>,
+            &ComponentEntityRef<Target<FootR>>,
+        ),
+        With<LocalUser>,
+    >,
+    mut transforms: Query<&mut Transform>,
+) {
+    let dt = time.map(|t| t.delta_seconds()).unwrap_or_default();
+    for (avatar, av, state, hips, hips_target, head, foot_l, foot_r) in avatars.iter_mut() {
+        let Ok(head) = transforms.get(head.entity_id).copied() else {
+            continue;
+        };
+        let spine = av.distance_hips_to_head;
+        let state = match state {
+            Some(mut state) => {
+                state.update(&head, spine, &av.body, dt);
+                state.clone()
+            }
+            None => {
+                let (Ok(hips), Ok(left), Ok(right)) = (
+                    transforms.get(hips_target.entity_id),
+                    transforms.get(foot_l.entity_id),
+                    transforms.get(foot_r.entity_id),
+                ) else {
+                    continue;
+                };
+                let rest = BodyRest {
+                    head: head.translation,
+                    hips: hips.translation,
+                    feet: [*left, *right],
+                };
+                let state = BodyState::new(rest, spine, &head);
+                cmd.entity(avatar).try_insert(state.clone());
+                state
+            }
+        };
+        let placed = [
+            (hips.entity_id, state.hips()),
+            (foot_l.entity_id, state.foot(0)),
+            (foot_r.entity_id, state.foot(1)),
+        ];
+        for (entity, placed) in placed {
+            if let Ok(mut transform) = transforms.get_mut(entity) {
+                transform.translation = placed.translation;
+                transform.rotation = placed.rotation;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn rest() -> BodyRest {
+        BodyRest {
+            head: Vec3::new(0.0, 1.6, 0.0),
+            hips: Vec3::new(0.0, 1.0, 0.0),
+            feet: [
+                Transform::from_xyz(0.1, 0.0, 0.0),
+                Transform::from_xyz(-0.1, 0.0, 0.0),
+            ],
+        }
+    }
+
+    fn settings() -> BodySettings {
+        BodySettings {
+            smoothing: 0.0,
+            ..default()
+        }
+    }
+
+    #[test]
+    fn test_standing() {
+        let state = BodyState::new(rest(), 0.6, &Transform::from_xyz(0.0, 1.6, 0.0));
+        assert!(state.hips().translation.abs_diff_eq(rest().hips, 1e-5));
+        assert!(state.hips().rotation.abs_diff_eq(Quat::IDENTITY, 1e-5));
+        assert!(state.foot(0).translation.abs_diff_eq(Vec3::X * 0.1, 1e-5));
+        assert!(state.foot(1).translation.abs_diff_eq(Vec3::X * -0.1, 1e-5));
+    }
+
+    #[test]
+    fn test_lean_and_crouch() {
+        let mut state = BodyState::new(rest(), 0.6, &Transform::from_xyz(0.0, 1.6, 0.0));
+        let looking_down =
+            Transform::from_xyz(0.0, 1.6, 0.0).with_rotation(Quat::from_rotation_x(0.6));
+        state.update(&looking_down, 0.6, &settings(), 0.01);
+        let hips = state.hips().translation;
+        // Leaning forward, +Z, leaves the hips behind.
+        assert!(hips.z < -0.1, "{}", hips);
+
+        state.update(&Transform::from_xyz(0.0, 0.9, 0.0), 0.6, &settings(), 0.01);
+        let hips = state.hips().translation;
+        assert!(hips.y < 0.5, "{}", hips);
+        assert!(hips.y >= 0.35 - 1e-5, "{}", hips);
+        assert!(hips.z < 0.0, "{}", hips);
+        // Feet are planted on the floor.
+        assert!(state.feet.iter().all(|f| f.planted.y == 0.0));
+    }
+
+    #[test]
+    fn test_steps_one_foot_at_a_time() {
+        let mut state = BodyState::new(rest(), 0.6, &Transform::from_xyz(0.0, 1.6, 0.0));
+        let walked = Transform::from_xyz(0.0, 1.6, 0.1);
+        state.update(&walked, 0.6, &settings(), 0.01);
+        assert!(
+            state.feet.iter().all(|f| f.step.is_none()),
+            "stepped too soon"
+        );
+
+        let walked = Transform::from_xyz(0.0, 1.6, 0.5);
+        state.update(&walked, 0.6, &settings(), 0.1);
+        let stepping: Vec<_> = state.feet.iter().filter(|f| f.step.is_some()).collect();
+        assert_eq!(stepping.len(), 1);
+        let lifted = state.foot(0).translation.y.max(state.foot(1).translation.y);
+        assert!(lifted > 0.0);
+
+        for _ in 0..10 {
+            state.update(&walked, 0.6, &settings(), 0.1);
+        }
+        assert!(state
+            .foot(0)
+            .translation
+            .abs_diff_eq(Vec3::new(0.1, 0.0, 0.5), 1e-5));
+        assert!(state
+            .foot(1)
+            .translation
+            .abs_diff_eq(Vec3::new(-0.1, 0.0, 0.5), 1e-5));
+    }
+
+    #[test]
+    fn test_turning_steps() {
+        let mut state = BodyState::new(rest(), 0.6, &Transform::from_xyz(0.0, 1.6, 0.0));
+        let turned = Transform::from_xyz(0.0, 1.6, 0.0).with_rotation(Quat::from_rotation_y(PI));
+        for _ in 0..10 {
+            state.update(&turned, 0.6, &settings(), 0.1);
+        }
+        assert!(state
+            .foot(0)
+            .translation
+            .abs_diff_eq(Vec3::new(-0.1, 0.0, 0.0), 1e-5));
+        let rotation = state.foot(0).rotation;
+        assert!(rotation.angle_between(Quat::from_rotation_y(PI)) < 1e-3);
+    }
+
+    #[test]
+    fn test_smoothing() {
+        let mut state = BodyState::new(rest(), 0.6, &Transform::from_xyz(0.0, 1.6, 0.0));
+        let moved = Transform::from_xyz(0.0, 1.6, 1.0);
+        state.update(&moved, 0.6, &default(), 0.05);
+        let z = state.hips().translation.z;
+        assert!(z > 0.0 && z < 1.0, "{}", z);
+        for _ in 0..100 {
+            state.update(&moved, 0.6, &default(), 0.05);
+        }
+        assert!((state.hips().translation.z - 1.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_angle_between() {
+        assert!((angle_between(0.1, TAU - 0.1) - 0.2).abs() < 1e-5);
+        assert!((angle_between(-3.0, 3.0) - (TAU - 6.0)).abs() < 1e-5);
+    }
+}
@@ -1,12 +1,17 @@
-use std::{any::TypeId, sync::LazyLock};
+use std::{collections::VecDeque, sync::LazyLock};
 
-use crate::{avatars::bones::*, AvatarGeneric};
+use crate::{
+    avatars::{bones::*, ik::*, rig_mapping::*},
+    AvatarGeneric,
+};
 use bevy::{ecs::world::DeferredWorld, prelude::*};
 use bevy_mod_inverse_kinematics::IkConstraint;
 use lux_components::ComponentEntityRef;
 
 pub fn apply(id: Entity, world: &mut DeferredWorld) {
-    if let Some(armature_id) = find_armature_entity_id(id, world) {
+    let mapping = world.get::<RigMapping>(id).cloned();
+    let mut report = RigReport::default();
+    if let Some(armature_id) = find_armature_entity_id(id, mapping.as_ref(), &mut report, world) {
         world
             .commands()
             .entity(armature_id)
@@ -15,32 +20,69 @@ pub fn apply(id: Entity, world: &mut DeferredWorld) {
             .commands()
             .entity(id)
             .insert(ComponentEntityRef::<Bone<Root>>::new(armature_id));
-        BONE_TREE.apply(id, armature_id, armature_id, world);
-        set_avatar_attributes(id, world);
+        BONE_TREE.apply(
+            id,
+            armature_id,
+            &[armature_id],
+            mapping.as_ref(),
+            &mut report,
+            world,
+        );
+        set_avatar_attributes(id, &report, world);
     }
+    log_report(&report);
+    world.commands().entity(id).insert(report);
 }
 
-fn set_avatar_attributes(id: Entity, world: &mut DeferredWorld) {
-    let opt_hips_id = find_by_name_in_childs(&Name::new("Hips"), id, world);
-    let opt_head_id = find_by_name_in_childs(&Name::new("Head"), id, world);
-    let Some(hips_id) = opt_hips_id else {
-        return;
+fn log_report(report: &RigReport) {
+    for mapped in report.mapped.iter() {
+        debug!("Bone {} is {} ({:?})", mapped.bone, mapped.node, mapped.by);
+    }
+    if report.missing.is_empty() {
+        info!("Mapped all {} bones of the avatar", report.mapped.len());
+    } else {
+        warn!(
+            "Mapped {} bones of the avatar, could not find: {}",
+            report.mapped.len(),
+            report.missing.join(", ")
+        );
+    }
+}
+
+fn set_avatar_attributes(id: Entity, report: &RigReport, world: &mut DeferredWorld) {
+    let position = |bone: &str| {
+        let entity = report.get(bone)?.entity;
+        Some(world.get::<GlobalTransform>(entity)?.translation())
     };
-    let Some(head_id) = opt_head_id else {
+    let (Some(hips), Some(head)) = (position("Hips"), position("Head")) else {
         return;
     };
-    let Some(hips_gt) = world.get::<GlobalTransform>(hips_id) else {
-        return;
+    let floor = match (position("Foot.L"), position("Foot.R")) {
+        (Some(left), Some(right)) => (left.y + right.y) * 0.5,
+        _ => hips.y - hips.distance(head),
     };
-    let Some(head_gt) = world.get::<GlobalTransform>(head_id) else {
-        return;
+    // Summed bone by bone, so it does not depend on the rest pose.
+    let length = |bones: &[&str]| -> Option<f32> {
+        let points = bones
+            .iter()
+            .map(|bone| position(bone))
+            .collect::<Option<Vec<_>>>()?;
+        Some(points.windows(2).map(|w| w[0].distance(w[1])).sum())
     };
-    let hips_gt = *hips_gt;
-    let head_gt = *head_gt;
+    let arm_span = length(&[
+        "Hand.L",
+        "Forearm.L",
+        "Arm.L",
+        "Arm.R",
+        "Forearm.R",
+        "Hand.R",
+    ]);
     let Some(mut av) = world.get_mut::<AvatarGeneric>(id) else {
         return;
     };
-    av.distance_hips_to_head = Vec3::distance(hips_gt.translation(), head_gt.translation());
+    av.distance_hips_to_head = Vec3::distance(hips, head);
+    av.height = head.y - floor;
+    av.arm_span = arm_span.unwrap_or_default();
 }
 
 struct BoneTree {
@@ -49,169 +91,218 @@ struct BoneTree {
 }
 
 impl BoneTree {
-    fn apply(&self, avatar_id: Entity, armature_id: Entity, id: Entity, world: &mut DeferredWorld) {
-        if let Some(child_id) = self.applier.apply(avatar_id, armature_id, id, world) {
-            for child in self.children.iter() {
-                child.apply(avatar_id, armature_id, child_id, world);
+    fn apply(
+        &self,
+        avatar_id: Entity,
+        armature_id: Entity,
+        starts: &[Entity],
+        mapping: Option<&RigMapping>,
+        report: &mut RigReport,
+        world: &mut DeferredWorld,
+    ) {
+        let found = self
+            .applier
+            .apply(avatar_id, armature_id, starts, mapping, report, world);
+        // Children of an optional bone are also searched from its parent,
+        // some rigs have only part of them below it.
+        let starts = match found {
+            Some(child_id) if self.applier.optional() => [&[child_id], starts].concat(),
+            Some(child_id) => vec![child_id],
+            None if self.applier.optional() => {
+                report.skipped.push(self.applier.name());
+                starts.to_vec()
             }
+            None => {
+                self.add_missing(report);
+                return;
+            }
+        };
+        for child in self.children.iter() {
+            child.apply(avatar_id, armature_id, &starts, mapping, report, world);
+        }
+    }
+
+    /// Bones below a missing one are not searched.
+    fn add_missing(&self, report: &mut RigReport) {
+        if self.applier.optional() {
+            report.skipped.push(self.applier.name());
+        } else {
+            report.missing.push(self.applier.name());
+        }
+        for child in self.children.iter() {
+            child.add_missing(report);
         }
     }
 }
 
-static BONE_TREE: LazyLock<BoneTree> = LazyLock::new(|| {
-    let hips = BonePair {
-        name: "Hips",
-        compo: Bone::<Hips>::default(),
-        target: Some(Target::<Hips>::default()),
-    };
-    let spine = BonePair {
-        name: "Spine",
-        compo: Bone::<Spine>::default(),
-        target: None,
-    };
-    let chest = BonePair {
-        name: "Chest",
-        compo: Bone::<Chest>::default(),
-        target: None,
-    };
-    let neck = BonePair {
-        name: "Neck",
-        compo: Bone::<Neck>::default(),
-        target: None,
-    };
-    let head = BonePair {
-        name: "Head",
-        compo: Bone::<Head>::default(),
-        target: Some(Target::<Head>::default()),
-    };
-    let arm_l = BonePair {
-        name: "Arm.L",
-        compo: Bone::<ArmL>::default(),
-        target: None,
-    };
-    let forearm_l = BonePair {
-        name: "Forearm.L",
-        compo: Bone::<ForearmL>::default(),
-        target: None,
-    };
-    let hand_l = BonePair {
-        name: "Hand.L",
-        compo: Bone::<HandL>::default(),
-        target: Some(Target::<HandL>::default()),
-    };
-    let arm_r = BonePair {
-        name: "Arm.R",
-        compo: Bone::<ArmR>::default(),
-        target: None,
-    };
-    let forearm_r = BonePair {
-        name: "Forearm.R",
-        compo: Bone::<ForearmR>::default(),
-        target: None,
-    };
-    let hand_r = BonePair {
-        name: "Hand.R",
-        compo: Bone::<HandR>::default(),
-        target: Some(Target::<HandR>::default()),
-    };
-    let thigh_l = BonePair {
-        name: "Thigh.L",
-        compo: Bone::<ThighL>::default(),
-        target: None,
-    };
-    let leg_l = BonePair {
-        name: "Leg.L",
-        compo: Bone::<LegL>::default(),
-        target: None,
-    };
-    let foot_l = BonePair {
-        name: "Foot.L",
-        compo: Bone::<FootL>::default(),
-        target: Some(Target::<FootL>::default()),
-    };
-    let thigh_r = BonePair {
-        name: "Thigh.R",
-        compo: Bone::<ThighR>::default(),
-        target: None,
-    };
-    let leg_r = BonePair {
-        name: "Leg.R",
-        compo: Bone::<LegR>::default(),
-        target: None,
-    };
-    let foot_r = BonePair {
-        name: "Foot.R",
-        compo: Bone::<FootR>::default(),
-        target: Some(Target::<FootR>::default()),
-    };
+fn tree(applier: impl BoneApplier + Send + Sync + 'static, children: Vec<BoneTree>) -> BoneTree {
     BoneTree {
-        applier: Box::new(hips),
-        children: vec![
-            BoneTree {
-                applier: Box::new(thigh_l),
-                children: vec![BoneTree {
-                    applier: Box::new(leg_l),
-                    children: vec![BoneTree {
-                        applier: Box::new(foot_l),
-                        children: vec![],
-                    }],
-                }],
-            },
-            BoneTree {
-                applier: Box::new(thigh_r),
-                children: vec![BoneTree {
-                    applier: Box::new(leg_r),
-                    children: vec![BoneTree {
-                        applier: Box::new(foot_r),
-                        children: vec![],
-                    }],
-                }],
-            },
-            BoneTree {
-                applier: Box::new(spine),
-                children: vec![BoneTree {
-                    applier: Box::new(chest),
-                    children: vec![
-                        BoneTree {
-                            applier: Box::new(neck),
-                            children: vec![BoneTree {
-                                applier: Box::new(head),
-                                children: vec![],
-                            }],
-                        },
-                        BoneTree {
-                            applier: Box::new(arm_l),
-                            children: vec![BoneTree {
-                                applier: Box::new(forearm_l),
-                                children: vec![BoneTree {
-                                    applier: Box::new(hand_l),
-                                    children: vec![],
-                                }],
-                            }],
-                        },
-                        BoneTree {
-                            applier: Box::new(arm_r),
-                            children: vec![BoneTree {
-                                applier: Box::new(forearm_r),
-                                children: vec![BoneTree {
-                                    applier: Box::new(hand_r),
-                                    children: vec![],
-                                }],
-                            }],
-                        },
-                    ],
-                }],
-            },
-        ],
+        applier: Box::new(applier),
+        children,
     }
+}
+
+fn bone<T: Bones + 'static>(name: &'static str) -> BonePair<T> {
+    BonePair {
+        name,
+        compo: Bone::<T>::default(),
+        target: None,
+        ik: None,
+        optional: false,
+    }
+}
+
+fn finger<P: Bones + 'static, I: Bones + 'static, D: Bones + 'static>(
+    names: [&'static str; 3],
+) -> BoneTree {
+    tree(
+        bone::<P>(names[0]).optional(),
+        vec![tree(
+            bone::<I>(names[1]).optional(),
+            vec![tree(bone::<D>(names[2]).optional(), vec![])],
+        )],
+    )
+}
+
+static BONE_TREE: LazyLock<BoneTree> = LazyLock::new(|| {
+    let fingers_l = vec![
+        finger::<ThumbProximalL, ThumbIntermediateL, ThumbDistalL>([
+            "ThumbProximal.L",
+            "ThumbIntermediate.L",
+            "ThumbDistal.L",
+        ]),
+        finger::<IndexProximalL, IndexIntermediateL, IndexDistalL>([
+            "IndexProximal.L",
+            "IndexIntermediate.L",
+            "IndexDistal.L",
+        ]),
+        finger::<MiddleProximalL, MiddleIntermediateL, MiddleDistalL>([
+            "MiddleProximal.L",
+            "MiddleIntermediate.L",
+            "MiddleDistal.L",
+        ]),
+        finger::<RingProximalL, RingIntermediateL, RingDistalL>([
+            "RingProximal.L",
+            "RingIntermediate.L",
+            "RingDistal.L",
+        ]),
+        finger::<LittleProximalL, LittleIntermediateL, LittleDistalL>([
+            "LittleProximal.L",
+            "LittleIntermediate.L",
+            "LittleDistal.L",
+        ]),
+    ];
+    let fingers_r = vec![
+        finger::<ThumbProximalR, ThumbIntermediateR, ThumbDistalR>([
+            "ThumbProximal.R",
+            "ThumbIntermediate.R",
+            "ThumbDistal.R",
+        ]),
+        finger::<IndexProximalR, IndexIntermediateR, IndexDistalR>([
+            "IndexProximal.R",
+            "IndexIntermediate.R",
+            "IndexDistal.R",
+        ]),
+        finger::<MiddleProximalR, MiddleIntermediateR, MiddleDistalR>([
+            "MiddleProximal.R",
+            "MiddleIntermediate.R",
+            "MiddleDistal.R",
+        ]),
+        finger::<RingProximalR, RingIntermediateR, RingDistalR>([
+            "RingProximal.R",
+            "RingIntermediate.R",
+            "RingDistal.R",
+        ]),
+        finger::<LittleProximalR, LittleIntermediateR, LittleDistalR>([
+            "LittleProximal.R",
+            "LittleIntermediate.R",
+            "LittleDistal.R",
+        ]),
+    ];
+    let arm_l = tree(
+        bone::<ShoulderL>("Shoulder.L").optional(),
+        vec![tree(
+            bone::<ArmL>("Arm.L"),
+            vec![tree(
+                bone::<ForearmL>("Forearm.L"),
+                vec![tree(
+                    bone::<HandL>("Hand.L").with_ik("Arm.L", Limb::Arm { elbow: "Forearm.L" }),
+                    fingers_l,
+                )],
+            )],
+        )],
+    );
+    let arm_r = tree(
+        bone::<ShoulderR>("Shoulder.R").optional(),
+        vec![tree(
+            bone::<ArmR>("Arm.R"),
+            vec![tree(
+                bone::<ForearmR>("Forearm.R"),
+                vec![tree(
+                    bone::<HandR>("Hand.R").with_ik("Arm.R", Limb::Arm { elbow: "Forearm.R" }),
+                    fingers_r,
+                )],
+            )],
+        )],
+    );
+    let leg_l = tree(
+        bone::<ThighL>("Thigh.L"),
+        vec![tree(
+            bone::<LegL>("Leg.L"),
+            vec![tree(
+                bone::<FootL>("Foot.L").with_ik("Thigh.L", Limb::Leg { knee: "Leg.L" }),
+                vec![tree(bone::<ToesL>("Toes.L").optional(), vec![])],
+            )],
+        )],
+    );
+    let leg_r = tree(
+        bone::<ThighR>("Thigh.R"),
+        vec![tree(
+            bone::<LegR>("Leg.R"),
+            vec![tree(
+                bone::<FootR>("Foot.R").with_ik("Thigh.R", Limb::Leg { knee: "Leg.R" }),
+                vec![tree(bone::<ToesR>("Toes.R").optional(), vec![])],
+            )],
+        )],
+    );
+    let neck = tree(
+        bone::<Neck>("Neck"),
+        vec![tree(
+            bone::<Head>("Head").with_ik("Neck", Limb::Head),
+            vec![],
+        )],
+    );
+    tree(
+        bone::<Hips>("Hips").with_target(),
+        vec![
+            leg_l,
+            leg_r,
+            tree(
+                bone::<Spine>("Spine"),
+                vec![tree(
+                    bone::<Chest>("Chest"),
+                    vec![tree(
+                        bone::<UpperChest>("UpperChest").optional(),
+                        vec![neck, arm_l, arm_r],
+                    )],
+                )],
+            ),
+        ],
+    )
 });
 
 trait BoneApplier {
+    fn name(&self) -> &'static str;
+
+    fn optional(&self) -> bool;
+
     fn apply(
         &self,
         avatar_id: Entity,
         armature_id: Entity,
-        parent_id: Entity,
+        starts: &[Entity],
+        mapping: Option<&RigMapping>,
+        report: &mut RigReport,
         world: &mut DeferredWorld,
     ) -> Option<Entity>;
 }
@@ -220,78 +311,322 @@ struct BonePair<T: Bones + 'static> {
     name: &'static str,
     compo: Bone<T>,
     target: Option<Target<T>>,
+    /// Highest bone the IK moves to reach the target, and the limb.
+    ik: Option<(&'static str, Limb)>,
+    /// Missing optional bones are skipped without breaking the chain.
+    optional: bool,
+}
+
+impl<T: Bones> BonePair<T> {
+    fn with_target(mut self) -> Self {
+        self.target = Some(Target::<T>::default());
+        self
+    }
+
+    fn with_ik(mut self, root: &'static str, limb: Limb) -> Self {
+        self.ik = Some((root, limb));
+        self.with_target()
+    }
+
+    fn optional(mut self) -> Self {
+        self.optional = true;
+        self
+    }
 }
 
 impl<T: Bones> BoneApplier for BonePair<T> {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn optional(&self) -> bool {
+        self.optional
+    }
+
     fn apply(
         &self,
         avatar_id: Entity,
         armature_id: Entity,
-        parent_id: Entity,
+        starts: &[Entity],
+        mapping: Option<&RigMapping>,
+        report: &mut RigReport,
         world: &mut DeferredWorld,
     ) -> Option<Entity> {
-        if let Some(found_id) = find_by_name_in_childs(&self.name.into(), parent_id, world) {
-            let tf = world.get::<GlobalTransform>(found_id)?;
-            let pos = tf.translation();
-            let mut cmds = world.commands();
-            cmds.entity(found_id).insert(self.compo.clone());
-            cmds.entity(avatar_id)
-                .insert(ComponentEntityRef::<Bone<T>>::new(found_id));
-            if let Some(target) = self.target.as_ref() {
-                let etid = cmds
-                    .spawn((
-                        SpatialBundle {
-                            transform: Transform {
-                                translation: pos,
-                                ..default()
-                            },
+        let found = starts
+            .iter()
+            .find_map(|start| find_bone(self.name, mapping, *start, world))?;
+        let found_id = found.entity;
+        report.mapped.push(found);
+        let tf = world.get::<GlobalTransform>(found_id)?;
+        let pos = tf.translation();
+        let config = world
+            .get::<AvatarGeneric>(avatar_id)
+            .cloned()
+            .unwrap_or_default();
+        // Bones in between, like twist bones, are part of the chain too.
+        let chain_length = self.ik.and_then(|(root, limb)| {
+            let configured = match limb {
+                Limb::Head => None,
+                Limb::Arm { .. } => config.arm_chain_length,
+                Limb::Leg { .. } => config.leg_chain_length,
+            };
+            let root = report.get(root)?;
+            configured.or_else(|| chain_length(found_id, root.entity, world))
+        });
+        let pole = self
+            .ik
+            .and_then(|(_, limb)| pole_at_rest(avatar_id, pos, limb, report, world));
+        let limit = self.ik.and_then(|(_, limb)| {
+            let (middle, max_angle, max_twist) = match limb {
+                Limb::Head => return None,
+                Limb::Arm { elbow } => (elbow, config.elbow_limit, config.elbow_twist_limit),
+                Limb::Leg { knee } => (knee, config.knee_limit, config.knee_twist_limit),
+            };
+            let middle = report.get(middle)?.entity;
+            let rest = world.get::<Transform>(middle)?.rotation;
+            let (axis, bone) = hinge_at_rest(avatar_id, middle, pos, limb, world)?;
+            Some((
+                middle,
+                JointLimit {
+                    rest,
+                    axis,
+                    bone,
+                    max_angle,
+                    max_twist,
+                },
+            ))
+        });
+        let mut cmds = world.commands();
+        if let Some((middle, limit)) = limit {
+            cmds.entity(middle).insert(limit);
+        }
+        cmds.entity(found_id).insert(self.compo.clone());
+        cmds.entity(avatar_id)
+            .insert(ComponentEntityRef::<Bone<T>>::new(found_id));
+        if let Some(target) = self.target.as_ref() {
+            let etid = cmds
+                .spawn((
+                    SpatialBundle {
+                        transform: Transform {
+                            translation: pos,
                             ..default()
                         },
-                        Name::new(format!("Target:{}", target.name())),
-                        target.clone(),
+                        ..default()
+                    },
+                    Name::new(format!("Target:{}", target.name())),
+                    target.clone(),
+                ))
+                .id();
+            cmds.entity(armature_id).add_child(etid);
+            cmds.entity(avatar_id)
+                .insert(ComponentEntityRef::<Target<T>>::new(etid));
+            let pole_target = pole.map(|(parent, translation)| {
+                let pole_id = cmds
+                    .spawn((
+                        SpatialBundle::from_transform(Transform::from_translation(translation)),
+                        Name::new(format!("Pole:{}", target.name())),
+                        Pole::<T>::default(),
+                        PoleRest(translation),
                     ))
                     .id();
-                cmds.entity(armature_id).add_child(etid);
+                cmds.entity(parent).add_child(pole_id);
                 cmds.entity(avatar_id)
-                    .insert(ComponentEntityRef::<Target<T>>::new(etid));
-
-                if TypeId::of::<T>() == TypeId::of::<Hips>() {
-                } else if TypeId::of::<T>() == TypeId::of::<Head>() {
-                    cmds.entity(found_id).insert(IkConstraint {
-                        chain_length: 1,
-                        iterations: 20,
-                        target: etid,
-                        pole_target: None,
-                        pole_angle: 0.0,
-                        enabled: true,
-                    });
-                } else {
-                    cmds.entity(found_id).insert(IkConstraint {
-                        chain_length: 2,
-                        iterations: 20,
-                        target: etid,
-                        pole_target: None,
-                        pole_angle: 0.0,
-                        enabled: true,
-                    });
-                }
+                    .insert(ComponentEntityRef::<Pole<T>>::new(pole_id));
+                pole_id
+            });
+            if let Some(chain_length) = chain_length {
+                cmds.entity(found_id).insert(IkConstraint {
+                    chain_length,
+                    iterations: config.ik_iterations,
+                    target: etid,
+                    pole_target,
+                    pole_angle: 0.0,
+                    enabled: true,
+                });
             }
-            return Some(found_id);
         }
-        warn!("Could not find bone: {}", self.name);
-        None
+        Some(found_id)
     }
 }
 
-fn find_armature_entity_id(entity_id: Entity, world: &DeferredWorld) -> Option<Entity> {
-    let target = "Armature".into();
-    let Some(armature_id) = find_by_name_in_childs(&target, entity_id, world) else {
+#[derive(Clone, Copy, Debug)]
+enum Limb {
+    Head,
+    Arm { elbow: &'static str },
+    Leg { knee: &'static str },
+}
+
+/// Elbows point backwards and knees forwards, as far as the hand or foot
+/// from them. The pole moves with the chest or the hips, returned with its
+/// position relative to them.
+fn pole_at_rest(
+    avatar_id: Entity,
+    end: Vec3,
+    limb: Limb,
+    report: &RigReport,
+    world: &DeferredWorld,
+) -> Option<(Entity, Vec3)> {
+    let (middle, parent) = match limb {
+        Limb::Head => return None,
+        Limb::Arm { elbow } => (elbow, "Chest"),
+        Limb::Leg { knee } => (knee, "Hips"),
+    };
+    let middle = world
+        .get::<GlobalTransform>(report.get(middle)?.entity)?
+        .translation();
+    let position = middle + pole_direction(avatar_id, limb, world)? * middle.distance(end);
+    let parent = report.get(parent)?.entity;
+    let parent_gt = world.get::<GlobalTransform>(parent)?;
+    let local = parent_gt.affine().inverse().transform_point3(position);
+    Some((parent, local))
+}
+
+/// Where the pole of a limb is, backwards for the elbows and forwards for
+/// the knees.
+fn pole_direction(avatar_id: Entity, limb: Limb, world: &DeferredWorld) -> Option<Vec3> {
+    let direction = match limb {
+        Limb::Head => return None,
+        Limb::Arm { .. } => -1.0,
+        Limb::Leg { .. } => 1.0,
+    };
+    // Avatars face +Z, as the head target turned from the camera.
+    let forward = world
+        .get::<GlobalTransform>(avatar_id)
+        .map(|gt| gt.affine().transform_vector3(Vec3::Z).normalize_or_zero())
+        .unwrap_or(Vec3::Z);
+    Some(forward * direction)
+}
+
+/// Axis the elbow or knee bends around and the direction of the bone below
+/// it, relative to its rest. The limb bends away from its pole.
+fn hinge_at_rest(
+    avatar_id: Entity,
+    middle: Entity,
+    end: Vec3,
+    limb: Limb,
+    world: &DeferredWorld,
+) -> Option<(Vec3, Vec3)> {
+    let to_local = world.get::<GlobalTransform>(middle)?.affine().inverse();
+    let bone = to_local.transform_point3(end).try_normalize()?;
+    let pole = to_local.transform_vector3(pole_direction(avatar_id, limb, world)?);
+    let axis = pole.cross(bone).try_normalize()?;
+    Some((axis, bone))
+}
+
+/// Parents from `bone` up to `root`, `root` included.
+fn chain_length(bone: Entity, root: Entity, world: &DeferredWorld) -> Option<usize> {
+    let mut length = 0;
+    let mut current = bone;
+    while current != root {
+        current = world.get::<Parent>(current)?.get();
+        length += 1;
+    }
+    Some(length)
+}
+
+/// The armature is either found by name or is the parent of the hips.
+fn find_armature_entity_id(
+    entity_id: Entity,
+    mapping: Option<&RigMapping>,
+    report: &mut RigReport,
+    world: &DeferredWorld,
+) -> Option<Entity> {
+    if let Some(found) = find_bone(ARMATURE, mapping, entity_id, world) {
+        let armature_id = found.entity;
+        report.mapped.push(found);
+        return Some(armature_id);
+    }
+    let hips = find_bone("Hips", mapping, entity_id, world)
+        .and_then(|hips| world.get::<Parent>(hips.entity))
+        .map(Parent::get);
+    let Some(armature_id) = hips else {
         warn!("Could not find Armature");
+        report.missing.push(ARMATURE);
         return None;
     };
+    report.mapped.push(MappedBone {
+        bone: ARMATURE,
+        node: node_name(armature_id, world),
+        entity: armature_id,
+        by: MappedBy::Heuristic,
+    });
     Some(armature_id)
 }
 
+/// By the mapping of the avatar, the exact name, the presets and at last
+/// the name that looks the most like the bone.
+fn find_bone(
+    bone: &'static str,
+    mapping: Option<&RigMapping>,
+    start: Entity,
+    world: &DeferredWorld,
+) -> Option<MappedBone> {
+    let by_name = |node: &str, by: MappedBy| {
+        find_by_name_in_childs(&Name::new(node.to_owned()), start, world).map(|entity| MappedBone {
+            bone,
+            node: node.to_owned(),
+            entity,
+            by,
+        })
+    };
+    let mapped = mapping.and_then(|m| {
+        m.node(bone)
+            .and_then(|node| by_name(node, MappedBy::Mapping(m.name.clone())))
+    });
+    if mapped.is_some() {
+        return mapped;
+    }
+    if let Some(found) = by_name(bone, MappedBy::Exact) {
+        return Some(found);
+    }
+    for preset in PRESETS.iter() {
+        let found = preset
+            .node(bone)
+            .and_then(|node| by_name(node, MappedBy::Mapping(preset.name.clone())));
+        if found.is_some() {
+            return found;
+        }
+    }
+    let entity = find_by_heuristic(bone, start, world)?;
+    Some(MappedBone {
+        bone,
+        node: node_name(entity, world),
+        entity,
+        by: MappedBy::Heuristic,
+    })
+}
+
+/// Best ranked name, the closest to `start` among equals.
+fn find_by_heuristic(bone: &str, start: Entity, world: &DeferredWorld) -> Option<Entity> {
+    let mut best: Option<(usize, Entity)> = None;
+    let mut queue = VecDeque::from([start]);
+    while let Some(next) = queue.pop_front() {
+        let Some(childs) = world.entity(next).get::<Children>() else {
+            continue;
+        };
+        for child in childs {
+            queue.push_back(*child);
+            let Some(name) = world.entity(*child).get::<Name>() else {
+                continue;
+            };
+            let Some(rank) = heuristic_rank(bone, name.as_str()) else {
+                continue;
+            };
+            if best.map(|(best, _)| rank < best).unwrap_or(true) {
+                best = Some((rank, *child));
+            }
+        }
+    }
+    best.map(|(_, entity)| entity)
+}
+
+fn node_name(entity: Entity, world: &DeferredWorld) -> String {
+    world
+        .get::<Name>(entity)
+        .map(|n| n.to_string())
+        .unwrap_or_default()
+}
+
 fn find_by_name_in_childs(target: &Name, start: Entity, world: &DeferredWorld) -> Option<Entity> {
     let mut queue = vec![start];
     while !queue.is_empty() {
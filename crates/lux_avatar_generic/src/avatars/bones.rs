@@ -34,12 +34,27 @@ impl<T: Bones> Target<T> {
     }
 }
 
+/// Where the middle joint of the limb reaching `Target<T>` points to.
+#[derive(Default, Clone, Debug, Component)]
+pub struct Pole<T: Bones> {
+    b: PhantomData<T>,
+}
+
+impl<T: Bones> Pole<T> {
+    pub fn name(&self) -> &'static str {
+        T::name()
+    }
+}
+
 bone!(Root);
 bone!(Hips);
 bone!(Spine);
 bone!(Chest);
+bone!(UpperChest);
 bone!(Neck);
 bone!(Head);
+bone!(ShoulderL);
+bone!(ShoulderR);
 bone!(ArmL);
 bone!(ArmR);
 bone!(ForearmL);
@@ -52,3 +67,35 @@ bone!(LegL);
 bone!(LegR);
 bone!(FootL);
 bone!(FootR);
+bone!(ToesL);
+bone!(ToesR);
+bone!(ThumbProximalL);
+bone!(ThumbIntermediateL);
+bone!(ThumbDistalL);
+bone!(IndexProximalL);
+bone!(IndexIntermediateL);
+bone!(IndexDistalL);
+bone!(MiddleProximalL);
+bone!(MiddleIntermediateL);
+bone!(MiddleDistalL);
+bone!(RingProximalL);
+bone!(RingIntermediateL);
+bone!(RingDistalL);
+bone!(LittleProximalL);
+bone!(LittleIntermediateL);
+bone!(LittleDistalL);
+bone!(ThumbProximalR);
+bone!(ThumbIntermediateR);
+bone!(ThumbDistalR);
+bone!(IndexProximalR);
+bone!(IndexIntermediateR);
+bone!(IndexDistalR);
+bone!(MiddleProximalR);
+bone!(MiddleIntermediateR);
+bone!(MiddleDistalR);
+bone!(RingProximalR);
+bone!(RingIntermediateR);
+bone!(RingDistalR);
+bone!(LittleProximalR);
+bone!(LittleIntermediateR);
+bone!(LittleDistalR);
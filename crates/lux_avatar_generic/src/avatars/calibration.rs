@@ -0,0 +1,330 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use bevy::{prelude::*, transform::TransformSystem};
+use lux_components::{ComponentEntityRef, LocalUser};
+use serde_json::{json, Map, Value};
+
+use crate::avatars::{bones::*, AvatarGeneric};
+
+/// File the calibrations of the avatars are kept in, see calibrations_path.
+pub const CALIBRATIONS_FILE: &str = "calibrations.json";
+/// Height of the eyes as part of the full height of a person.
+pub const EYE_HEIGHT_RATIO: f32 = 0.94;
+
+/// Sizes of the real user, taken from a T-pose or given by hand.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct UserMeasures {
+    /// Height of the eyes above the floor.
+    pub eye_height: f32,
+    /// From palm to palm with the arms open, when known.
+    pub arm_span: Option<f32>,
+}
+
+impl UserMeasures {
+    /// From the full height of the user, the arms are taken as proportioned
+    /// as the avatar ones.
+    pub fn from_height(height: f32) -> Self {
+        Self {
+            eye_height: height * EYE_HEIGHT_RATIO,
+            arm_span: None,
+        }
+    }
+}
+
+/// File the avatar was loaded from, the calibration is stored by it.
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct AvatarFile(pub String);
+
+/// How much the avatar is scaled and how much further its arms reach than
+/// the user ones, after scaling.
+#[derive(Component, Reflect, Debug, Clone, Copy, PartialEq)]
+#[reflect(Component)]
+pub struct Calibration {
+    pub scale: f32,
+    pub arm_ratio: f32,
+}
+
+impl Default for Calibration {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            arm_ratio: 1.0,
+        }
+    }
+}
+
+impl Calibration {
+    /// None when the avatar has not been measured yet.
+    pub fn new(avatar: &AvatarGeneric, user: &UserMeasures) -> Option<Self> {
+        if avatar.height() <= 0.0 || user.eye_height <= 0.0 {
+            return None;
+        }
+        let scale = user.eye_height / avatar.height();
+        let arm_ratio = match user.arm_span {
+            Some(span) if avatar.arm_span() > 0.0 => avatar.arm_span() * scale / span,
+            _ => 1.0,
+        };
+        Some(Self { scale, arm_ratio })
+    }
+
+    /// The hand target moved so that the user fully stretched is the avatar
+    /// fully stretched.
+    pub fn reach(&self, shoulder: Vec3, hand: Vec3) -> Vec3 {
+        shoulder + (hand - shoulder) * self.arm_ratio
+    }
+}
+
+/// Calibrations by avatar file, saved at every change.
+#[derive(Resource, Debug, Default, Clone, PartialEq)]
+pub struct Calibrations {
+    pub path: Option<PathBuf>,
+    pub avatars: HashMap<String, Calibration>,
+}
+
+impl Calibrations {
+    /// Missing or broken files start with no calibrations.
+    pub fn load(path: &Path) -> Self {
+        let avatars = match fs::read_to_string(path) {
+            Ok(text) => parse_calibrations(&text).unwrap_or_else(|e| {
+                warn!("Ignoring calibrations in {}: {}", path.display(), e);
+                HashMap::new()
+            }),
+            Err(_) => HashMap::new(),
+        };
+        Self {
+            path: Some(path.to_owned()),
+            avatars,
+        }
+    }
+
+    fn save(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let mut avatars = Map::new();
+        for (file, calibration) in self.avatars.iter() {
+            avatars.insert(
+                file.clone(),
+                json!({"scale": calibration.scale, "arm_ratio": calibration.arm_ratio}),
+            );
+        }
+        let text = serde_json::to_string_pretty(&Value::Object(avatars)).unwrap_or_default();
+        let written = match path.parent() {
+            Some(dir) => fs::create_dir_all(dir).and_then(|_| fs::write(path, text)),
+            None => fs::write(path, text),
+        };
+        if let Err(e) = written {
+            warn!("Cannot save calibrations to {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// In the lux folder of the user configuration, None when the system has
+/// no such folder.
+pub fn calibrations_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("lux").join(CALIBRATIONS_FILE))
+}
+
+fn parse_calibrations(text: &str) -> Result<HashMap<String, Calibration>, String> {
+    let value: Value = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    let Some(avatars) = value.as_object() else {
+        return Err("expected an object by avatar file".to_owned());
+    };
+    let mut calibrations = HashMap::new();
+    for (file, calibration) in avatars {
+        let field = |name| calibration.get(name).and_then(Value::as_f64);
+        let (Some(scale), Some(arm_ratio)) = (field("scale"), field("arm_ratio")) else {
+            return Err(format!("incomplete calibration for {}", file));
+        };
+        calibrations.insert(
+            file.clone(),
+            Calibration {
+                scale: scale as f32,
+                arm_ratio: arm_ratio as f32,
+            },
+        );
+    }
+    Ok(calibrations)
+}
+
+pub(crate) fn init(app: &mut App) {
+    let calibrations = match calibrations_path() {
+        Some(path) => Calibrations::load(&path),
+        None => {
+            warn!("No configuration folder, calibrations are not saved");
+            Calibrations::default()
+        }
+    };
+    app.insert_resource(calibrations);
+    app.add_systems(Update, (calibrate, load_calibration, scale_avatar).chain());
+    // After the trackers wrote the targets, before the IK reads them.
+    app.add_systems(
+        PostUpdate,
+        (reach::<HandL, ArmL>, reach::<HandR, ArmR>).before(TransformSystem::TransformPropagate),
+    );
+}
+
+fn calibrate(
+    mut cmd: Commands,
+    mut calibrations: ResMut<Calibrations>,
+    avatars: Query<
+        (
+            Entity,
+            &AvatarGeneric,
+            Ref<UserMeasures>,
+            Option<&AvatarFile>,
+        ),
+        Without<Calibration>,
+    >,
+    mut calibrated: Query<(
+        &AvatarGeneric,
+        Ref<UserMeasures>,
+        Option<&AvatarFile>,
+        &mut Calibration,
+    )>,
+) {
+    let mut store = |file: Option<&AvatarFile>, calibration: Calibration| {
+        info!(
+            "Avatar scaled by {:.2}, arms reach {:.2}",
+            calibration.scale, calibration.arm_ratio
+        );
+        if let Some(file) = file {
+            calibrations.avatars.insert(file.0.clone(), calibration);
+            calibrations.save();
+        }
+    };
+    for (avatar, av, user, file) in avatars.iter() {
+        if let Some(calibration) = Calibration::new(av, &user) {
+            store(file, calibration);
+            cmd.entity(avatar).try_insert(calibration);
+        }
+    }
+    for (av, user, file, mut calibration) in calibrated.iter_mut() {
+        if !user.is_changed() {
+            continue;
+        }
+        if let Some(next) = Calibration::new(av, &user) {
+            if calibration.set_if_neq(next) {
+                store(file, next);
+            }
+        }
+    }
+}
+
+/// Avatars without measures of the user take the ones stored for the file.
+fn load_calibration(
+    mut cmd: Commands,
+    calibrations: Res<Calibrations>,
+    avatars: Query<(Entity, &AvatarFile), (Without<Calibration>, Without<UserMeasures>)>,
+) {
+    for (avatar, file) in avatars.iter() {
+        if let Some(calibration) = calibrations.avatars.get(&file.0) {
+            cmd.entity(avatar).try_insert(*calibration);
+        }
+    }
+}
+
+fn scale_avatar(mut avatars: Query<(&Calibration, &mut Transform), Changed<Calibration>>) {
+    for (calibration, mut transform) in avatars.iter_mut() {
+        transform.scale = Vec3::splat(calibration.scale);
+    }
+}
+
+/// Only targets written this frame are moved, so that the reach is not
+/// applied again on the ones nobody tracks.
+fn reach<Hand: Bones + 'static, Arm: Bones + 'static>(
+    avatars: Query<
+        (
+            &Calibration,
+            &ComponentEntityRef<Bone<Arm>>,
+            &ComponentEntityRef<Target<Hand>>,
+        ),
+        With<LocalUser>,
+    >,
+    globals: Query<&GlobalTransform>,
+    mut targets: Query<(&mut Transform, &Parent), (With<Target<Hand>>, Changed<Transform>)>,
+) {
+    for (calibration, arm, target) in avatars.iter() {
+        if calibration.arm_ratio == 1.0 {
+            continue;
+        }
+        let Ok((mut transform, parent)) = targets.get_mut(target.entity_id) else {
+            continue;
+        };
+        let (Ok(parent), Ok(shoulder)) = (globals.get(parent.get()), globals.get(arm.entity_id))
+        else {
+            continue;
+        };
+        let shoulder = parent
+            .affine()
+            .inverse()
+            .transform_point3(shoulder.translation());
+        let transform = transform.bypass_change_detection();
+        transform.translation = calibration.reach(shoulder, transform.translation);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_calibration() {
+        let avatar = AvatarGeneric {
+            height: 1.5,
+            arm_span: 1.5,
+            ..default()
+        };
+        assert_eq!(
+            Calibration::new(&AvatarGeneric::default(), &UserMeasures::from_height(1.8)),
+            None
+        );
+
+        let calibration = Calibration::new(
+            &avatar,
+            &UserMeasures {
+                eye_height: 1.8,
+                arm_span: Some(2.0),
+            },
+        )
+        .unwrap();
+        assert!((calibration.scale - 1.2).abs() < 1e-5);
+        // Scaled arms span 1.8, the user ones 2.0.
+        assert!((calibration.arm_ratio - 0.9).abs() < 1e-5);
+
+        let reach = calibration.reach(Vec3::new(0.2, 1.4, 0.0), Vec3::new(1.2, 1.4, 0.0));
+        assert!(reach.abs_diff_eq(Vec3::new(1.1, 1.4, 0.0), 1e-5));
+
+        let calibration =
+            Calibration::new(&avatar, &UserMeasures::from_height(1.5 / EYE_HEIGHT_RATIO)).unwrap();
+        assert!((calibration.scale - 1.0).abs() < 1e-5);
+        assert_eq!(calibration.arm_ratio, 1.0);
+    }
+
+    #[test]
+    fn test_calibrations_file() {
+        let dir = std::env::temp_dir().join(format!("lux_calibrations_{}", std::process::id()));
+        let path = dir.join("lux").join(CALIBRATIONS_FILE);
+        let mut calibrations = Calibrations::load(&path);
+        assert!(calibrations.avatars.is_empty());
+        let calibration = Calibration {
+            scale: 1.25,
+            arm_ratio: 0.5,
+        };
+        calibrations
+            .avatars
+            .insert("avatars/fox.glb".to_owned(), calibration);
+        calibrations.save();
+
+        let loaded = Calibrations::load(&path);
+        assert_eq!(loaded.avatars.get("avatars/fox.glb"), Some(&calibration));
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(parse_calibrations(r#"{"a.glb": {"scale": 1.0}}"#).is_err());
+        assert!(parse_calibrations("[]").is_err());
+    }
+}
@@ -0,0 +1,198 @@
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::prelude::*;
+use bevy_sync::SyncComponent;
+
+use crate::avatars::rig_mapping::{RigReport, FINGERS, FINGER_JOINTS};
+
+/// Bones of the fingers of one hand, in the order of `FINGERS` and `FINGER_JOINTS`.
+pub const FINGER_BONES: usize = FINGERS.len() * FINGER_JOINTS.len();
+
+/// Rotation of each finger bone from its rest, synced so that the others
+/// see the finger poses too.
+#[derive(Component, Reflect, Default, Debug, Clone, PartialEq)]
+#[reflect(Component)]
+pub struct HandPose {
+    pub left: [Quat; FINGER_BONES],
+    pub right: [Quat; FINGER_BONES],
+}
+
+/// Tracked joint rotations of one hand, all in the same space, with the
+/// frames of OpenXR: -Z along the bone and +Y on the back of the hand.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TrackedHand {
+    /// Metacarpal, proximal and distal.
+    pub thumb: [Quat; 3],
+    /// Index, middle, ring and little, each metacarpal, proximal,
+    /// intermediate and distal.
+    pub fingers: [[Quat; 4]; 4],
+}
+
+impl TrackedHand {
+    /// Rotations for the avatar finger bones, the tracked joints bend their
+    /// children so each bone takes the rotation of its parent joint to itself.
+    /// The thumb metacarpal is left at rest, its rest differs too much between
+    /// tracking and rigs.
+    pub fn retarget(&self) -> [Quat; FINGER_BONES] {
+        let mut bones = [Quat::IDENTITY; FINGER_BONES];
+        let joints = FINGER_JOINTS.len();
+        bones[1] = to_bone_frame(self.thumb[0], self.thumb[1]);
+        bones[2] = to_bone_frame(self.thumb[1], self.thumb[2]);
+        for (finger, chain) in self.fingers.iter().enumerate() {
+            for joint in 0..joints {
+                bones[(finger + 1) * joints + joint] =
+                    to_bone_frame(chain[joint], chain[joint + 1]);
+            }
+        }
+        bones
+    }
+}
+
+/// Rotation of `joint` from `parent`, moved from the tracking frame to the
+/// one of glTF bones, which point along +Y with +Z on the back of the hand.
+fn to_bone_frame(parent: Quat, joint: Quat) -> Quat {
+    let basis = Quat::from_rotation_x(FRAC_PI_2);
+    (basis * (parent.inverse() * joint) * basis.inverse()).normalize()
+}
+
+/// Rest rotations of the finger bones an avatar has.
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct FingerRest {
+    pub left: [Option<(Entity, Quat)>; FINGER_BONES],
+    pub right: [Option<(Entity, Quat)>; FINGER_BONES],
+}
+
+pub(crate) fn init(app: &mut App) {
+    app.sync_component::<HandPose>();
+    app.add_systems(Update, (store_finger_rest, apply_hand_pose).chain());
+}
+
+fn finger_bone_names(side: &str) -> impl Iterator<Item = String> + '_ {
+    FINGERS.iter().flat_map(move |finger| {
+        FINGER_JOINTS
+            .iter()
+            .map(move |joint| format!("{}{}.{}", finger, joint, side))
+    })
+}
+
+fn store_finger_rest(
+    mut commands: Commands,
+    avatars: Query<(Entity, &RigReport), Without<FingerRest>>,
+    transforms: Query<&Transform>,
+) {
+    for (avatar, report) in avatars.iter() {
+        let rest = |side| {
+            let mut rest = [None; FINGER_BONES];
+            for (bone, name) in finger_bone_names(side).enumerate() {
+                rest[bone] = report
+                    .get(&name)
+                    .and_then(|m| Some((m.entity, transforms.get(m.entity).ok()?.rotation)));
+            }
+            rest
+        };
+        commands.entity(avatar).try_insert(FingerRest {
+            left: rest("L"),
+            right: rest("R"),
+        });
+    }
+}
+
+fn apply_hand_pose(
+    avatars: Query<(&HandPose, &FingerRest), Or<(Changed<HandPose>, Added<FingerRest>)>>,
+    mut transforms: Query<&mut Transform>,
+) {
+    for (pose, rest) in avatars.iter() {
+        let bones = rest
+            .left
+            .iter()
+            .zip(pose.left.iter())
+            .chain(rest.right.iter().zip(pose.right.iter()));
+        for (rest, rotation) in bones {
+            let Some((entity, rest)) = rest else {
+                continue;
+            };
+            if let Ok(mut transform) = transforms.get_mut(*entity) {
+                transform.rotation = *rest * *rotation;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::avatars::rig_mapping::{MappedBone, MappedBy};
+
+    #[test]
+    fn test_open_hand_is_rest() {
+        let hand = Quat::from_euler(EulerRot::YXZ, 0.3, -1.2, 0.7);
+        let tracked = TrackedHand {
+            thumb: [hand; 3],
+            fingers: [[hand; 4]; 4],
+        };
+        for rotation in tracked.retarget() {
+            assert!(rotation.abs_diff_eq(Quat::IDENTITY, 1e-5), "{}", rotation);
+        }
+    }
+
+    #[test]
+    fn test_curl_and_splay() {
+        let hand = Quat::from_euler(EulerRot::YXZ, 1.0, 0.4, -0.2);
+        let curl = Quat::from_rotation_x(-0.5);
+        let splay = Quat::from_rotation_y(0.2);
+        let mut tracked = TrackedHand {
+            thumb: [hand; 3],
+            fingers: [[hand; 4]; 4],
+        };
+        // Index curled at each joint, middle spread at the knuckle.
+        tracked.fingers[0] = [
+            hand,
+            hand * curl,
+            hand * curl * curl,
+            hand * curl * curl * curl,
+        ];
+        tracked.fingers[1] = [hand, hand * splay, hand * splay, hand * splay];
+        tracked.thumb = [hand, hand * curl, hand * curl];
+
+        let bones = tracked.retarget();
+        let index = 3;
+        let middle = 6;
+        for joint in 0..3 {
+            // Curling stays around X, splaying moves from Y to Z.
+            assert!(bones[index + joint].abs_diff_eq(Quat::from_rotation_x(-0.5), 1e-5));
+        }
+        assert!(bones[middle].abs_diff_eq(Quat::from_rotation_z(0.2), 1e-5));
+        assert!(bones[middle + 1].abs_diff_eq(Quat::IDENTITY, 1e-5));
+        assert_eq!(bones[0], Quat::IDENTITY);
+        assert!(bones[1].abs_diff_eq(Quat::from_rotation_x(-0.5), 1e-5));
+        assert!(bones[2].abs_diff_eq(Quat::IDENTITY, 1e-5));
+    }
+
+    #[test]
+    fn test_pose_applied_from_rest() {
+        let mut app = App::new();
+        init(&mut app);
+        let rest = Quat::from_rotation_z(0.3);
+        let bone = app.world_mut().spawn(Transform::from_rotation(rest)).id();
+        let report = RigReport {
+            mapped: vec![MappedBone {
+                bone: "IndexIntermediate.L",
+                node: "index_02_l".to_owned(),
+                entity: bone,
+                by: MappedBy::Heuristic,
+            }],
+            ..default()
+        };
+        let avatar = app.world_mut().spawn(report).id();
+        app.update();
+
+        let curl = Quat::from_rotation_x(-0.8);
+        let mut pose = HandPose::default();
+        pose.left[4] = curl;
+        app.world_mut().entity_mut(avatar).insert(pose);
+        app.update();
+
+        let rotation = app.world().get::<Transform>(bone).unwrap().rotation;
+        assert!(rotation.abs_diff_eq(rest * curl, 1e-5));
+    }
+}
@@ -0,0 +1,322 @@
+use std::f32::consts::FRAC_PI_4;
+
+use bevy::prelude::*;
+use lux_components::ComponentEntityRef;
+
+use crate::avatars::bones::*;
+
+/// A hinge, like an elbow or a knee, applied after the IK solved the limb.
+/// It bends one way around `axis` and twists a little around `bone`, both
+/// relative to its rest.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct JointLimit {
+    pub rest: Quat,
+    pub axis: Vec3,
+    /// Direction of the bone below the joint.
+    pub bone: Vec3,
+    pub max_angle: f32,
+    pub max_twist: f32,
+}
+
+impl JointLimit {
+    /// `rotation` bent only around the axis, towards positive angles, with
+    /// the bend and the twist clamped.
+    pub fn limit(&self, rotation: Quat) -> Quat {
+        let turn = self.rest.inverse() * rotation;
+        let twist = twist_angle(turn, self.bone);
+        let swing = turn * Quat::from_axis_angle(self.bone, twist).inverse();
+        let swung = swing * self.bone;
+        let bend = self
+            .axis
+            .dot(self.bone.cross(swung))
+            .atan2(self.bone.dot(swung));
+        let limited = Quat::from_axis_angle(self.axis, bend.clamp(0.0, self.max_angle))
+            * Quat::from_axis_angle(self.bone, twist.clamp(-self.max_twist, self.max_twist));
+        (self.rest * limited).normalize()
+    }
+}
+
+/// Position of a pole at rest, relative to its parent.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct PoleRest(pub Vec3);
+
+pub(crate) fn init(app: &mut App) {
+    app.add_systems(
+        Update,
+        (
+            turn_elbow_pole::<HandL, ArmL>,
+            turn_elbow_pole::<HandR, ArmR>,
+        ),
+    );
+    // The solver writes the transforms and globals after they are
+    // propagated in PostUpdate, the limits go on top of it before they are
+    // extracted for rendering.
+    app.add_systems(Last, (limit_joints, propagate_limits).chain());
+}
+
+/// Angle `rotation` turns around `axis`, without the swing away from it.
+pub fn twist_angle(rotation: Quat, axis: Vec3) -> f32 {
+    let projected = axis * Vec3::new(rotation.x, rotation.y, rotation.z).dot(axis);
+    let twist = Quat::from_xyzw(projected.x, projected.y, projected.z, rotation.w);
+    if twist.length_squared() < f32::EPSILON {
+        return 0.0;
+    }
+    let (twist_axis, angle) = twist.normalize().to_axis_angle();
+    let angle = if angle > std::f32::consts::PI {
+        angle - std::f32::consts::TAU
+    } else {
+        angle
+    };
+    angle * twist_axis.dot(axis).signum()
+}
+
+fn limit_joints(mut joints: Query<(&mut Transform, &JointLimit)>) {
+    for (mut transform, limit) in joints.iter_mut() {
+        let limited = limit.limit(transform.rotation);
+        if !limited.abs_diff_eq(transform.rotation, 1e-6) {
+            transform.rotation = limited;
+        }
+    }
+}
+
+/// Globals of the joints and the bones below them, the propagation of this
+/// frame already ran.
+fn propagate_limits(
+    joints: Query<(Entity, Option<&Parent>), With<JointLimit>>,
+    transforms: Query<(&Transform, Option<&Children>)>,
+    mut globals: Query<&mut GlobalTransform>,
+) {
+    for (joint, parent) in joints.iter() {
+        let parent = parent
+            .and_then(|p| globals.get(p.get()).ok().copied())
+            .unwrap_or_default();
+        propagate(joint, parent, &transforms, &mut globals);
+    }
+}
+
+fn propagate(
+    entity: Entity,
+    parent: GlobalTransform,
+    transforms: &Query<(&Transform, Option<&Children>)>,
+    globals: &mut Query<&mut GlobalTransform>,
+) {
+    let Ok((transform, children)) = transforms.get(entity) else {
+        return;
+    };
+    let global = parent * *transform;
+    if let Ok(mut existing) = globals.get_mut(entity) {
+        existing.set_if_neq(global);
+    }
+    for child in children.into_iter().flatten() {
+        propagate(*child, global, transforms, globals);
+    }
+}
+
+/// The elbow follows half the turn of the hand around the arm, palms down
+/// open it outwards and palms up close it to the body.
+#[allow(clippy::type_complexity)]
+fn turn_elbow_pole<Hand: Bones + 'static, Arm: Bones + 'static>(
+    avatars: Query<(
+        &ComponentEntityRef<Pole<Hand>>,
+        &ComponentEntityRef<Bone<Arm>>,
+        &ComponentEntityRef<Target<Hand>>,
+    )>,
+    globals: Query<&GlobalTransform>,
+    targets: Query<&Transform, (With<Target<Hand>>, Without<Pole<Hand>>)>,
+    mut poles: Query<(&mut Transform, &PoleRest, &Parent), With<Pole<Hand>>>,
+) {
+    for (pole, arm, target) in avatars.iter() {
+        let Ok((mut transform, rest, parent)) = poles.get_mut(pole.entity_id) else {
+            continue;
+        };
+        let (Ok(parent), Ok(shoulder), Ok(hand), Ok(hand_local)) = (
+            globals.get(parent.get()),
+            globals.get(arm.entity_id),
+            globals.get(target.entity_id),
+            targets.get(target.entity_id),
+        ) else {
+            continue;
+        };
+        let shoulder = shoulder.translation();
+        let Some(axis) = (hand.translation() - shoulder).try_normalize() else {
+            continue;
+        };
+        // Turn of the target from its rest, which is the rotation of its parent.
+        let armature = hand.compute_transform().rotation * hand_local.rotation.inverse();
+        let turn = armature * hand_local.rotation * armature.inverse();
+        let angle = (twist_angle(turn, axis) * 0.5).clamp(-FRAC_PI_4, FRAC_PI_4);
+        let at_rest = parent.transform_point(rest.0);
+        let turned = shoulder + Quat::from_axis_angle(axis, angle) * (at_rest - shoulder);
+        let local = parent.affine().inverse().transform_point3(turned);
+        if transform.translation != local {
+            transform.translation = local;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bevy_mod_inverse_kinematics::{IkConstraint, InverseKinematicsPlugin};
+
+    #[test]
+    fn test_hinge_limit() {
+        let limit = hinge(Quat::from_rotation_y(0.3));
+        let bent = limit.rest * Quat::from_rotation_z(1.0);
+        assert!(limit.limit(bent).abs_diff_eq(bent, 1e-5));
+
+        let over = limit.rest * Quat::from_rotation_z(2.5);
+        let limited = limit.limit(over);
+        assert!(limited.abs_diff_eq(limit.rest * Quat::from_rotation_z(1.5), 1e-5));
+
+        // Bending backwards is straightened.
+        let hyperextended = limit.rest * Quat::from_rotation_z(-0.5);
+        assert!(limit.limit(hyperextended).abs_diff_eq(limit.rest, 1e-5));
+
+        // Turning sideways is not a bend.
+        let sideways = limit.rest * Quat::from_rotation_x(0.5);
+        assert!(limit.limit(sideways).abs_diff_eq(limit.rest, 1e-5));
+    }
+
+    #[test]
+    fn test_hinge_twist_limit() {
+        let limit = hinge(Quat::IDENTITY);
+        let twisted = Quat::from_rotation_z(1.0) * Quat::from_rotation_y(0.1);
+        assert!(limit.limit(twisted).abs_diff_eq(twisted, 1e-5));
+
+        let over = Quat::from_rotation_z(1.0) * Quat::from_rotation_y(-1.0);
+        let limited = limit.limit(over);
+        let expected = Quat::from_rotation_z(1.0) * Quat::from_rotation_y(-0.2);
+        assert!(limited.abs_diff_eq(expected, 1e-5));
+    }
+
+    #[test]
+    fn test_twist_angle() {
+        let axis = Vec3::new(1.0, 0.0, 0.0);
+        let twist = Quat::from_axis_angle(axis, 0.6);
+        let swing = Quat::from_rotation_y(0.4);
+        assert!((twist_angle(twist, axis) - 0.6).abs() < 1e-5);
+        assert!((twist_angle(twist.inverse(), axis) + 0.6).abs() < 1e-5);
+        assert!(twist_angle(swing, axis).abs() < 1e-5);
+        assert!((twist_angle(swing * twist, axis) - 0.6).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_joints_limited() {
+        let mut app = App::new();
+        init(&mut app);
+        let joint = app
+            .world_mut()
+            .spawn((
+                Transform::from_rotation(Quat::from_rotation_z(2.0)),
+                JointLimit {
+                    max_angle: 1.0,
+                    ..hinge(Quat::IDENTITY)
+                },
+            ))
+            .id();
+        app.update();
+
+        let rotation = app.world().get::<Transform>(joint).unwrap().rotation;
+        assert!(rotation.abs_diff_eq(Quat::from_rotation_z(1.0), 1e-5));
+    }
+
+    #[test]
+    fn test_solved_joints_limited_before_shown() {
+        let mut app = App::new();
+        app.add_plugins((TransformPlugin, HierarchyPlugin, InverseKinematicsPlugin));
+        init(&mut app);
+        // The target is next to the shoulder, reaching it folds the elbow.
+        let target = app
+            .world_mut()
+            .spawn(TransformBundle::from_transform(Transform::from_xyz(
+                0.05, -0.1, 0.0,
+            )))
+            .id();
+        let (arm, forearm, hand) = limb(&mut app, target, Quat::from_rotation_z(2.5));
+        for _ in 0..3 {
+            app.update();
+
+            let local = *app.world().get::<Transform>(forearm).unwrap();
+            assert!(local.rotation.angle_between(Quat::IDENTITY) <= 1.5 + 1e-4);
+            assert_rendered_limited(&app, arm, forearm, hand);
+        }
+    }
+
+    #[test]
+    fn test_hyperextended_solve_is_clamped() {
+        let mut app = App::new();
+        app.add_plugins((TransformPlugin, HierarchyPlugin, InverseKinematicsPlugin));
+        init(&mut app);
+        // Only reachable by bending the elbow backwards, towards -X.
+        let target = app
+            .world_mut()
+            .spawn(TransformBundle::from_transform(Transform::from_xyz(
+                -0.25, -0.4, 0.0,
+            )))
+            .id();
+        let (arm, forearm, hand) = limb(&mut app, target, Quat::from_rotation_z(-0.3));
+        for _ in 0..3 {
+            app.update();
+
+            let local = *app.world().get::<Transform>(forearm).unwrap();
+            let bone = local.rotation * Vec3::NEG_Y;
+            assert!(bone.x >= -1e-4, "elbow bent backwards: {:?}", bone);
+            assert_rendered_limited(&app, arm, forearm, hand);
+        }
+    }
+
+    /// An elbow bending around Z, moving the forearm from -Y to +X.
+    fn hinge(rest: Quat) -> JointLimit {
+        JointLimit {
+            rest,
+            axis: Vec3::Z,
+            bone: Vec3::NEG_Y,
+            max_angle: 1.5,
+            max_twist: 0.2,
+        }
+    }
+
+    /// Arm, forearm and a hand reaching for `target`.
+    fn limb(app: &mut App, target: Entity, elbow: Quat) -> (Entity, Entity, Entity) {
+        let arm = app.world_mut().spawn(TransformBundle::default()).id();
+        let forearm = app
+            .world_mut()
+            .spawn((
+                TransformBundle::from_transform(Transform {
+                    translation: Vec3::new(0.0, -0.3, 0.0),
+                    rotation: elbow,
+                    ..default()
+                }),
+                hinge(Quat::IDENTITY),
+            ))
+            .set_parent(arm)
+            .id();
+        let hand = app
+            .world_mut()
+            .spawn((
+                TransformBundle::from_transform(Transform::from_xyz(0.0, -0.3, 0.0)),
+                IkConstraint {
+                    chain_length: 2,
+                    iterations: 20,
+                    target,
+                    pole_target: None,
+                    pole_angle: 0.0,
+                    enabled: true,
+                },
+            ))
+            .set_parent(forearm)
+            .id();
+        (arm, forearm, hand)
+    }
+
+    /// What is rendered matches the limited joint.
+    fn assert_rendered_limited(app: &App, arm: Entity, forearm: Entity, hand: Entity) {
+        let local = *app.world().get::<Transform>(forearm).unwrap();
+        let global = |e| *app.world().get::<GlobalTransform>(e).unwrap();
+        let expected = global(arm) * local * *app.world().get::<Transform>(hand).unwrap();
+        assert!(global(hand)
+            .translation()
+            .abs_diff_eq(expected.translation(), 1e-4));
+    }
+}
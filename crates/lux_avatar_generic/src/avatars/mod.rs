@@ -1,6 +1,11 @@
 #![allow(clippy::type_complexity)]
 
+pub mod body;
 pub mod bones;
+pub mod calibration;
+pub mod fingers;
+pub mod ik;
+pub mod rig_mapping;
 
 mod bone_assigner;
 
@@ -11,6 +16,7 @@ use bevy::{
     prelude::*,
 };
 use bevy_sync::SyncComponent;
+use body::BodySettings;
 use bones::{Bone, FootL, FootR, HandL, HandR, Head, Hips, Root, Target};
 
 use lux_components::{ComponentEntityRef, LocalUser};
@@ -22,19 +28,70 @@ impl Plugin for AvatarPlugin {
     fn build(&self, app: &mut App) {
         app.sync_component::<AvatarGeneric>();
         app.add_plugins(InverseKinematicsPlugin);
+        fingers::init(app);
+        ik::init(app);
+        calibration::init(app);
         app.add_systems(Update, local_user_enters);
         app.add_systems(Update, local_user_exits);
         app.add_systems(
             Update,
-            (copy_roation_target_head, orient_hips_to_head).chain(),
+            (copy_roation_target_head, body::estimate_body).chain(),
         );
     }
 }
 
-#[derive(Reflect, Default)]
+#[derive(Reflect, Clone, Debug)]
 #[reflect(Component)]
 pub struct AvatarGeneric {
     distance_hips_to_head: f32,
+    height: f32,
+    arm_span: f32,
+    /// Iterations of the IK solver of each limb.
+    pub ik_iterations: usize,
+    /// Bones the IK moves to reach the hands, by default from hand to arm.
+    pub arm_chain_length: Option<usize>,
+    /// Bones the IK moves to reach the feet, by default from foot to thigh.
+    pub leg_chain_length: Option<usize>,
+    /// Most the elbows bend from rest, in radians, never backwards.
+    pub elbow_limit: f32,
+    /// Most the elbows twist around the forearm, in radians.
+    pub elbow_twist_limit: f32,
+    /// Most the knees bend from rest, in radians, never backwards.
+    pub knee_limit: f32,
+    /// Most the knees twist around the shin, in radians.
+    pub knee_twist_limit: f32,
+    /// How the hips and feet follow the head.
+    pub body: BodySettings,
+}
+
+impl Default for AvatarGeneric {
+    fn default() -> Self {
+        Self {
+            distance_hips_to_head: 0.0,
+            height: 0.0,
+            arm_span: 0.0,
+            ik_iterations: 20,
+            arm_chain_length: None,
+            leg_chain_length: None,
+            elbow_limit: 150f32.to_radians(),
+            elbow_twist_limit: 30f32.to_radians(),
+            knee_limit: 150f32.to_radians(),
+            knee_twist_limit: 15f32.to_radians(),
+            body: BodySettings::default(),
+        }
+    }
+}
+
+impl AvatarGeneric {
+    /// Height of the head above the feet, as authored.
+    pub fn height(&self) -> f32 {
+        self.height
+    }
+
+    /// From hand to hand along the arms, as authored.
+    pub fn arm_span(&self) -> f32 {
+        self.arm_span
+    }
 }
 
 impl Component for AvatarGeneric {
@@ -103,32 +160,6 @@ fn local_user_exits(
     }
 }
 
-fn orient_hips_to_head(
-    av: Query<(
-        &AvatarGeneric,
-        &ComponentEntityRef<Bone<Hips>>,
-        &ComponentEntityRef<Target<Head>>,
-    )>,
-    mut hips_tf: Query<&mut Transform, With<Bone<Hips>>>,
-    head_tf: Query<&Transform, (Without<Bone<Hips>>, With<Target<Head>>)>,
-) {
-    for (av, hips_id, head_id) in av.iter() {
-        if let Ok(head) = head_tf.get(head_id.entity_id) {
-            if let Ok(mut hips) = hips_tf.get_mut(hips_id.entity_id) {
-                let yonly = Quat::from_euler(
-                    EulerRot::YXZ,
-                    head.rotation.to_euler(EulerRot::YXZ).0,
-                    0.0,
-                    0.0,
-                );
-                hips.rotation = yonly;
-                hips.translation = head.translation;
-                hips.translation.y -= av.distance_hips_to_head;
-            }
-        }
-    }
-}
-
 fn copy_roation_target_head(
     mut q: Query<(&mut Transform, &IkConstraint), With<Bone<Head>>>,
     src: Query<&Transform, (Without<Bone<Head>>, With<Target<Head>>)>,
@@ -144,6 +175,7 @@ fn copy_roation_target_head(
 mod test {
     use super::*;
     use bones::*;
+    use rig_mapping::*;
 
     #[test]
     fn test() {
@@ -244,6 +276,329 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_mixamo_rig() {
+        let mut app = app();
+        let mixamo = RigMapping::mixamo();
+        let root = add_rig(&mut app, "Character", |bone| {
+            mixamo.node(bone).unwrap().to_owned()
+        });
+        app.update();
+        app.world_mut()
+            .commands()
+            .entity(root)
+            .try_insert(AvatarGeneric::default());
+        app.update();
+
+        check_bone_name::<Root>(&mut app, "Character");
+        check_bone_name::<Hips>(&mut app, "mixamorig:Hips");
+        check_bone_name::<Chest>(&mut app, "mixamorig:Spine2");
+        check_bone_name::<ForearmL>(&mut app, "mixamorig:LeftForeArm");
+        check_bone_name::<LegR>(&mut app, "mixamorig:RightLeg");
+        check_target_name::<HandR>(&mut app, "mixamorig:RightHand");
+
+        let report = app.world().get::<RigReport>(root).unwrap();
+        assert!(report.missing.is_empty(), "{:?}", report.missing);
+        assert_eq!(report.mapped.len(), 18);
+        assert_eq!(report.get(ARMATURE).unwrap().by, MappedBy::Heuristic);
+        assert_eq!(
+            report.get("Hips").unwrap().by,
+            MappedBy::Mapping("mixamo".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_heuristic_rig_and_report() {
+        let mut app = app();
+        let root = add_rig(&mut app, "Armature", |bone| match bone {
+            "Hips" => "Bip01_Pelvis".to_owned(),
+            "Forearm.L" => "LeftLowerArm".to_owned(),
+            "Head" => "Skull".to_owned(),
+            "Foot.R" => "toes_r".to_owned(),
+            bone => bone.to_owned(),
+        });
+        app.world_mut()
+            .entity_mut(root)
+            .insert(RigMapping::new("custom").with("Head", "Skull"));
+        app.update();
+        app.world_mut()
+            .commands()
+            .entity(root)
+            .try_insert(AvatarGeneric::default());
+        app.update();
+
+        check_bone_name::<Hips>(&mut app, "Bip01_Pelvis");
+        check_bone_name::<ForearmL>(&mut app, "LeftLowerArm");
+        let report = app.world().get::<RigReport>(root).unwrap();
+        assert_eq!(report.missing, vec!["Foot.R"]);
+        assert_eq!(report.get("Hips").unwrap().by, MappedBy::Heuristic);
+        assert_eq!(report.get("Spine").unwrap().by, MappedBy::Exact);
+        assert_eq!(
+            report.get("Head").unwrap().by,
+            MappedBy::Mapping("custom".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_optional_and_extra_bones() {
+        let mut app = app();
+        let root = add_armature(&mut app);
+        app.update();
+        insert_between(&mut app, "Chest", "Neck", "UpperChest");
+        insert_between(&mut app, "Chest", "Arm.L", "Shoulder.L");
+        insert_between(&mut app, "Forearm.L", "Hand.L", "ForearmTwist.L");
+        add_chain(
+            &mut app,
+            "Hand.L",
+            &["IndexProximal.L", "IndexIntermediate.L", "IndexDistal.L"],
+        );
+        add_chain(&mut app, "Foot.R", &["Toes.R"]);
+        app.update();
+        app.world_mut()
+            .commands()
+            .entity(root)
+            .try_insert(AvatarGeneric::default());
+        app.update();
+
+        check_bone_name::<UpperChest>(&mut app, "UpperChest");
+        check_bone_name::<ShoulderL>(&mut app, "Shoulder.L");
+        check_bone_name::<ArmR>(&mut app, "Arm.R");
+        check_bone_name::<IndexDistalL>(&mut app, "IndexDistal.L");
+        check_bone_name::<ToesR>(&mut app, "Toes.R");
+        assert_eq!(ik_chain_length::<HandL>(&mut app), 3);
+        assert_eq!(ik_chain_length::<HandR>(&mut app), 2);
+        assert_eq!(ik_chain_length::<Head>(&mut app), 1);
+
+        let report = app.world().get::<RigReport>(root).unwrap();
+        assert!(report.missing.is_empty(), "{:?}", report.missing);
+        assert!(report.skipped.contains(&"Shoulder.R"));
+        assert!(report.skipped.contains(&"ThumbProximal.L"));
+        assert!(!report.skipped.contains(&"IndexProximal.L"));
+    }
+
+    #[test]
+    fn test_poles_and_ik_settings() {
+        let mut app = app();
+        let root = add_armature(&mut app);
+        app.update();
+        let forearm = find(&mut app, "Forearm.L");
+        let hand = find(&mut app, "Hand.L");
+        app.world_mut().entity_mut(forearm).insert((
+            Transform::from_rotation(Quat::from_rotation_x(0.2)),
+            GlobalTransform::from_xyz(0.3, 1.4, 0.0),
+        ));
+        app.world_mut()
+            .entity_mut(hand)
+            .insert(GlobalTransform::from_xyz(0.6, 1.4, 0.0));
+        app.world_mut()
+            .commands()
+            .entity(root)
+            .try_insert(AvatarGeneric {
+                ik_iterations: 7,
+                leg_chain_length: Some(3),
+                elbow_limit: 1.0,
+                ..default()
+            });
+        app.update();
+
+        let mut q = app
+            .world_mut()
+            .query_filtered::<&IkConstraint, With<Bone<HandL>>>();
+        let ik = q.single(app.world());
+        assert_eq!(ik.iterations, 7);
+        assert_eq!(ik.chain_length, 2);
+        let pole = ik.pole_target.expect("hand without pole");
+        assert!(app.world().get::<bones::Pole<HandL>>(pole).is_some());
+        assert_eq!(
+            app.world().get::<Name>(pole).unwrap().as_str(),
+            "Pole:HandL"
+        );
+        assert_eq!(ik_chain_length::<FootR>(&mut app), 3);
+
+        let mut q = app
+            .world_mut()
+            .query_filtered::<&IkConstraint, With<Bone<Head>>>();
+        assert!(q.single(app.world()).pole_target.is_none());
+
+        let limit = app.world().get::<ik::JointLimit>(forearm).unwrap();
+        assert_eq!(limit.rest, Quat::from_rotation_x(0.2));
+        assert_eq!(limit.max_angle, 1.0);
+        // The forearm bends forwards, away from the pole behind the elbow.
+        let global = app.world().get::<GlobalTransform>(forearm).unwrap();
+        let bent = global
+            .affine()
+            .transform_vector3(Quat::from_axis_angle(limit.axis, 0.5) * limit.bone);
+        let straight = global.affine().transform_vector3(limit.bone);
+        assert!(bent.z > straight.z);
+    }
+
+    #[test]
+    fn test_body_follows_head() {
+        let mut app = app();
+        app.init_resource::<Time>();
+        let root = add_armature(&mut app);
+        app.update();
+        for (name, at) in [
+            ("Hips", Vec3::new(0.0, 1.0, 0.0)),
+            ("Head", Vec3::new(0.0, 1.6, 0.0)),
+            ("Foot.L", Vec3::new(0.1, 0.0, 0.0)),
+            ("Foot.R", Vec3::new(-0.1, 0.0, 0.0)),
+        ] {
+            let bone = find(&mut app, name);
+            app.world_mut().entity_mut(bone).insert((
+                Transform::from_translation(at),
+                GlobalTransform::from_translation(at),
+            ));
+        }
+        app.world_mut().entity_mut(root).insert(AvatarGeneric {
+            body: BodySettings {
+                smoothing: 0.0,
+                ..default()
+            },
+            ..default()
+        });
+        app.world_mut().entity_mut(root).insert(LocalUser);
+        app.update();
+
+        let head = target::<Head>(&mut app);
+        app.world_mut()
+            .get_mut::<Transform>(head)
+            .unwrap()
+            .translation = Vec3::new(0.0, 1.6, 1.0);
+        for _ in 0..10 {
+            app.world_mut()
+                .resource_mut::<Time>()
+                .advance_by(std::time::Duration::from_millis(100));
+            app.update();
+        }
+
+        let hips = find(&mut app, "Hips");
+        let hips = app.world().get::<Transform>(hips).unwrap().translation;
+        assert!(hips.abs_diff_eq(Vec3::new(0.0, 1.0, 1.0), 1e-4), "{}", hips);
+        let foot_l = target::<FootL>(&mut app);
+        let foot_l = app.world().get::<Transform>(foot_l).unwrap().translation;
+        assert!(
+            foot_l.abs_diff_eq(Vec3::new(0.1, 0.0, 1.0), 1e-4),
+            "{}",
+            foot_l
+        );
+        let foot_r = target::<FootR>(&mut app);
+        let foot_r = app.world().get::<Transform>(foot_r).unwrap().translation;
+        assert!(
+            foot_r.abs_diff_eq(Vec3::new(-0.1, 0.0, 1.0), 1e-4),
+            "{}",
+            foot_r
+        );
+    }
+
+    #[test]
+    fn test_calibration_scales_avatar() {
+        let mut app = app();
+        app.insert_resource(calibration::Calibrations::default());
+        let root = add_armature(&mut app);
+        app.update();
+        for (name, at) in [
+            ("Hips", Vec3::new(0.0, 1.0, 0.0)),
+            ("Head", Vec3::new(0.0, 1.5, 0.0)),
+            ("Foot.L", Vec3::new(0.1, 0.0, 0.0)),
+            ("Foot.R", Vec3::new(-0.1, 0.0, 0.0)),
+            ("Arm.L", Vec3::new(0.2, 1.4, 0.0)),
+            ("Forearm.L", Vec3::new(0.45, 1.4, 0.0)),
+            ("Hand.L", Vec3::new(0.7, 1.4, 0.0)),
+            ("Arm.R", Vec3::new(-0.2, 1.4, 0.0)),
+            ("Forearm.R", Vec3::new(-0.45, 1.4, 0.0)),
+            ("Hand.R", Vec3::new(-0.7, 1.4, 0.0)),
+        ] {
+            let bone = find(&mut app, name);
+            app.world_mut()
+                .entity_mut(bone)
+                .insert(GlobalTransform::from_translation(at));
+        }
+        app.world_mut().entity_mut(root).insert((
+            Transform::default(),
+            AvatarGeneric::default(),
+            LocalUser,
+            calibration::UserMeasures {
+                eye_height: 1.8,
+                arm_span: Some(2.0),
+            },
+        ));
+        let av = app.world().get::<AvatarGeneric>(root).unwrap();
+        assert!((av.height() - 1.5).abs() < 1e-5);
+        assert!((av.arm_span() - 1.4).abs() < 1e-5);
+        app.update();
+        app.update();
+
+        let calibration = *app.world().get::<calibration::Calibration>(root).unwrap();
+        assert!((calibration.scale - 1.2).abs() < 1e-5);
+        assert!((calibration.arm_ratio - 0.84).abs() < 1e-5);
+        let scale = app.world().get::<Transform>(root).unwrap().scale;
+        assert!(scale.abs_diff_eq(Vec3::splat(1.2), 1e-5));
+
+        // Tracked hands are pulled towards the shoulder.
+        let hand = target::<HandL>(&mut app);
+        app.world_mut()
+            .get_mut::<Transform>(hand)
+            .unwrap()
+            .translation = Vec3::new(1.2, 1.4, 0.0);
+        app.update();
+        let reached = app.world().get::<Transform>(hand).unwrap().translation;
+        assert!(
+            reached.abs_diff_eq(Vec3::new(1.04, 1.4, 0.0), 1e-5),
+            "{}",
+            reached
+        );
+        app.update();
+        let reached = app.world().get::<Transform>(hand).unwrap().translation;
+        assert!(
+            reached.abs_diff_eq(Vec3::new(1.04, 1.4, 0.0), 1e-5),
+            "{}",
+            reached
+        );
+    }
+
+    fn target<T: 'static + Bones + Send + Sync>(app: &mut App) -> Entity {
+        let mut q = app.world_mut().query_filtered::<Entity, With<Target<T>>>();
+        q.single(app.world())
+    }
+
+    fn ik_chain_length<T: 'static + Bones + Send + Sync>(app: &mut App) -> usize {
+        let mut q = app
+            .world_mut()
+            .query_filtered::<&IkConstraint, With<Bone<T>>>();
+        q.single(app.world()).chain_length
+    }
+
+    fn find(app: &mut App, name: &str) -> Entity {
+        let mut q = app.world_mut().query::<(Entity, &Name)>();
+        q.iter(app.world())
+            .find(|(_, n)| n.as_str() == name)
+            .unwrap()
+            .0
+    }
+
+    fn insert_between(app: &mut App, parent: &str, child: &str, name: &str) {
+        let parent = find(app, parent);
+        let child = find(app, child);
+        let between = app
+            .world_mut()
+            .spawn((GlobalTransform::default(), Name::new(name.to_owned())))
+            .set_parent(parent)
+            .id();
+        app.world_mut().entity_mut(child).set_parent(between);
+    }
+
+    fn add_chain(app: &mut App, parent: &str, names: &[&str]) {
+        let mut parent = find(app, parent);
+        for name in names {
+            parent = app
+                .world_mut()
+                .spawn((GlobalTransform::default(), Name::new(name.to_string())))
+                .set_parent(parent)
+                .id();
+        }
+    }
+
     fn check_bone_name<T: 'static + Bones + Send + Sync>(app: &mut App, name: &'static str) {
         let mut q = app.world_mut().query_filtered::<&Name, With<Bone<T>>>();
         let found = q
@@ -297,33 +652,37 @@ mod test {
     }
 
     fn add_armature(app: &mut App) -> Entity {
+        add_rig(app, "Armature", |bone| bone.to_owned())
+    }
+
+    fn add_rig(app: &mut App, armature: &str, name: impl Fn(&str) -> String) -> Entity {
         let root = app
             .world_mut()
             .spawn((GlobalTransform::default(), Name::new("Root")))
             .id();
         let arma = app
             .world_mut()
-            .spawn((GlobalTransform::default(), Name::new("Armature")))
+            .spawn((GlobalTransform::default(), Name::new(armature.to_owned())))
             .id();
         let hips = app
             .world_mut()
-            .spawn((GlobalTransform::default(), Name::new("Hips")))
+            .spawn((GlobalTransform::default(), Name::new(name("Hips"))))
             .id();
         let spine = app
             .world_mut()
-            .spawn((GlobalTransform::default(), Name::new("Spine")))
+            .spawn((GlobalTransform::default(), Name::new(name("Spine"))))
             .id();
         let chest = app
             .world_mut()
-            .spawn((GlobalTransform::default(), Name::new("Chest")))
+            .spawn((GlobalTransform::default(), Name::new(name("Chest"))))
             .id();
         let neck = app
             .world_mut()
-            .spawn((GlobalTransform::default(), Name::new("Neck")))
+            .spawn((GlobalTransform::default(), Name::new(name("Neck"))))
             .id();
         let head = app
             .world_mut()
-            .spawn((GlobalTransform::default(), Name::new("Head")))
+            .spawn((GlobalTransform::default(), Name::new(name("Head"))))
             .id();
         app.world_mut().commands().entity(root).add_child(arma);
         app.world_mut().commands().entity(arma).add_child(hips);
@@ -334,27 +693,27 @@ mod test {
 
         let arm_l = app
             .world_mut()
-            .spawn((GlobalTransform::default(), Name::new("Arm.L")))
+            .spawn((GlobalTransform::default(), Name::new(name("Arm.L"))))
             .id();
         let forearm_l = app
             .world_mut()
-            .spawn((GlobalTransform::default(), Name::new("Forearm.L")))
+            .spawn((GlobalTransform::default(), Name::new(name("Forearm.L"))))
             .id();
         let hand_l = app
             .world_mut()
-            .spawn((GlobalTransform::default(), Name::new("Hand.L")))
+            .spawn((GlobalTransform::default(), Name::new(name("Hand.L"))))
             .id();
         let arm_r = app
             .world_mut()
-            .spawn((GlobalTransform::default(), Name::new("Arm.R")))
+            .spawn((GlobalTransform::default(), Name::new(name("Arm.R"))))
             .id();
         let forearm_r = app
             .world_mut()
-            .spawn((GlobalTransform::default(), Name::new("Forearm.R")))
+            .spawn((GlobalTransform::default(), Name::new(name("Forearm.R"))))
             .id();
         let hand_r = app
             .world_mut()
-            .spawn((GlobalTransform::default(), Name::new("Hand.R")))
+            .spawn((GlobalTransform::default(), Name::new(name("Hand.R"))))
             .id();
         app.world_mut().commands().entity(chest).add_child(arm_l);
         app.world_mut()
@@ -377,27 +736,27 @@ mod test {
 
         let thigh_l = app
             .world_mut()
-            .spawn((GlobalTransform::default(), Name::new("Thigh.L")))
+            .spawn((GlobalTransform::default(), Name::new(name("Thigh.L"))))
             .id();
         let leg_l = app
             .world_mut()
-            .spawn((GlobalTransform::default(), Name::new("Leg.L")))
+            .spawn((GlobalTransform::default(), Name::new(name("Leg.L"))))
             .id();
         let foot_l = app
             .world_mut()
-            .spawn((GlobalTransform::default(), Name::new("Foot.L")))
+            .spawn((GlobalTransform::default(), Name::new(name("Foot.L"))))
             .id();
         let thigh_r = app
             .world_mut()
-            .spawn((GlobalTransform::default(), Name::new("Thigh.R")))
+            .spawn((GlobalTransform::default(), Name::new(name("Thigh.R"))))
             .id();
         let leg_r = app
             .world_mut()
-            .spawn((GlobalTransform::default(), Name::new("Leg.R")))
+            .spawn((GlobalTransform::default(), Name::new(name("Leg.R"))))
             .id();
         let foot_r = app
             .world_mut()
-            .spawn((GlobalTransform::default(), Name::new("Foot.R")))
+            .spawn((GlobalTransform::default(), Name::new(name("Foot.R"))))
             .id();
         app.world_mut().commands().entity(hips).add_child(thigh_l);
         app.world_mut().commands().entity(thigh_l).add_child(leg_l);
@@ -0,0 +1,382 @@
+use std::{collections::HashMap, sync::LazyLock};
+
+use bevy::prelude::*;
+
+/// Generic name of the node holding the bones.
+pub const ARMATURE: &str = "Armature";
+
+/// Finger bones are named like `IndexProximal.L`.
+pub const FINGERS: [&str; 5] = ["Thumb", "Index", "Middle", "Ring", "Little"];
+pub const FINGER_JOINTS: [&str; 3] = ["Proximal", "Intermediate", "Distal"];
+
+/// Node names of a rig by the generic bone names (`Hips`, `Arm.L`, ...).
+/// Put it on the avatar before `AvatarGeneric` for rigs that neither the
+/// presets nor the name matching get right.
+#[derive(Component, Clone, Debug, Default, PartialEq)]
+pub struct RigMapping {
+    pub name: String,
+    nodes: HashMap<String, String>,
+}
+
+impl RigMapping {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            nodes: HashMap::new(),
+        }
+    }
+
+    pub fn with(mut self, bone: &str, node: &str) -> Self {
+        self.nodes.insert(bone.to_owned(), node.to_owned());
+        self
+    }
+
+    fn with_sides(self, bone: &str, left: &str, right: &str) -> Self {
+        self.with(&format!("{}.L", bone), left)
+            .with(&format!("{}.R", bone), right)
+    }
+
+    /// Node of each finger, from its name, joint from 1 to 3 and side.
+    fn with_fingers(mut self, node: impl Fn(&str, usize, &str) -> String) -> Self {
+        for finger in FINGERS {
+            for (joint, joint_name) in FINGER_JOINTS.iter().enumerate() {
+                for side in ["L", "R"] {
+                    let bone = format!("{}{}.{}", finger, joint_name, side);
+                    self.nodes.insert(bone, node(finger, joint + 1, side));
+                }
+            }
+        }
+        self
+    }
+
+    pub fn node(&self, bone: &str) -> Option<&str> {
+        self.nodes.get(bone).map(String::as_str)
+    }
+
+    pub fn mixamo() -> Self {
+        Self::new("mixamo")
+            .with("Hips", "mixamorig:Hips")
+            .with("Spine", "mixamorig:Spine")
+            .with("Chest", "mixamorig:Spine2")
+            .with_sides(
+                "Shoulder",
+                "mixamorig:LeftShoulder",
+                "mixamorig:RightShoulder",
+            )
+            .with("Neck", "mixamorig:Neck")
+            .with("Head", "mixamorig:Head")
+            .with_sides("Arm", "mixamorig:LeftArm", "mixamorig:RightArm")
+            .with_sides("Forearm", "mixamorig:LeftForeArm", "mixamorig:RightForeArm")
+            .with_sides("Hand", "mixamorig:LeftHand", "mixamorig:RightHand")
+            .with_sides("Thigh", "mixamorig:LeftUpLeg", "mixamorig:RightUpLeg")
+            .with_sides("Leg", "mixamorig:LeftLeg", "mixamorig:RightLeg")
+            .with_sides("Foot", "mixamorig:LeftFoot", "mixamorig:RightFoot")
+            .with_sides("Toes", "mixamorig:LeftToeBase", "mixamorig:RightToeBase")
+            .with_fingers(|finger, joint, side| {
+                let side = if side == "L" { "Left" } else { "Right" };
+                let finger = if finger == "Little" { "Pinky" } else { finger };
+                format!("mixamorig:{}Hand{}{}", side, finger, joint)
+            })
+    }
+
+    /// Deform bones of a generated Blender Rigify rig.
+    pub fn rigify() -> Self {
+        Self::new("rigify")
+            .with(ARMATURE, "rig")
+            .with("Hips", "DEF-spine")
+            .with("Spine", "DEF-spine.001")
+            .with("Chest", "DEF-spine.003")
+            .with("Neck", "DEF-spine.004")
+            .with("Head", "DEF-spine.006")
+            .with_sides("Shoulder", "DEF-shoulder.L", "DEF-shoulder.R")
+            .with_sides("Arm", "DEF-upper_arm.L", "DEF-upper_arm.R")
+            .with_sides("Forearm", "DEF-forearm.L", "DEF-forearm.R")
+            .with_sides("Hand", "DEF-hand.L", "DEF-hand.R")
+            .with_sides("Thigh", "DEF-thigh.L", "DEF-thigh.R")
+            .with_sides("Leg", "DEF-shin.L", "DEF-shin.R")
+            .with_sides("Foot", "DEF-foot.L", "DEF-foot.R")
+            .with_sides("Toes", "DEF-toe.L", "DEF-toe.R")
+            .with_fingers(|finger, joint, side| match finger {
+                "Thumb" => format!("DEF-thumb.0{}.{}", joint, side),
+                "Little" => format!("DEF-f_pinky.0{}.{}", joint, side),
+                finger => format!("DEF-f_{}.0{}.{}", finger.to_lowercase(), joint, side),
+            })
+    }
+
+    /// The Unreal mannequin skeleton.
+    pub fn unreal() -> Self {
+        Self::new("unreal")
+            .with("Hips", "pelvis")
+            .with("Spine", "spine_01")
+            .with("Chest", "spine_03")
+            .with("Neck", "neck_01")
+            .with("Head", "head")
+            .with_sides("Shoulder", "clavicle_l", "clavicle_r")
+            .with_sides("Arm", "upperarm_l", "upperarm_r")
+            .with_sides("Forearm", "lowerarm_l", "lowerarm_r")
+            .with_sides("Hand", "hand_l", "hand_r")
+            .with_sides("Thigh", "thigh_l", "thigh_r")
+            .with_sides("Leg", "calf_l", "calf_r")
+            .with_sides("Foot", "foot_l", "foot_r")
+            .with_sides("Toes", "ball_l", "ball_r")
+            .with_fingers(|finger, joint, side| {
+                let finger = if finger == "Little" { "pinky" } else { finger };
+                format!(
+                    "{}_0{}_{}",
+                    finger.to_lowercase(),
+                    joint,
+                    side.to_lowercase()
+                )
+            })
+    }
+
+    /// VRM humanoid rigs as VRoid exports them.
+    pub fn vrm() -> Self {
+        Self::new("vrm")
+            .with("Hips", "J_Bip_C_Hips")
+            .with("Spine", "J_Bip_C_Spine")
+            .with("Chest", "J_Bip_C_Chest")
+            .with("UpperChest", "J_Bip_C_UpperChest")
+            .with_sides("Shoulder", "J_Bip_L_Shoulder", "J_Bip_R_Shoulder")
+            .with("Neck", "J_Bip_C_Neck")
+            .with("Head", "J_Bip_C_Head")
+            .with_sides("Arm", "J_Bip_L_UpperArm", "J_Bip_R_UpperArm")
+            .with_sides("Forearm", "J_Bip_L_LowerArm", "J_Bip_R_LowerArm")
+            .with_sides("Hand", "J_Bip_L_Hand", "J_Bip_R_Hand")
+            .with_sides("Thigh", "J_Bip_L_UpperLeg", "J_Bip_R_UpperLeg")
+            .with_sides("Leg", "J_Bip_L_LowerLeg", "J_Bip_R_LowerLeg")
+            .with_sides("Foot", "J_Bip_L_Foot", "J_Bip_R_Foot")
+            .with_sides("Toes", "J_Bip_L_ToeBase", "J_Bip_R_ToeBase")
+            .with_fingers(|finger, joint, side| format!("J_Bip_{}_{}{}", side, finger, joint))
+    }
+}
+
+/// Tried in order after the exact names and before the name matching.
+pub(crate) static PRESETS: LazyLock<Vec<RigMapping>> = LazyLock::new(|| {
+    vec![
+        RigMapping::mixamo(),
+        RigMapping::rigify(),
+        RigMapping::unreal(),
+        RigMapping::vrm(),
+    ]
+});
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum MappedBy {
+    /// The node has the generic name.
+    Exact,
+    /// By a mapping, either a preset or the one of the avatar.
+    Mapping(String),
+    /// The node name looked like the bone.
+    Heuristic,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MappedBone {
+    pub bone: &'static str,
+    pub node: String,
+    pub entity: Entity,
+    pub by: MappedBy,
+}
+
+/// Which nodes the bones of an avatar were found in, and which were not.
+#[derive(Component, Clone, Debug, Default, PartialEq)]
+pub struct RigReport {
+    pub mapped: Vec<MappedBone>,
+    pub missing: Vec<&'static str>,
+    /// Optional bones the rig does not have.
+    pub skipped: Vec<&'static str>,
+}
+
+impl RigReport {
+    pub fn get(&self, bone: &str) -> Option<&MappedBone> {
+        self.mapped.iter().find(|m| m.bone == bone)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Side {
+    Left,
+    Right,
+}
+
+/// Alternative names of each generic bone, better first.
+static SYNONYMS: LazyLock<HashMap<String, Vec<String>>> = LazyLock::new(|| {
+    let mut synonyms: HashMap<String, Vec<String>> = [
+        (ARMATURE, &["armature", "skeleton"][..]),
+        ("Hips", &["hips", "hip", "pelvis"]),
+        ("Spine", &["spine", "spine1", "spine01", "abdomen", "waist"]),
+        (
+            "Chest",
+            &["chest", "spine2", "spine02", "spine3", "spine03"],
+        ),
+        ("UpperChest", &["upperchest"]),
+        ("Neck", &["neck", "neck1", "neck01"]),
+        ("Head", &["head"]),
+        ("Shoulder", &["shoulder", "clavicle", "collar"]),
+        ("Arm", &["upperarm", "arm"]),
+        ("Forearm", &["forearm", "lowerarm", "elbow"]),
+        ("Hand", &["hand", "wrist"]),
+        ("Thigh", &["thigh", "upleg", "upperleg"]),
+        ("Leg", &["leg", "shin", "calf", "lowerleg", "knee"]),
+        ("Foot", &["foot", "ankle"]),
+        ("Toes", &["toes", "toe", "toebase", "ball"]),
+    ]
+    .into_iter()
+    .map(|(bone, names)| {
+        let names = names.iter().map(|n| n.to_string()).collect();
+        (bone.to_owned(), names)
+    })
+    .collect();
+    for finger in FINGERS {
+        let aliases = match finger {
+            "Little" => vec!["little", "pinky"],
+            finger => vec![finger],
+        };
+        for (joint, joint_name) in FINGER_JOINTS.iter().enumerate() {
+            let joint = joint + 1;
+            let mut names = vec![];
+            for alias in aliases.iter() {
+                let alias = alias.to_lowercase();
+                names.push(format!("{}{}", alias, joint_name.to_lowercase()));
+                names.push(format!("{}{}", alias, joint));
+                names.push(format!("{}0{}", alias, joint));
+                names.push(format!("hand{}{}", alias, joint));
+                names.push(format!("f{}0{}", alias, joint));
+            }
+            synonyms.insert(format!("{}{}", finger, joint_name), names);
+        }
+    }
+    synonyms
+});
+
+/// How well a node name fits a generic bone, lower is better, for example
+/// `Forearm.L` fits `mixamorig_LeftForeArm`, `lowerarm_l` or `J_Bip_L_LowerArm`.
+pub(crate) fn heuristic_rank(bone: &str, node: &str) -> Option<usize> {
+    let (base, side) = match bone.rsplit_once('.') {
+        Some((base, "L")) => (base, Some(Side::Left)),
+        Some((base, "R")) => (base, Some(Side::Right)),
+        _ => (bone, None),
+    };
+    let (node_side, core) = normalise(node);
+    if node_side != side {
+        return None;
+    }
+    SYNONYMS.get(base)?.iter().position(|s| *s == core)
+}
+
+/// Side and the rest of the name, lowercase without separators or the
+/// usual prefixes of the exporters.
+fn normalise(node: &str) -> (Option<Side>, String) {
+    let lower = node.to_lowercase();
+    let mut side = None;
+    let mut core = String::new();
+    for token in lower.split(|c: char| !c.is_ascii_alphanumeric()) {
+        match token {
+            "" | "def" | "j" | "c" => (),
+            "l" | "left" => side = Some(Side::Left),
+            "r" | "right" => side = Some(Side::Right),
+            t if t.starts_with("mixamorig") || t.starts_with("bip") => (),
+            t => {
+                if let Some(rest) = t.strip_prefix("left") {
+                    side = Some(Side::Left);
+                    core.push_str(rest);
+                } else if let Some(rest) = t.strip_prefix("right") {
+                    side = Some(Side::Right);
+                    core.push_str(rest);
+                } else {
+                    core.push_str(t);
+                }
+            }
+        }
+    }
+    (side, core)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_heuristic_names() {
+        for (bone, node) in [
+            ("Forearm.L", "mixamorig_LeftForeArm"),
+            ("Forearm.L", "lowerarm_l"),
+            ("Forearm.L", "J_Bip_L_LowerArm"),
+            ("Forearm.R", "RightLowerArm"),
+            ("Arm.R", "DEF-upper_arm.R"),
+            ("Thigh.L", "mixamorig1:LeftUpLeg"),
+            ("Leg.R", "calf_r"),
+            ("Hips", "Bip01_Pelvis"),
+            ("Chest", "spine_03"),
+            ("Head", "J_Bip_C_Head"),
+            ("Shoulder.L", "clavicle_l"),
+            ("Toes.R", "mixamorig:RightToeBase"),
+            ("ThumbProximal.L", "DEF-thumb.01.L"),
+            ("IndexIntermediate.R", "mixamorig:RightHandIndex2"),
+            ("LittleDistal.L", "DEF-f_pinky.03.L"),
+            ("LittleProximal.R", "J_Bip_R_Little1"),
+            ("RingDistal.L", "ring_03_l"),
+            (ARMATURE, "Skeleton"),
+        ] {
+            assert!(
+                heuristic_rank(bone, node).is_some(),
+                "{} should match {}",
+                node,
+                bone
+            );
+        }
+    }
+
+    #[test]
+    fn test_heuristic_mismatches() {
+        for (bone, node) in [
+            ("Forearm.L", "RightForeArm"),
+            ("Arm.L", "LeftForeArm"),
+            ("Hand.L", "LeftHandIndex1"),
+            ("Leg.L", "LeftUpLeg"),
+            ("Hips", "LeftHip"),
+            ("IndexProximal.L", "LeftHandIndex2"),
+            ("ThumbDistal.L", "RightHandThumb3"),
+        ] {
+            assert!(
+                heuristic_rank(bone, node).is_none(),
+                "{} should not match {}",
+                node,
+                bone
+            );
+        }
+    }
+
+    #[test]
+    fn test_presets_have_every_bone() {
+        for preset in PRESETS.iter() {
+            for bone in [
+                "Hips",
+                "Spine",
+                "Chest",
+                "Neck",
+                "Head",
+                "Arm.L",
+                "Arm.R",
+                "Forearm.L",
+                "Forearm.R",
+                "Hand.L",
+                "Hand.R",
+                "Thigh.L",
+                "Thigh.R",
+                "Leg.L",
+                "Leg.R",
+                "Foot.L",
+                "Foot.R",
+            ] {
+                assert!(
+                    preset.node(bone).is_some(),
+                    "{} misses {}",
+                    preset.name,
+                    bone
+                );
+            }
+        }
+    }
+}
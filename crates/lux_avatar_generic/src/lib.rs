@@ -2,7 +2,11 @@ use avatars::AvatarPlugin;
 
 mod avatars;
 
+pub use avatars::body;
 pub use avatars::bones;
+pub use avatars::calibration;
+pub use avatars::fingers;
+pub use avatars::rig_mapping;
 pub use avatars::AvatarGeneric;
 
 pub fn init(app: &mut bevy::prelude::App) {
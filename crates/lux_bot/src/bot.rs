@@ -0,0 +1,329 @@
+use std::{
+    f32::consts::TAU,
+    net::IpAddr,
+    sync::mpsc::Sender,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use bevy::{prelude::*, time::common_conditions::on_timer};
+use bevy_sync::SyncMark;
+use lux_cli::{Args, Command};
+use lux_components::{LocalUser, User};
+use lux_networking::NetworkStats;
+
+use crate::{BotClock, BotReport, HostStats};
+
+/// Each bot walks on a circle of its own, the circles on a grid.
+const WALK_RADIUS: f32 = 5.0;
+const WALK_SPEED: f32 = 1.4;
+const GRID: usize = 10;
+const GRID_SPACING: f32 = 12.0;
+/// How often the bot clock is written.
+const CLOCK_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Resource)]
+struct Bot {
+    index: usize,
+    reports: Sender<BotReport>,
+    latencies: Vec<f32>,
+}
+
+/// The root of the avatar of this bot.
+#[derive(Component)]
+struct OwnAvatar;
+
+/// Parts of the avatar that move along scripted paths.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+enum BotTarget {
+    Head,
+    HandL,
+    HandR,
+}
+
+impl BotTarget {
+    const ALL: [Self; 3] = [Self::Head, Self::HandL, Self::HandR];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Head => "Head",
+            Self::HandL => "Hand L",
+            Self::HandR => "Hand R",
+        }
+    }
+
+    /// Relative to the avatar: the head bobs, hands swing in turn.
+    fn at(&self, t: f32) -> Transform {
+        let step = (t * TAU).sin();
+        match self {
+            Self::Head => Transform::from_xyz(0.0, 1.7 + 0.03 * step.abs(), 0.0),
+            Self::HandL => Transform::from_xyz(-0.3, 1.0, 0.25 * step),
+            Self::HandR => Transform::from_xyz(0.3, 1.0, -0.25 * step),
+        }
+    }
+}
+
+/// Where bot `index` walks to at `t` seconds.
+fn walk(index: usize, t: f32) -> Transform {
+    let center = Vec3::new(
+        (index % GRID) as f32 * GRID_SPACING,
+        0.0,
+        (index / GRID) as f32 * GRID_SPACING,
+    );
+    let angle = t * WALK_SPEED / WALK_RADIUS + index as f32;
+    let offset = Vec3::new(angle.cos(), 0.0, angle.sin());
+    let forward = Vec3::new(-angle.sin(), 0.0, angle.cos());
+    Transform::from_translation(center + offset * WALK_RADIUS).looking_to(forward, Vec3::Y)
+}
+
+fn now() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs_f64())
+        .unwrap_or_default()
+}
+
+/// A headless client joining the host, walking around and reporting
+/// what it measures once per second.
+pub(crate) fn app(ip: IpAddr, port: u16, index: usize, reports: Sender<BotReport>) -> App {
+    let args = args(ip, port, index);
+    let mut app = App::new();
+    lux_headless::init(&mut app);
+    lux_networking::init(&args, &mut app);
+    lux_components::init(&mut app);
+    crate::sync_components(&mut app);
+    app.insert_resource(args);
+    init(&mut app, index, reports);
+    app
+}
+
+/// Bots run in one process, each takes the web port after the one before.
+fn args(ip: IpAddr, port: u16, index: usize) -> Args {
+    let web_port = u16::try_from(index)
+        .ok()
+        .and_then(|index| port.checked_add(2)?.checked_add(index));
+    Args {
+        xr_enabled: false,
+        name: Some(format!("Bot {}", index)),
+        plugins_dir: None,
+        interest_radius: None,
+        sim_latency: None,
+        sim_jitter: None,
+        sim_loss: None,
+        sim_bandwidth: None,
+        record: None,
+        log_level: None,
+        log_filter: None,
+        log_file: None,
+        log_json: false,
+        height: None,
+        command: Some(Command::Join {
+            ip,
+            port,
+            avatar_file: None,
+            web_port,
+        }),
+    }
+}
+
+fn init(app: &mut App, index: usize, reports: Sender<BotReport>) {
+    app.insert_resource(Bot {
+        index,
+        reports,
+        latencies: vec![],
+    });
+    app.add_systems(Startup, spawn_avatar);
+    app.add_systems(Update, (follow_paths, measure_latency));
+    app.add_systems(Update, write_clock.run_if(on_timer(CLOCK_INTERVAL)));
+    app.add_systems(Update, send_report.run_if(on_timer(Duration::from_secs(1))));
+}
+
+fn spawn_avatar(mut commands: Commands, bot: Res<Bot>) {
+    commands
+        .spawn((
+            SyncMark,
+            User,
+            LocalUser,
+            OwnAvatar,
+            BotClock::default(),
+            Name::new(format!("Bot {}", bot.index)),
+            SpatialBundle::from_transform(walk(bot.index, 0.0)),
+        ))
+        .with_children(|parent| {
+            for target in BotTarget::ALL {
+                parent.spawn((
+                    SyncMark,
+                    target,
+                    Name::new(target.name()),
+                    SpatialBundle::from_transform(target.at(0.0)),
+                ));
+            }
+        });
+}
+
+fn follow_paths(
+    time: Res<Time>,
+    bot: Res<Bot>,
+    mut avatar: Query<&mut Transform, With<OwnAvatar>>,
+    mut targets: Query<(&mut Transform, &BotTarget), Without<OwnAvatar>>,
+) {
+    let t = time.elapsed_seconds();
+    for mut transform in avatar.iter_mut() {
+        *transform = walk(bot.index, t);
+    }
+    for (mut transform, target) in targets.iter_mut() {
+        *transform = target.at(t);
+    }
+}
+
+fn write_clock(mut clocks: Query<&mut BotClock, With<OwnAvatar>>) {
+    for mut clock in clocks.iter_mut() {
+        clock.sent = now();
+    }
+}
+
+/// Bots share the wall clock, so the age of a received clock is the time
+/// it took to go through the host.
+fn measure_latency(
+    mut bot: ResMut<Bot>,
+    clocks: Query<&BotClock, (Changed<BotClock>, Without<OwnAvatar>)>,
+) {
+    let now = now();
+    for clock in clocks.iter() {
+        if clock.sent > 0.0 {
+            bot.latencies.push((now - clock.sent).max(0.0) as f32);
+        }
+    }
+}
+
+fn send_report(mut bot: ResMut<Bot>, stats: Option<Res<NetworkStats>>, host: Query<&HostStats>) {
+    let report = BotReport {
+        bot: bot.index,
+        sent_bytes_per_second: stats.map(|s| s.total_bytes_per_second()).unwrap_or(0.0),
+        latencies: std::mem::take(&mut bot.latencies),
+        host: host.iter().next().copied(),
+    };
+    // The summary is gone when the process is closing.
+    let _ = bot.reports.send(report);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+    use clap::Parser;
+    use std::{
+        net::{Ipv4Addr, TcpListener, UdpSocket},
+        sync::mpsc,
+    };
+
+    #[test]
+    fn test_walk_stays_on_own_circle() {
+        for index in [0, 7, 23] {
+            let start = walk(index, 0.0).translation;
+            let mut last = start;
+            for step in 1..100 {
+                let at = walk(index, step as f32 * 0.1).translation;
+                assert!(at.distance(last) < WALK_SPEED * 0.1 + 1e-3);
+                assert!(at.distance(start) <= WALK_RADIUS * 2.0 + 1e-3);
+                last = at;
+            }
+        }
+        let a = walk(0, 0.0).translation;
+        let b = walk(1, 0.0).translation;
+        assert!(a.distance(b) > 1.0);
+    }
+
+    #[test]
+    fn test_latency_of_other_clocks() {
+        let (sender, receiver) = mpsc::channel();
+        let mut app = App::new();
+        app.init_resource::<Time>();
+        init(&mut app, 0, sender);
+        app.update();
+        app.world_mut().spawn(BotClock { sent: now() - 0.05 });
+        app.update();
+        app.world_mut().run_system_once(send_report);
+
+        let report = receiver.try_recv().unwrap();
+        assert_eq!(report.bot, 0);
+        assert_eq!(report.latencies.len(), 1, "own clock is not measured");
+        assert!(report.latencies[0] >= 0.05);
+        assert!(report.host.is_none());
+    }
+
+    #[test]
+    fn test_bots_have_own_web_ports() {
+        let ip = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        let ports: Vec<_> = (0..3)
+            .map(|index| match args(ip, 5000, index).command {
+                Some(Command::Join { web_port, .. }) => web_port,
+                _ => None,
+            })
+            .collect();
+        assert_eq!(ports, vec![Some(5002), Some(5003), Some(5004)]);
+    }
+
+    #[test]
+    fn test_bots_join_one_host() {
+        const BOTS: usize = 3;
+        let ip = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        let port = free_ports(BOTS as u16 + 2);
+        let args = Args::parse_from([
+            "lux",
+            "host",
+            "world.glb",
+            "127.0.0.1",
+            "--port",
+            &port.to_string(),
+        ]);
+        let mut host = App::new();
+        lux_headless::init(&mut host);
+        lux_networking::init(&args, &mut host);
+        lux_components::init(&mut host);
+        crate::init(&args, &mut host);
+        host.insert_resource(args);
+        let (sender, _receiver) = mpsc::channel();
+        let mut bots: Vec<App> = (0..BOTS)
+            .map(|index| app(ip, port, index, sender.clone()))
+            .collect();
+
+        let mut joined = 0;
+        for _ in 0..600 {
+            for bot in bots.iter_mut() {
+                bot.update();
+            }
+            host.update();
+            joined = host
+                .world_mut()
+                .query_filtered::<&Name, With<User>>()
+                .iter(host.world())
+                .filter(|n| n.as_str().starts_with("Bot "))
+                .count();
+            if joined == BOTS {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(joined, BOTS, "not every bot joined the host");
+    }
+
+    /// A port given by the system, with `count` ports after it free too.
+    fn free_ports(count: u16) -> u16 {
+        loop {
+            let port = UdpSocket::bind("127.0.0.1:0")
+                .and_then(|s| s.local_addr())
+                .unwrap()
+                .port();
+            let free = (1..=count).all(|i| {
+                port.checked_add(i)
+                    .is_some_and(|p| TcpListener::bind(("127.0.0.1", p)).is_ok())
+            });
+            let transforms = port
+                .checked_add(1)
+                .is_some_and(|p| UdpSocket::bind(("127.0.0.1", p)).is_ok());
+            if free && transforms {
+                return port;
+            }
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use std::time::Duration;
+
+use bevy::{prelude::*, time::common_conditions::on_timer};
+use bevy_sync::SyncMark;
+use lux_components::{time_ticks, TickTimes, User};
+
+/// How long the host takes per tick, published once per second so that
+/// bots can report it.
+#[derive(Component, Reflect, Default, Debug, Clone, Copy, PartialEq)]
+#[reflect(Component)]
+pub struct HostStats {
+    pub tick_ms: f32,
+    pub max_tick_ms: f32,
+    pub users: u32,
+}
+
+pub(crate) fn init(app: &mut App) {
+    time_ticks(app);
+    app.add_systems(
+        Update,
+        publish_stats.run_if(resource_exists::<Time>.and_then(on_timer(Duration::from_secs(1)))),
+    );
+}
+
+fn publish_stats(
+    mut commands: Commands,
+    times: Res<TickTimes>,
+    users: Query<(), With<User>>,
+    mut published: Query<&mut HostStats>,
+) {
+    let ticks = times.latest();
+    if ticks.ticks == 0 {
+        return;
+    }
+    let stats = HostStats {
+        tick_ms: ticks.average().as_secs_f32() * 1000.0,
+        max_tick_ms: ticks.max.as_secs_f32() * 1000.0,
+        users: users.iter().count() as u32,
+    };
+    match published.get_single_mut() {
+        Ok(mut published) => *published = stats,
+        Err(_) => {
+            commands.spawn((SyncMark, Name::new("Host stats"), stats));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+
+    #[test]
+    fn test_stats_are_published() {
+        let mut app = App::new();
+        init(&mut app);
+        app.add_systems(Update, || std::thread::sleep(Duration::from_millis(2)));
+        app.world_mut().spawn(User);
+        app.update();
+        app.update();
+        app.world_mut().run_system_once(publish_stats);
+
+        let mut query = app.world_mut().query::<&HostStats>();
+        let stats = *query.single(app.world());
+        assert!(stats.tick_ms >= 2.0, "{:?}", stats);
+        assert!(stats.max_tick_ms >= stats.tick_ms);
+        assert_eq!(stats.users, 1);
+    }
+}
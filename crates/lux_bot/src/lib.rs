@@ -0,0 +1,147 @@
+mod bot;
+mod host_stats;
+mod report;
+
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::{
+        mpsc::{self, Receiver},
+        Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use bevy::{prelude::*, time::common_conditions::on_timer};
+use bevy_sync::SyncComponent;
+use lux_cli::{Args, Command};
+
+pub use host_stats::HostStats;
+pub use report::{BotReport, LoadSummary};
+
+/// Bots joining one after the other, not all at once.
+const JOIN_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Wall clock time a bot last wrote, in seconds since the epoch.
+/// Bots run in one process, so the others tell the sync latency from it.
+#[derive(Component, Reflect, Default, Debug, Clone, Copy, PartialEq)]
+#[reflect(Component)]
+pub struct BotClock {
+    pub sent: f64,
+}
+
+/// The host measures its ticks for the bots, `lux bot` runs the bots
+/// and prints what they report.
+pub fn init(args: &Args, app: &mut App) {
+    match &args.command {
+        Some(Command::Bot {
+            ip,
+            port,
+            count,
+            seconds,
+        }) => run_bots(app, *ip, *port, *count, *seconds),
+        Some(Command::Host { .. }) => {
+            sync_components(app);
+            host_stats::init(app);
+        }
+        _ => sync_components(app),
+    }
+}
+
+pub(crate) fn sync_components(app: &mut App) {
+    app.sync_component::<HostStats>();
+    app.sync_component::<BotClock>();
+}
+
+#[derive(Resource)]
+struct Reports {
+    count: usize,
+    receiver: Mutex<Receiver<BotReport>>,
+    last: HashMap<usize, BotReport>,
+}
+
+#[derive(Resource)]
+struct StopAfter(Duration);
+
+fn run_bots(app: &mut App, ip: IpAddr, port: u16, count: usize, seconds: Option<u64>) {
+    let (sender, receiver) = mpsc::channel();
+    for index in 0..count {
+        let sender = sender.clone();
+        let spawned = thread::Builder::new()
+            .name(format!("bot {}", index))
+            .spawn(move || {
+                thread::sleep(JOIN_INTERVAL * index as u32);
+                bot::app(ip, port, index, sender).run();
+            });
+        if let Err(e) = spawned {
+            error!("Cannot start bot {}: {}", index, e);
+        }
+    }
+    app.insert_resource(Reports {
+        count,
+        receiver: Mutex::new(receiver),
+        last: HashMap::new(),
+    });
+    app.add_systems(
+        Update,
+        print_summary.run_if(on_timer(Duration::from_secs(1))),
+    );
+    if let Some(seconds) = seconds {
+        app.insert_resource(StopAfter(Duration::from_secs(seconds)));
+        app.add_systems(Update, stop_after.after(print_summary));
+    }
+}
+
+fn print_summary(mut reports: ResMut<Reports>) {
+    let received: Vec<_> = match reports.receiver.lock() {
+        Ok(receiver) => receiver.try_iter().collect(),
+        Err(_) => return,
+    };
+    for report in received {
+        reports.last.insert(report.bot, report);
+    }
+    println!(
+        "{}",
+        LoadSummary::from_reports(reports.count, &reports.last)
+    );
+}
+
+fn stop_after(time: Res<Time>, stop: Res<StopAfter>, mut exit: EventWriter<AppExit>) {
+    if time.elapsed() >= stop.0 {
+        exit.send(AppExit::Success);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_reports_are_collected() {
+        let (sender, receiver) = mpsc::channel();
+        let mut app = App::new();
+        app.insert_resource(Reports {
+            count: 2,
+            receiver: Mutex::new(receiver),
+            last: HashMap::new(),
+        });
+        app.add_systems(Update, print_summary);
+        for bot in [0, 1, 1] {
+            let report = BotReport {
+                bot,
+                sent_bytes_per_second: 100.0,
+                ..default()
+            };
+            sender.send(report).unwrap();
+        }
+        app.update();
+
+        let reports = app.world().resource::<Reports>();
+        assert_eq!(reports.last.len(), 2);
+        assert_eq!(
+            LoadSummary::from_reports(reports.count, &reports.last).bytes_per_second,
+            200.0
+        );
+    }
+}
@@ -0,0 +1,122 @@
+use std::{collections::HashMap, fmt};
+
+use crate::HostStats;
+
+/// What a bot measured over the last second.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BotReport {
+    pub bot: usize,
+    /// Estimated bytes per second the bot sent.
+    pub sent_bytes_per_second: f32,
+    /// Seconds the clocks of the other bots took to arrive.
+    pub latencies: Vec<f32>,
+    /// Last stats received from the host, none until connected.
+    pub host: Option<HostStats>,
+}
+
+/// The last report of every bot put together.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LoadSummary {
+    pub bots: usize,
+    pub connected: usize,
+    pub host_users: u32,
+    pub host_tick_ms: f32,
+    pub host_max_tick_ms: f32,
+    pub bytes_per_second: f32,
+    pub latency_ms: f32,
+    pub latency_p95_ms: f32,
+}
+
+impl LoadSummary {
+    pub fn from_reports(bots: usize, reports: &HashMap<usize, BotReport>) -> Self {
+        let mut summary = Self {
+            bots,
+            ..Default::default()
+        };
+        let mut latencies = vec![];
+        for report in reports.values() {
+            summary.bytes_per_second += report.sent_bytes_per_second;
+            latencies.extend(report.latencies.iter().map(|l| l * 1000.0));
+            let Some(host) = report.host else {
+                continue;
+            };
+            summary.connected += 1;
+            // Bots get the stats at different times, the worst is kept.
+            summary.host_users = summary.host_users.max(host.users);
+            summary.host_tick_ms = summary.host_tick_ms.max(host.tick_ms);
+            summary.host_max_tick_ms = summary.host_max_tick_ms.max(host.max_tick_ms);
+        }
+        if !latencies.is_empty() {
+            latencies.sort_by(f32::total_cmp);
+            summary.latency_ms = latencies.iter().sum::<f32>() / latencies.len() as f32;
+            let p95 = ((latencies.len() as f32 * 0.95) as usize).min(latencies.len() - 1);
+            summary.latency_p95_ms = latencies[p95];
+        }
+        summary
+    }
+}
+
+impl fmt::Display for LoadSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "bots {}/{} | host users {} tick {:.2}ms max {:.2}ms | sent {:.1} KB/s | latency {:.1}ms p95 {:.1}ms",
+            self.connected,
+            self.bots,
+            self.host_users,
+            self.host_tick_ms,
+            self.host_max_tick_ms,
+            self.bytes_per_second / 1024.0,
+            self.latency_ms,
+            self.latency_p95_ms,
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_summary() {
+        let host = HostStats {
+            tick_ms: 2.0,
+            max_tick_ms: 5.0,
+            users: 3,
+        };
+        let mut reports = HashMap::new();
+        reports.insert(
+            0,
+            BotReport {
+                bot: 0,
+                sent_bytes_per_second: 1000.0,
+                latencies: vec![0.01, 0.03],
+                host: Some(host),
+            },
+        );
+        reports.insert(
+            1,
+            BotReport {
+                bot: 1,
+                sent_bytes_per_second: 500.0,
+                latencies: vec![0.02],
+                host: None,
+            },
+        );
+        let summary = LoadSummary::from_reports(3, &reports);
+        assert_eq!(summary.bots, 3);
+        assert_eq!(summary.connected, 1);
+        assert_eq!(summary.host_users, 3);
+        assert_eq!(summary.host_max_tick_ms, 5.0);
+        assert_eq!(summary.bytes_per_second, 1500.0);
+        assert!((summary.latency_ms - 20.0).abs() < 1e-3);
+        assert!((summary.latency_p95_ms - 30.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_summary_without_reports() {
+        let summary = LoadSummary::from_reports(2, &HashMap::new());
+        assert_eq!(summary.connected, 0);
+        assert_eq!(summary.latency_ms, 0.0);
+    }
+}
@@ -1,19 +1,67 @@
 use std::net::IpAddr;
 
-use bevy::prelude::Resource;
+use bevy::{log::Level, prelude::Resource};
 use clap::{Parser, Subcommand};
 
-#[derive(Parser, Clone, Debug, Resource)]
+/// Port used for sync when none is given, the web transport takes the next ones.
+pub const DEFAULT_PORT: u16 = 4001;
+/// Ports a session takes from its port on: sync, the web server of the host
+/// with the transforms it sends on the same port over UDP, and the web server
+/// of a client joined from the same machine.
+pub const SESSION_PORTS: u16 = 3;
+
+#[derive(Parser, Clone, Debug, PartialEq, Resource)]
 #[command(author, version, about, long_about = None)]
 pub struct Args {
     /// Enable XR/VR
     #[clap(name = "xr", long, default_value_t = false)]
     pub xr_enabled: bool,
+    /// Name shown to the other users.
+    #[clap(long, global = true)]
+    pub name: Option<String>,
+    /// Folder with the WASM plugins and their `plugins.json` config.
+    #[clap(name = "plugins", long, global = true)]
+    pub plugins_dir: Option<String>,
+    /// Hosting, changes of entities farther than this from every user are not sent.
+    #[clap(long, global = true)]
+    pub interest_radius: Option<f32>,
+    /// Transforms sent by hosts and joined clients are delayed by this many
+    /// milliseconds, to test smoothing on bad links. Other updates go through
+    /// bevy_sync and are not delayed.
+    #[clap(long, global = true)]
+    pub sim_latency: Option<u32>,
+    /// Variation of the transform delay in milliseconds.
+    #[clap(long, global = true)]
+    pub sim_jitter: Option<u32>,
+    /// Chance from 0 to 1 that a sent transform datagram is lost.
+    #[clap(long, global = true)]
+    pub sim_loss: Option<f32>,
+    /// Bandwidth in bytes per second the sent transforms share.
+    #[clap(long, global = true)]
+    pub sim_bandwidth: Option<u32>,
+    /// Record the changes of synched components to this file.
+    #[clap(long, global = true)]
+    pub record: Option<String>,
+    /// Lowest level logged: error, warn, info, debug or trace.
+    #[clap(long, global = true)]
+    pub log_level: Option<Level>,
+    /// Log levels per module, like `lux_networking=debug,lux_world=trace`.
+    #[clap(long, global = true)]
+    pub log_filter: Option<String>,
+    /// Also append the logs to this file.
+    #[clap(long, global = true)]
+    pub log_file: Option<String>,
+    /// Write the log file as JSON lines, needs `--log-file`.
+    #[clap(long, global = true, default_value_t = false, requires = "log_file")]
+    pub log_json: bool,
+    /// Your height in meters, the .glb/.gltf avatar is scaled to match it.
+    #[clap(long, global = true)]
+    pub height: Option<f32>,
     #[clap(subcommand)]
     pub command: Option<Command>,
 }
 
-#[derive(Subcommand, Clone, Debug)]
+#[derive(Subcommand, Clone, Debug, PartialEq)]
 pub enum Command {
     #[clap(name = "host")]
     Host {
@@ -21,17 +69,280 @@ pub enum Command {
         #[clap(long, default_value_t = false)]
         headless: bool,
         ip: Option<IpAddr>,
+        #[clap(long, default_value_t = DEFAULT_PORT)]
+        port: u16,
         /// Path to the avatar file.
         /// Supports: .vrm or .glb/.gltf avatars.
         #[clap(name = "avatar", long)]
         avatar_file: Option<String>,
+        /// Serve `/metrics` and `/health` over HTTP on this port, only for
+        /// headless hosts. Off unless given.
+        #[clap(long, requires = "headless")]
+        metrics: Option<u16>,
+        /// Address the metrics are served on, localhost unless given.
+        #[clap(long)]
+        metrics_ip: Option<IpAddr>,
     },
     #[clap(name = "join")]
     Join {
         ip: IpAddr,
+        #[clap(long, default_value_t = DEFAULT_PORT)]
+        port: u16,
         /// Path to the avatar file.
         /// Supports: .vrm or .glb/.gltf avatars.
         #[clap(name = "avatar", long)]
         avatar_file: Option<String>,
+        /// Port of the web transport of this client, by default two after
+        /// the port of the host.
+        #[clap(long)]
+        web_port: Option<u16>,
+    },
+    /// Headless clients joining a host, to load test it.
+    #[clap(name = "bot")]
+    Bot {
+        ip: IpAddr,
+        #[clap(long, default_value_t = DEFAULT_PORT)]
+        port: u16,
+        /// How many clients join.
+        #[clap(long, default_value_t = 10)]
+        count: usize,
+        /// Stop after this many seconds, otherwise run until closed.
+        #[clap(long)]
+        seconds: Option<u64>,
     },
+    /// Plays a recorded session back, without joining or changing anything.
+    #[clap(name = "replay")]
+    Replay {
+        file: String,
+        #[clap(long, default_value_t = 1.0)]
+        speed: f32,
+    },
+}
+
+impl Args {
+    /// Avatar file of either command, if any.
+    pub fn avatar_file(&self) -> Option<&str> {
+        match &self.command {
+            Some(Command::Host { avatar_file, .. }) => avatar_file.as_deref(),
+            Some(Command::Join { avatar_file, .. }) => avatar_file.as_deref(),
+            Some(Command::Bot { .. }) | Some(Command::Replay { .. }) | None => None,
+        }
+    }
+
+    /// Command line that parses back into these same arguments,
+    /// used to relaunch into another session.
+    pub fn to_cli_args(&self) -> Vec<String> {
+        let mut cli = vec![];
+        if self.xr_enabled {
+            cli.push("--xr".to_string());
+        }
+        if let Some(name) = &self.name {
+            cli.extend(["--name".to_string(), name.clone()]);
+        }
+        if let Some(plugins) = &self.plugins_dir {
+            cli.extend(["--plugins".to_string(), plugins.clone()]);
+        }
+        if let Some(radius) = self.interest_radius {
+            cli.extend(["--interest-radius".to_string(), radius.to_string()]);
+        }
+        if let Some(latency) = self.sim_latency {
+            cli.extend(["--sim-latency".to_string(), latency.to_string()]);
+        }
+        if let Some(jitter) = self.sim_jitter {
+            cli.extend(["--sim-jitter".to_string(), jitter.to_string()]);
+        }
+        if let Some(loss) = self.sim_loss {
+            cli.extend(["--sim-loss".to_string(), loss.to_string()]);
+        }
+        if let Some(bandwidth) = self.sim_bandwidth {
+            cli.extend(["--sim-bandwidth".to_string(), bandwidth.to_string()]);
+        }
+        if let Some(record) = &self.record {
+            cli.extend(["--record".to_string(), record.clone()]);
+        }
+        if let Some(level) = self.log_level {
+            cli.extend(["--log-level".to_string(), level.to_string()]);
+        }
+        if let Some(filter) = &self.log_filter {
+            cli.extend(["--log-filter".to_string(), filter.clone()]);
+        }
+        if let Some(file) = &self.log_file {
+            cli.extend(["--log-file".to_string(), file.clone()]);
+        }
+        if self.log_json {
+            cli.push("--log-json".to_string());
+        }
+        if let Some(height) = self.height {
+            cli.extend(["--height".to_string(), height.to_string()]);
+        }
+        match &self.command {
+            Some(Command::Host {
+                world_file,
+                headless,
+                ip,
+                port,
+                avatar_file,
+                metrics,
+                metrics_ip,
+            }) => {
+                cli.extend(["host".to_string(), world_file.clone()]);
+                if let Some(ip) = ip {
+                    cli.push(ip.to_string());
+                }
+                if *headless {
+                    cli.push("--headless".to_string());
+                }
+                cli.extend(["--port".to_string(), port.to_string()]);
+                if let Some(avatar) = avatar_file {
+                    cli.extend(["--avatar".to_string(), avatar.clone()]);
+                }
+                if let Some(metrics) = metrics {
+                    cli.extend(["--metrics".to_string(), metrics.to_string()]);
+                }
+                if let Some(ip) = metrics_ip {
+                    cli.extend(["--metrics-ip".to_string(), ip.to_string()]);
+                }
+            }
+            Some(Command::Join {
+                ip,
+                port,
+                avatar_file,
+                web_port,
+            }) => {
+                cli.extend(["join".to_string(), ip.to_string()]);
+                cli.extend(["--port".to_string(), port.to_string()]);
+                if let Some(avatar) = avatar_file {
+                    cli.extend(["--avatar".to_string(), avatar.clone()]);
+                }
+                if let Some(web_port) = web_port {
+                    cli.extend(["--web-port".to_string(), web_port.to_string()]);
+                }
+            }
+            Some(Command::Bot {
+                ip,
+                port,
+                count,
+                seconds,
+            }) => {
+                cli.extend(["bot".to_string(), ip.to_string()]);
+                cli.extend(["--port".to_string(), port.to_string()]);
+                cli.extend(["--count".to_string(), count.to_string()]);
+                if let Some(seconds) = seconds {
+                    cli.extend(["--seconds".to_string(), seconds.to_string()]);
+                }
+            }
+            Some(Command::Replay { file, speed }) => {
+                cli.extend(["replay".to_string(), file.clone()]);
+                cli.extend(["--speed".to_string(), speed.to_string()]);
+            }
+            None => {}
+        }
+        cli
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_default_port() {
+        let args = Args::parse_from(["lux", "join", "127.0.0.1"]);
+        let Some(Command::Join { port, .. }) = args.command else {
+            panic!("not a join");
+        };
+        assert_eq!(port, DEFAULT_PORT);
+    }
+
+    #[test]
+    fn test_host_roundtrip() {
+        roundtrip(&[
+            "--xr",
+            "--name",
+            "Ada",
+            "host",
+            "world.glb",
+            "::1",
+            "--headless",
+            "--port",
+            "5000",
+            "--avatar",
+            "me.vrm",
+            "--height",
+            "1.82",
+            "--record",
+            "session.jsonl",
+            "--metrics",
+            "9100",
+            "--metrics-ip",
+            "0.0.0.0",
+            "--interest-radius",
+            "75.5",
+        ]);
+    }
+
+    #[test]
+    fn test_metrics_only_headless() {
+        let cli = ["lux", "host", "world.glb", "--metrics", "9100"];
+        assert!(Args::try_parse_from(cli).is_err());
+    }
+
+    #[test]
+    fn test_log_json_needs_file() {
+        assert!(Args::try_parse_from(["lux", "--log-json"]).is_err());
+        assert!(Args::try_parse_from(["lux", "join", "::1", "--log-json"]).is_err());
+        assert!(Args::try_parse_from(["lux", "--log-json", "--log-file", "lux.log"]).is_ok());
+    }
+
+    #[test]
+    fn test_join_roundtrip() {
+        roundtrip(&[
+            "join",
+            "10.0.0.2",
+            "--port",
+            "5000",
+            "--web-port",
+            "5010",
+            "--name",
+            "Ada",
+            "--plugins",
+            "plugins",
+            "--sim-latency",
+            "150",
+            "--sim-jitter",
+            "20",
+            "--sim-loss",
+            "0.05",
+            "--sim-bandwidth",
+            "64000",
+            "--log-level",
+            "debug",
+            "--log-filter",
+            "lux_networking=trace,wgpu=error",
+            "--log-file",
+            "lux.log",
+            "--log-json",
+        ]);
+    }
+
+    #[test]
+    fn test_bot_roundtrip() {
+        roundtrip(&["bot", "::1", "--count", "50", "--seconds", "30"]);
+    }
+
+    #[test]
+    fn test_replay_roundtrip() {
+        roundtrip(&["replay", "session.jsonl", "--speed", "0.5"]);
+    }
+
+    #[test]
+    fn test_no_command_roundtrip() {
+        roundtrip(&[]);
+    }
+
+    fn roundtrip(cli: &[&str]) {
+        let args = Args::parse_from(std::iter::once("lux").chain(cli.iter().copied()));
+        let again = Args::parse_from(std::iter::once("lux".to_string()).chain(args.to_cli_args()));
+        assert_eq!(args, again);
+    }
 }
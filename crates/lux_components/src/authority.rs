@@ -0,0 +1,284 @@
+use std::{any::TypeId, collections::HashMap};
+
+use bevy::{prelude::*, reflect::TypePath};
+use bevy_sync::{SyncComponent, SyncExclude};
+
+use crate::{LocalPeer, LocalUser};
+
+/// A peer driving a component of an entity.
+#[derive(Reflect, Default, Debug, Clone, PartialEq)]
+pub struct ComponentOwner {
+    pub component: String,
+    pub peer: u64,
+}
+
+/// Which peer drives each component of this entity. The owner sends its
+/// values, everybody else only receives them.
+/// Components are named by their type path, which is the same in every build.
+/// Components without an owner are sent by whoever changes them.
+/// Of the remote owned components only Transform is interpolated, by the
+/// smoothing, the others are applied as they are received.
+#[derive(Component, Default, Reflect, Debug, Clone, PartialEq)]
+#[reflect(Component)]
+pub struct Authority {
+    pub owners: Vec<ComponentOwner>,
+}
+
+impl Authority {
+    pub fn claim<C: Component + TypePath>(&mut self, peer: u64) {
+        self.release::<C>();
+        self.owners.push(ComponentOwner {
+            component: C::type_path().to_owned(),
+            peer,
+        });
+    }
+
+    pub fn release<C: Component + TypePath>(&mut self) {
+        self.owners.retain(|o| o.component != C::type_path());
+    }
+
+    pub fn owner<C: Component + TypePath>(&self) -> Option<u64> {
+        self.owner_of(C::type_path())
+    }
+
+    fn owner_of(&self, component: &str) -> Option<u64> {
+        self.owners
+            .iter()
+            .find(|o| o.component == component)
+            .map(|o| o.peer)
+    }
+}
+
+/// Local systems computing a component on every peer, so it is never sent.
+/// Counted per component, see ControlledBy.
+#[derive(Component, Default, Debug)]
+pub(crate) struct LocalControllers {
+    counts: HashMap<TypeId, usize>,
+}
+
+impl LocalControllers {
+    pub(crate) fn add<C: Component>(&mut self) {
+        *self.counts.entry(TypeId::of::<C>()).or_default() += 1;
+    }
+
+    pub(crate) fn remove<C: Component>(&mut self) {
+        if let Some(count) = self.counts.get_mut(&TypeId::of::<C>()) {
+            *count = count.saturating_sub(1);
+        }
+        self.counts.retain(|_, count| *count > 0);
+    }
+
+    pub(crate) fn count<C: Component>(&self) -> usize {
+        self.counts
+            .get(&TypeId::of::<C>())
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+}
+
+pub(crate) fn is_remote<C: Component + TypePath>(
+    authority: Option<&Authority>,
+    local: Option<&LocalPeer>,
+) -> bool {
+    authority
+        .and_then(|a| a.owner::<C>())
+        .is_some_and(|owner| Some(owner) != local.map(|p| p.id))
+}
+
+/// SyncExclude<C> is there when a local system or another peer drives C.
+pub(crate) fn refresh_exclude<C: Component + Default + TypePath>(
+    world: &mut World,
+    entity: Entity,
+) {
+    let local = world.get_resource::<LocalPeer>().copied();
+    let Some(mut entity) = world.get_entity_mut(entity) else {
+        return;
+    };
+    let controlled = entity
+        .get::<LocalControllers>()
+        .is_some_and(|c| c.count::<C>() > 0);
+    let remote = is_remote::<C>(entity.get::<Authority>(), local.as_ref());
+    let excluded = entity.contains::<SyncExclude<C>>();
+    if (controlled || remote) && !excluded {
+        entity.insert(SyncExclude::<C>::default());
+    } else if !(controlled || remote) && excluded {
+        entity.remove::<SyncExclude<C>>();
+    }
+}
+
+pub trait AuthorityAppExt {
+    /// Keeps SyncExclude<C> in line with who drives C.
+    fn add_authority<C: Component + Default + TypePath>(&mut self) -> &mut Self;
+}
+
+impl AuthorityAppExt for App {
+    fn add_authority<C: Component + Default + TypePath>(&mut self) -> &mut Self {
+        self.add_systems(PostUpdate, apply_authority::<C>)
+    }
+}
+
+fn apply_authority<C: Component + Default + TypePath>(
+    mut commands: Commands,
+    changed: Query<Entity, Changed<Authority>>,
+    peer: Option<Res<LocalPeer>>,
+    all: Query<Entity, With<Authority>>,
+) {
+    let peer_changed = peer.is_some_and(|p| p.is_changed());
+    let entities: Vec<Entity> = if peer_changed {
+        all.iter().collect()
+    } else {
+        changed.iter().collect()
+    };
+    for entity in entities {
+        commands.add(move |world: &mut World| refresh_exclude::<C>(world, entity));
+    }
+}
+
+/// The local user is driven by this peer.
+fn claim_local_user(
+    mut commands: Commands,
+    peer: Res<LocalPeer>,
+    mut users: Query<(Entity, Option<&mut Authority>), Added<LocalUser>>,
+) {
+    for (entity, authority) in users.iter_mut() {
+        match authority {
+            Some(mut authority) => authority.claim::<Transform>(peer.id),
+            None => {
+                let mut authority = Authority::default();
+                authority.claim::<Transform>(peer.id);
+                commands.entity(entity).insert(authority);
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct AuthorityPlugin;
+
+impl Plugin for AuthorityPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LocalPeer>();
+        app.register_type::<ComponentOwner>();
+        app.register_type::<Vec<ComponentOwner>>();
+        app.sync_component::<Authority>();
+        // Received transforms are smoothed by SmoothingPlugin.
+        app.add_authority::<Transform>();
+        app.add_systems(Update, claim_local_user);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ControlledBy;
+    use bevy::render::primitives::Aabb;
+
+    const LOCAL: u64 = 1;
+    const REMOTE: u64 = 2;
+
+    #[test]
+    fn test_remote_owner_adds_exclude() {
+        let mut app = setup();
+        let spawn = spawn_owned(&mut app, REMOTE);
+        app.update();
+
+        let spawn = app.world().entity(spawn);
+        assert!(spawn.get::<SyncExclude<Transform>>().is_some());
+    }
+
+    #[test]
+    fn test_local_owner_has_no_exclude() {
+        let mut app = setup();
+        let spawn = spawn_owned(&mut app, LOCAL);
+        app.update();
+
+        let spawn = app.world().entity(spawn);
+        assert!(spawn.get::<SyncExclude<Transform>>().is_none());
+    }
+
+    #[test]
+    fn test_release_removes_exclude() {
+        let mut app = setup();
+        let spawn = spawn_owned(&mut app, REMOTE);
+        app.update();
+        app.world_mut()
+            .get_mut::<Authority>(spawn)
+            .unwrap()
+            .release::<Transform>();
+        app.update();
+
+        let spawn = app.world().entity(spawn);
+        assert!(spawn.get::<SyncExclude<Transform>>().is_none());
+    }
+
+    #[test]
+    fn test_exclude_kept_while_locally_controlled() {
+        let mut app = setup();
+        let spawn = spawn_owned(&mut app, REMOTE);
+        app.world_mut()
+            .entity_mut(spawn)
+            .insert(ControlledBy::<Transform, Aabb>::default());
+        app.update();
+        app.world_mut()
+            .get_mut::<Authority>(spawn)
+            .unwrap()
+            .release::<Transform>();
+        app.update();
+
+        let spawn = app.world().entity(spawn);
+        assert!(spawn.get::<SyncExclude<Transform>>().is_some());
+    }
+
+    #[test]
+    fn test_claim_replaces_owner() {
+        let mut authority = Authority::default();
+        authority.claim::<Transform>(REMOTE);
+        authority.claim::<Transform>(LOCAL);
+        authority.claim::<Name>(REMOTE);
+        assert_eq!(authority.owner::<Transform>(), Some(LOCAL));
+        assert_eq!(authority.owner::<Name>(), Some(REMOTE));
+        assert_eq!(authority.owners.len(), 2);
+    }
+
+    #[test]
+    fn test_local_user_is_claimed() {
+        let mut app = setup();
+        let spawn = app
+            .world_mut()
+            .spawn((Transform::default(), LocalUser))
+            .id();
+        app.update();
+
+        let authority = app.world().entity(spawn).get::<Authority>().unwrap();
+        assert_eq!(authority.owner::<Transform>(), Some(LOCAL));
+    }
+
+    #[test]
+    fn test_owners_named_by_type_path() {
+        let mut authority = Authority::default();
+        authority.claim::<Transform>(REMOTE);
+        assert_eq!(
+            authority.owners[0].component,
+            "bevy_transform::components::transform::Transform"
+        );
+    }
+
+    fn setup() -> App {
+        let mut app = App::new();
+        app.insert_resource(LocalPeer { id: LOCAL });
+        app.add_plugins(AuthorityPlugin);
+        app
+    }
+
+    fn spawn_owned(app: &mut App, peer: u64) -> Entity {
+        let mut authority = Authority::default();
+        authority.claim::<Transform>(peer);
+        app.world_mut()
+            .spawn((Transform::default(), authority))
+            .id()
+    }
+}
@@ -1,79 +1,59 @@
 use bevy::{
     ecs::component::{ComponentHooks, StorageType},
     prelude::*,
+    reflect::TypePath,
 };
-use bevy_sync::SyncExclude;
 use std::marker::PhantomData;
 
+use crate::authority::{refresh_exclude, LocalControllers};
+
 /// This component marks that a component is controlled by another component
 /// This will allow to span or control how many controlling components there
 /// are and if they are zero or 1+, to delete or sidespawn a SyncExclude<C>.
 /// This will not be synched as it is a local only blocker for sending data.
+/// It is the local side of the authority system, see Authority for peers.
 #[derive(Default)]
-pub struct ControlledBy<C: Component + Default, F: Component> {
+pub struct ControlledBy<C: Component + Default + TypePath, F: Component> {
     c: PhantomData<C>,
     f: PhantomData<F>,
 }
 
-#[derive(Component)]
-struct ControlledByCounter {
-    counter: usize,
-}
-
-impl<C: Component + Default, F: Component> Component for ControlledBy<C, F> {
+impl<C: Component + Default + TypePath, F: Component> Component for ControlledBy<C, F> {
     const STORAGE_TYPE: StorageType = StorageType::Table;
     fn register_component_hooks(hooks: &mut ComponentHooks) {
         hooks.on_add(|mut world, entity_id, _component_id| {
-            counter_inc(&mut world, entity_id);
-            world
-                .commands()
-                .entity(entity_id)
-                .insert(SyncExclude::<C>::default());
+            world.commands().add(move |world: &mut World| {
+                let Some(mut entity) = world.get_entity_mut(entity_id) else {
+                    return;
+                };
+                match entity.get_mut::<LocalControllers>() {
+                    Some(mut controllers) => controllers.add::<C>(),
+                    None => {
+                        let mut controllers = LocalControllers::default();
+                        controllers.add::<C>();
+                        entity.insert(controllers);
+                    }
+                }
+                refresh_exclude::<C>(world, entity_id);
+            });
         });
         hooks.on_remove(|mut world, entity_id, _component_id| {
-            if counter_dec_and_get(&mut world, entity_id) == 0 {
-                world
-                    .commands()
-                    .entity(entity_id)
-                    .remove::<ControlledByCounter>()
-                    .remove::<SyncExclude<C>>();
-            }
+            world.commands().add(move |world: &mut World| {
+                let Some(mut entity) = world.get_entity_mut(entity_id) else {
+                    return;
+                };
+                if let Some(mut controllers) = entity.get_mut::<LocalControllers>() {
+                    controllers.remove::<C>();
+                    if controllers.is_empty() {
+                        entity.remove::<LocalControllers>();
+                    }
+                }
+                refresh_exclude::<C>(world, entity_id);
+            });
         });
     }
 }
 
-fn counter_inc(world: &mut bevy::ecs::world::DeferredWorld<'_>, entity_id: Entity) {
-    let entity_mut = &mut world.entity_mut(entity_id);
-    match entity_mut.get_mut::<ControlledByCounter>() {
-        Some(mut c) => {
-            c.counter += 1;
-            c.counter
-        }
-        None => {
-            world
-                .commands()
-                .entity(entity_id)
-                .insert(ControlledByCounter { counter: 1 });
-            1
-        }
-    };
-}
-
-fn counter_dec_and_get(
-    world: &mut bevy::ecs::world::DeferredWorld<'_>,
-    entity_id: Entity,
-) -> usize {
-    let entity_mut = &mut world.entity_mut(entity_id);
-    let counter = match entity_mut.get_mut::<ControlledByCounter>() {
-        Some(mut c) => {
-            c.counter -= 1;
-            c.counter
-        }
-        None => 0,
-    };
-    counter
-}
-
 #[cfg(test)]
 mod test {
     use super::*;
@@ -132,7 +112,7 @@ mod test {
         app.update();
 
         let spawn = app.world().entity(spawn);
-        assert!(spawn.get::<ControlledByCounter>().is_some());
+        assert_eq!(spawn.get::<LocalControllers>().unwrap().count::<Name>(), 1);
         assert!(spawn.get::<SyncExclude<Name>>().is_some());
     }
 
@@ -161,4 +141,28 @@ mod test {
         let spawn = app.world().entity(spawn);
         assert!(spawn.get::<SyncExclude<Name>>().is_none());
     }
+
+    #[test]
+    fn test_controlled_by_counts_each_component() {
+        let mut app = App::new();
+        let spawn = app
+            .world_mut()
+            .spawn((
+                Name::new(""),
+                Transform::default(),
+                ControlledBy::<Name, Aabb>::default(),
+                ControlledBy::<Transform, Aabb>::default(),
+            ))
+            .id();
+        app.update();
+        app.world_mut()
+            .commands()
+            .entity(spawn)
+            .remove::<ControlledBy<Transform, Aabb>>();
+        app.update();
+
+        let spawn = app.world().entity(spawn);
+        assert!(spawn.get::<SyncExclude<Name>>().is_some());
+        assert!(spawn.get::<SyncExclude<Transform>>().is_none());
+    }
 }
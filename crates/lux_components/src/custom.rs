@@ -0,0 +1,368 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use bevy_sync::{SyncComponent, SyncMark};
+
+/// Types a field of a custom component can have.
+#[derive(Reflect, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldType {
+    Bool,
+    Int,
+    Float,
+    Text,
+    Vec3,
+}
+
+impl FieldType {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "bool" => Some(Self::Bool),
+            "int" => Some(Self::Int),
+            "float" => Some(Self::Float),
+            "text" => Some(Self::Text),
+            "vec3" => Some(Self::Vec3),
+            _ => None,
+        }
+    }
+
+    pub fn default_value(&self) -> CustomValue {
+        match self {
+            Self::Bool => CustomValue::Bool(false),
+            Self::Int => CustomValue::Int(0),
+            Self::Float => CustomValue::Float(0.0),
+            Self::Text => CustomValue::Text(String::new()),
+            Self::Vec3 => CustomValue::Vec3(Vec3::ZERO),
+        }
+    }
+}
+
+#[derive(Reflect, Debug, Clone, PartialEq)]
+pub enum CustomValue {
+    Bool(bool),
+    Int(i64),
+    Float(f32),
+    Text(String),
+    Vec3(Vec3),
+}
+
+impl Default for CustomValue {
+    fn default() -> Self {
+        Self::Bool(false)
+    }
+}
+
+impl CustomValue {
+    pub fn field_type(&self) -> FieldType {
+        match self {
+            Self::Bool(_) => FieldType::Bool,
+            Self::Int(_) => FieldType::Int,
+            Self::Float(_) => FieldType::Float,
+            Self::Text(_) => FieldType::Text,
+            Self::Vec3(_) => FieldType::Vec3,
+        }
+    }
+}
+
+/// A component declared by the world package: a name and typed fields.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CustomComponentDef {
+    pub name: String,
+    pub fields: Vec<(String, FieldType)>,
+}
+
+impl CustomComponentDef {
+    /// A new instance with every field at its default.
+    pub fn instance(&self) -> CustomComponent {
+        CustomComponent {
+            name: self.name.clone(),
+            fields: self
+                .fields
+                .iter()
+                .map(|(name, ty)| CustomField {
+                    name: name.clone(),
+                    value: ty.default_value(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Components the loaded world declares.
+#[derive(Resource, Debug, Clone, Default, PartialEq)]
+pub struct CustomComponentDefs(pub Vec<CustomComponentDef>);
+
+impl CustomComponentDefs {
+    pub fn get(&self, name: &str) -> Option<&CustomComponentDef> {
+        self.0.iter().find(|d| d.name == name)
+    }
+}
+
+#[derive(Reflect, Default, Debug, Clone, PartialEq)]
+pub struct CustomField {
+    pub name: String,
+    pub value: CustomValue,
+}
+
+/// One custom component. It is also the synced child entity that carries
+/// it, so that peers editing different components of an entity at once
+/// don't overwrite each other.
+#[derive(Component, Reflect, Default, Debug, Clone, PartialEq)]
+#[reflect(Component)]
+pub struct CustomComponent {
+    pub name: String,
+    pub fields: Vec<CustomField>,
+}
+
+/// The custom components of an entity. Values carry their type, so peers
+/// that don't know the declarations can still show and edit them.
+/// This is a local view kept in step with the CustomComponent children,
+/// which are what is synced.
+#[derive(Component, Default, Reflect, Debug, Clone, PartialEq)]
+#[reflect(Component)]
+pub struct CustomComponents {
+    pub components: Vec<CustomComponent>,
+}
+
+impl CustomComponents {
+    pub fn get(&self, component: &str, field: &str) -> Option<&CustomValue> {
+        self.components
+            .iter()
+            .find(|c| c.name == component)?
+            .fields
+            .iter()
+            .find(|f| f.name == field)
+            .map(|f| &f.value)
+    }
+
+    /// Only existing fields can be set, and only with a value of their type.
+    pub fn set(&mut self, component: &str, field: &str, value: CustomValue) -> bool {
+        let Some(existing) = self
+            .components
+            .iter_mut()
+            .find(|c| c.name == component)
+            .and_then(|c| c.fields.iter_mut().find(|f| f.name == field))
+        else {
+            return false;
+        };
+        if existing.value.field_type() != value.field_type() {
+            return false;
+        }
+        existing.value = value;
+        true
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct CustomComponentsPlugin;
+
+impl Plugin for CustomComponentsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CustomComponentDefs>();
+        app.register_type::<FieldType>();
+        app.register_type::<CustomValue>();
+        app.register_type::<CustomField>();
+        app.register_type::<Vec<CustomField>>();
+        app.register_type::<CustomComponent>();
+        app.register_type::<Vec<CustomComponent>>();
+        app.register_type::<CustomComponents>();
+        app.sync_component::<CustomComponent>();
+        app.add_systems(PostUpdate, sync_custom_components);
+    }
+}
+
+/// Entries changed by peers go into the view of their parent first, so that
+/// pushing the local changes after does not revert them. Both are one
+/// system, so that it does not see its own writes as changes.
+fn sync_custom_components(
+    mut cmd: Commands,
+    mut entries: Query<(&mut CustomComponent, Option<Ref<Parent>>)>,
+    mut owners: Query<(Entity, &mut CustomComponents, Option<&Children>)>,
+) {
+    let mut new_owners = HashMap::<Entity, CustomComponents>::new();
+    for (entry, parent) in entries.iter_mut() {
+        let Some(parent) = parent else {
+            continue;
+        };
+        if !entry.is_changed() && !parent.is_changed() {
+            continue;
+        }
+        let mut owner = owners.get_mut(parent.get());
+        let components = match owner.as_mut() {
+            Ok((_, owner, _)) => owner.as_mut(),
+            Err(_) => new_owners.entry(parent.get()).or_default(),
+        };
+        let existing = components
+            .components
+            .iter()
+            .position(|c| c.name == entry.name);
+        match existing {
+            Some(i) if components.components[i] == *entry => {}
+            // Created from this view, which may have changed since.
+            Some(_) if entry.is_added() => {}
+            Some(i) => components.components[i] = entry.clone(),
+            None => components.components.push(entry.clone()),
+        }
+    }
+    for (owner, components) in new_owners {
+        if let Some(mut e) = cmd.get_entity(owner) {
+            e.insert(components);
+        }
+    }
+
+    // Local changes go to the entries of the components that changed only.
+    for (owner, custom, children) in owners.iter_mut() {
+        if !custom.is_changed() {
+            continue;
+        }
+        let children: Vec<Entity> = children
+            .map(|c| c.iter().copied().filter(|c| entries.contains(*c)).collect())
+            .unwrap_or_default();
+        for child in children.iter() {
+            let Ok((mut entry, _)) = entries.get_mut(*child) else {
+                continue;
+            };
+            match custom.components.iter().find(|c| c.name == entry.name) {
+                Some(component) => {
+                    entry.set_if_neq(component.clone());
+                }
+                None => cmd.entity(*child).despawn_recursive(),
+            }
+        }
+        for component in custom.components.iter() {
+            let exists = children
+                .iter()
+                .any(|c| entries.get(*c).is_ok_and(|(e, _)| e.name == component.name));
+            if !exists {
+                let entry = cmd
+                    .spawn((
+                        Name::new(format!("Custom:{}", component.name)),
+                        component.clone(),
+                        SyncMark,
+                    ))
+                    .id();
+                cmd.entity(owner).add_child(entry);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_instance_has_defaults() {
+        let def = door();
+        let components = CustomComponents {
+            components: vec![def.instance()],
+        };
+        assert_eq!(
+            components.get("Door", "open"),
+            Some(&CustomValue::Bool(false))
+        );
+        assert_eq!(
+            components.get("Door", "speed"),
+            Some(&CustomValue::Float(0.0))
+        );
+        assert_eq!(components.get("Door", "color"), None);
+        assert_eq!(components.get("Lamp", "open"), None);
+    }
+
+    #[test]
+    fn test_set_keeps_types() {
+        let mut components = CustomComponents {
+            components: vec![door().instance()],
+        };
+        assert!(components.set("Door", "open", CustomValue::Bool(true)));
+        assert!(!components.set("Door", "open", CustomValue::Int(1)));
+        assert!(!components.set("Door", "locked", CustomValue::Bool(true)));
+        assert_eq!(
+            components.get("Door", "open"),
+            Some(&CustomValue::Bool(true))
+        );
+    }
+
+    #[test]
+    fn test_components_synced_one_by_one() {
+        let mut app = App::new();
+        app.add_plugins(CustomComponentsPlugin);
+        let owner = app
+            .world_mut()
+            .spawn(CustomComponents {
+                components: vec![door().instance(), lamp().instance()],
+            })
+            .id();
+        app.update();
+        assert_eq!(entries(&mut app, owner).len(), 2);
+        // Peers get the entries a frame later at the earliest.
+        app.update();
+
+        // A peer turns the lamp on while the door is opened here.
+        let lamp_entry = entries(&mut app, owner)
+            .into_iter()
+            .find(|(_, c)| c.name == "Lamp")
+            .unwrap()
+            .0;
+        let mut lamp = app
+            .world()
+            .get::<CustomComponent>(lamp_entry)
+            .unwrap()
+            .clone();
+        lamp.fields[0].value = CustomValue::Bool(true);
+        app.world_mut().entity_mut(lamp_entry).insert(lamp);
+        app.world_mut()
+            .get_mut::<CustomComponents>(owner)
+            .unwrap()
+            .set("Door", "open", CustomValue::Bool(true));
+        app.update();
+
+        let custom = app.world().get::<CustomComponents>(owner).unwrap();
+        assert_eq!(custom.get("Door", "open"), Some(&CustomValue::Bool(true)));
+        assert_eq!(custom.get("Lamp", "on"), Some(&CustomValue::Bool(true)));
+        let entries = entries(&mut app, owner);
+        assert!(entries
+            .iter()
+            .all(|(_, c)| c.fields[0].value == CustomValue::Bool(true)));
+    }
+
+    #[test]
+    fn test_components_received_from_peers() {
+        let mut app = App::new();
+        app.add_plugins(CustomComponentsPlugin);
+        let owner = app.world_mut().spawn_empty().id();
+        for component in [door().instance(), lamp().instance()] {
+            app.world_mut().spawn(component).set_parent(owner);
+        }
+        app.update();
+
+        let custom = app.world().get::<CustomComponents>(owner).unwrap();
+        assert_eq!(custom.components.len(), 2);
+        assert_eq!(entries(&mut app, owner).len(), 2);
+    }
+
+    fn entries(app: &mut App, owner: Entity) -> Vec<(Entity, CustomComponent)> {
+        app.world_mut()
+            .query::<(Entity, &CustomComponent, &Parent)>()
+            .iter(app.world())
+            .filter(|(_, _, parent)| parent.get() == owner)
+            .map(|(e, c, _)| (e, c.clone()))
+            .collect()
+    }
+
+    fn lamp() -> CustomComponentDef {
+        CustomComponentDef {
+            name: "Lamp".to_owned(),
+            fields: vec![("on".to_owned(), FieldType::Bool)],
+        }
+    }
+
+    fn door() -> CustomComponentDef {
+        CustomComponentDef {
+            name: "Door".to_owned(),
+            fields: vec![
+                ("open".to_owned(), FieldType::Bool),
+                ("speed".to_owned(), FieldType::Float),
+            ],
+        }
+    }
+}
@@ -1,17 +1,54 @@
+pub use authority::{Authority, AuthorityAppExt, ComponentOwner};
 pub use controlled_by::ControlledBy;
+pub use custom::{
+    CustomComponent, CustomComponentDef, CustomComponentDefs, CustomComponents, CustomField,
+    CustomValue, FieldType,
+};
 pub use local_user::LocalUser;
+pub use peer::{peer_color, LocalPeer};
+pub use picking::{ray_aabb, ray_hits_aabb, PickRequest, PickResult, PickSelect, PickSource};
+pub use portal::Portal;
 pub use reference::ComponentEntityRef;
+pub use selection::{Selected, SelectedBy, Selection, SelectionEvent};
+pub use smoothing::{SmoothTransform, SmoothingSet, TransformSmoothing, TransformSnapshot};
+pub use spawn_point::{pick_free_spawn, SpawnPoint, VoidLevel, DEFAULT_VOID_LEVEL};
+pub use tick_times::{time_ticks, TickTimes, TickWindow};
 pub use user::User;
+pub use uuid_assets::{AddByUuid, AssetSources};
 
+mod authority;
 mod controlled_by;
+mod custom;
 mod local_user;
+mod peer;
+mod picking;
+mod portal;
 mod reference;
+mod selection;
+mod smoothing;
+mod spawn_point;
+mod tick_times;
 mod user;
+mod uuid_assets;
 
+use authority::AuthorityPlugin;
+use custom::CustomComponentsPlugin;
 use local_user::LocalUserPlugin;
+use picking::PickingPlugin;
+use portal::PortalPlugin;
+use selection::SelectionPlugin;
+use smoothing::SmoothingPlugin;
+use spawn_point::SpawnPointPlugin;
 use user::UserPlugin;
 
 pub fn init(app: &mut bevy::prelude::App) {
     app.add_plugins(LocalUserPlugin);
     app.add_plugins(UserPlugin);
+    app.add_plugins(SelectionPlugin);
+    app.add_plugins(PickingPlugin);
+    app.add_plugins(SpawnPointPlugin);
+    app.add_plugins(PortalPlugin);
+    app.add_plugins(CustomComponentsPlugin);
+    app.add_plugins(AuthorityPlugin);
+    app.add_plugins(SmoothingPlugin);
 }
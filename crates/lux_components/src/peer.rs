@@ -0,0 +1,23 @@
+use bevy::prelude::*;
+use bevy_sync::Uuid;
+
+/// Identifies this running instance among the peers of a session.
+/// It is random per run and only used to tell apart what each peer does
+/// on shared entities, as synched components refer to peers by this id.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LocalPeer {
+    pub id: u64,
+}
+
+impl Default for LocalPeer {
+    fn default() -> Self {
+        Self {
+            id: Uuid::new_v4().as_u64_pair().0,
+        }
+    }
+}
+
+/// Stable color to tell peers apart visually.
+pub fn peer_color(id: u64) -> Color {
+    Color::hsl((id % 360) as f32, 0.9, 0.6)
+}
@@ -0,0 +1,256 @@
+use bevy::{prelude::*, render::primitives::Aabb};
+use bevy_sync::{SyncEntity, SyncMark};
+
+use crate::SelectionEvent;
+
+/// Where a pick ray originates from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PickSource {
+    Desktop,
+    LeftHand,
+    RightHand,
+}
+
+/// How a pick changes the selection, if at all.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PickSelect {
+    Replace,
+    Add,
+    Toggle,
+}
+
+/// Casts a ray against the bounds of synched entities.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct PickRequest {
+    pub ray: Ray3d,
+    pub source: PickSource,
+    pub select: Option<PickSelect>,
+}
+
+/// Answer to a PickRequest, with the closest entity hit if any.
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub struct PickResult {
+    pub source: PickSource,
+    /// Selection mode of the request, None when it was an interaction.
+    pub select: Option<PickSelect>,
+    pub entity: Option<Entity>,
+    pub point: Vec3,
+    pub distance: f32,
+}
+
+#[derive(Default)]
+pub(crate) struct PickingPlugin;
+
+impl Plugin for PickingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<PickRequest>();
+        app.add_event::<PickResult>();
+        app.add_systems(Update, pick);
+    }
+}
+
+type Pickable = Or<(With<SyncMark>, With<SyncEntity>)>;
+
+fn pick(
+    mut requests: EventReader<PickRequest>,
+    mut results: EventWriter<PickResult>,
+    mut selections: EventWriter<SelectionEvent>,
+    targets: Query<(Entity, &Aabb, &GlobalTransform), Pickable>,
+) {
+    for request in requests.read() {
+        let hit = targets
+            .iter()
+            .filter_map(|(e, aabb, gt)| ray_hits_aabb(request.ray, aabb, gt).map(|t| (e, t)))
+            .min_by(|a, b| a.1.total_cmp(&b.1));
+        let (entity, distance) = match hit {
+            Some((e, t)) => (Some(e), t),
+            None => (None, f32::INFINITY),
+        };
+        results.send(PickResult {
+            source: request.source,
+            select: request.select,
+            entity,
+            point: request.ray.get_point(distance.min(f32::MAX)),
+            distance,
+        });
+        match (request.select, entity) {
+            (Some(PickSelect::Replace), Some(e)) => {
+                selections.send(SelectionEvent::Replace(e));
+            }
+            (Some(PickSelect::Replace), None) => {
+                selections.send(SelectionEvent::Clear);
+            }
+            (Some(PickSelect::Add), Some(e)) => {
+                selections.send(SelectionEvent::Add(e));
+            }
+            (Some(PickSelect::Toggle), Some(e)) => {
+                selections.send(SelectionEvent::Toggle(e));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Distance along the ray to the entity bounds, if hit.
+/// The ray is moved in the local space of the entity, where the Aabb is
+/// defined, so rotated and scaled entities are picked on their real bounds.
+pub fn ray_hits_aabb(ray: Ray3d, aabb: &Aabb, gt: &GlobalTransform) -> Option<f32> {
+    let inverse = gt.affine().inverse();
+    let origin = inverse.transform_point3(ray.origin);
+    let direction = inverse.transform_vector3(*ray.direction);
+    let min = Vec3::from(aabb.min());
+    let max = Vec3::from(aabb.max());
+    ray_aabb(origin, direction, min, max)
+}
+
+/// Slab test of a ray against an axis aligned box.
+/// Returns the ray parameter of the first hit in front of the origin,
+/// or zero when the origin is inside the box.
+pub fn ray_aabb(origin: Vec3, direction: Vec3, min: Vec3, max: Vec3) -> Option<f32> {
+    let mut t_min = 0.0f32;
+    let mut t_max = f32::INFINITY;
+    for axis in 0..3 {
+        let o = origin[axis];
+        let d = direction[axis];
+        if d.abs() < f32::EPSILON {
+            if o < min[axis] || o > max[axis] {
+                return None;
+            }
+            continue;
+        }
+        let t1 = (min[axis] - o) / d;
+        let t2 = (max[axis] - o) / d;
+        t_min = t_min.max(t1.min(t2));
+        t_max = t_max.min(t1.max(t2));
+        if t_min > t_max {
+            return None;
+        }
+    }
+    Some(t_min)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::selection::{Selected, SelectionPlugin};
+
+    #[test]
+    fn test_ray_hits_box_in_front() {
+        let t = ray_aabb(Vec3::new(0.0, 0.0, 5.0), -Vec3::Z, -Vec3::ONE, Vec3::ONE);
+        assert_eq!(t, Some(4.0));
+    }
+
+    #[test]
+    fn test_ray_misses_box_behind() {
+        let t = ray_aabb(Vec3::new(0.0, 0.0, 5.0), Vec3::Z, -Vec3::ONE, Vec3::ONE);
+        assert_eq!(t, None);
+    }
+
+    #[test]
+    fn test_ray_misses_box_aside() {
+        let t = ray_aabb(Vec3::new(3.0, 0.0, 5.0), -Vec3::Z, -Vec3::ONE, Vec3::ONE);
+        assert_eq!(t, None);
+    }
+
+    #[test]
+    fn test_ray_from_inside_box() {
+        let t = ray_aabb(Vec3::ZERO, Vec3::X, -Vec3::ONE, Vec3::ONE);
+        assert_eq!(t, Some(0.0));
+    }
+
+    #[test]
+    fn test_ray_hits_translated_entity() {
+        let aabb = Aabb::from_min_max(-Vec3::ONE, Vec3::ONE);
+        let gt = GlobalTransform::from_translation(Vec3::new(10.0, 0.0, 0.0));
+        let ray = Ray3d::new(Vec3::new(10.0, 0.0, 5.0), -Vec3::Z);
+        assert_eq!(ray_hits_aabb(ray, &aabb, &gt), Some(4.0));
+        let ray = Ray3d::new(Vec3::new(0.0, 0.0, 5.0), -Vec3::Z);
+        assert_eq!(ray_hits_aabb(ray, &aabb, &gt), None);
+    }
+
+    #[test]
+    fn test_ray_hits_scaled_entity() {
+        let aabb = Aabb::from_min_max(-Vec3::ONE, Vec3::ONE);
+        let gt = GlobalTransform::from_scale(Vec3::splat(3.0));
+        let ray = Ray3d::new(Vec3::new(2.5, 0.0, 5.0), -Vec3::Z);
+        let t = ray_hits_aabb(ray, &aabb, &gt).unwrap();
+        assert!((t - 2.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_pick_selects_closest_entity() {
+        let mut app = setup();
+        let near = spawn_box(&mut app, 2.0);
+        let far = spawn_box(&mut app, -2.0);
+        request(&mut app, 0.0, Some(PickSelect::Replace));
+
+        assert!(app.world().entity(near).get::<Selected>().is_some());
+        assert!(app.world().entity(far).get::<Selected>().is_none());
+    }
+
+    #[test]
+    fn test_pick_miss_clears_selection() {
+        let mut app = setup();
+        let entity = spawn_box(&mut app, 0.0);
+        request(&mut app, 0.0, Some(PickSelect::Replace));
+        assert!(app.world().entity(entity).get::<Selected>().is_some());
+
+        request(&mut app, 10.0, Some(PickSelect::Replace));
+        assert!(app.world().entity(entity).get::<Selected>().is_none());
+    }
+
+    #[test]
+    fn test_pick_without_selection_reports_result() {
+        let mut app = setup();
+        let entity = spawn_box(&mut app, 0.0);
+        request(&mut app, 0.0, None);
+
+        assert!(app.world().entity(entity).get::<Selected>().is_none());
+        let results = app.world().resource::<Events<PickResult>>();
+        let result = results.get_reader().read(results).last().copied().unwrap();
+        assert_eq!(result.entity, Some(entity));
+        assert_eq!(result.distance, 4.0);
+        assert_eq!(result.point, Vec3::new(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn test_pick_ignores_non_synched_entities() {
+        let mut app = setup();
+        let entity = app
+            .world_mut()
+            .spawn((
+                Aabb::from_min_max(-Vec3::ONE, Vec3::ONE),
+                GlobalTransform::default(),
+            ))
+            .id();
+        request(&mut app, 0.0, Some(PickSelect::Add));
+
+        assert!(app.world().entity(entity).get::<Selected>().is_none());
+    }
+
+    fn setup() -> App {
+        let mut app = App::new();
+        app.add_plugins(SelectionPlugin);
+        app.add_plugins(PickingPlugin);
+        app
+    }
+
+    fn spawn_box(app: &mut App, z: f32) -> Entity {
+        app.world_mut()
+            .spawn((
+                SyncMark,
+                Aabb::from_min_max(-Vec3::ONE, Vec3::ONE),
+                GlobalTransform::from_translation(Vec3::new(0.0, 0.0, z)),
+            ))
+            .id()
+    }
+
+    fn request(app: &mut App, x: f32, select: Option<PickSelect>) {
+        app.world_mut().send_event(PickRequest {
+            ray: Ray3d::new(Vec3::new(x, 0.0, 5.0), -Vec3::Z),
+            source: PickSource::Desktop,
+            select,
+        });
+        app.update();
+    }
+}
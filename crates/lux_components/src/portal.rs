@@ -0,0 +1,19 @@
+use bevy::prelude::*;
+use bevy_sync::SyncComponent;
+
+/// Entering this entity moves the user to another session.
+/// The target is either `ip:port` of a host to join, or a world file to host.
+#[derive(Component, Default, Reflect, Debug, Clone, PartialEq)]
+#[reflect(Component)]
+pub struct Portal {
+    pub target: String,
+}
+
+#[derive(Default)]
+pub(crate) struct PortalPlugin;
+
+impl Plugin for PortalPlugin {
+    fn build(&self, app: &mut App) {
+        app.sync_component::<Portal>();
+    }
+}
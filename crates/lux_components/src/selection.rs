@@ -0,0 +1,256 @@
+use bevy::prelude::*;
+use bevy_sync::{SyncComponent, SyncMark};
+
+use crate::LocalPeer;
+
+/// Marks an entity as selected by the local user.
+/// This is local only, other peers see it through SelectedBy.
+#[derive(Component, Default, Debug)]
+pub struct Selected;
+
+/// Synced child of an entity selected by a peer, one per peer so that
+/// concurrent selections do not overwrite each other.
+#[derive(Component, Default, Reflect, Debug, Clone, PartialEq)]
+#[reflect(Component)]
+pub struct SelectedBy {
+    pub peer: u64,
+}
+
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub enum SelectionEvent {
+    /// Select only this entity
+    Replace(Entity),
+    /// Add the entity to the current selection
+    Add(Entity),
+    /// Add the entity if not selected, remove it otherwise
+    Toggle(Entity),
+    Remove(Entity),
+    Clear,
+}
+
+/// Entities with Selected, in the order they were selected.
+#[derive(Resource, Default, Debug)]
+pub struct Selection {
+    entities: Vec<Entity>,
+}
+
+impl Selection {
+    /// The last selected entity, the one tools like inspectors act on.
+    pub fn primary(&self) -> Option<Entity> {
+        self.entities.last().copied()
+    }
+
+    pub fn entities(&self) -> &[Entity] {
+        &self.entities
+    }
+
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.entities.contains(&entity)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct SelectionPlugin;
+
+impl Plugin for SelectionPlugin {
+    fn build(&self, app: &mut App) {
+        app.sync_component::<SelectedBy>();
+        app.init_resource::<LocalPeer>();
+        app.init_resource::<Selection>();
+        app.add_event::<SelectionEvent>();
+        app.add_systems(
+            PostUpdate,
+            (prune_selection, apply_selection_events, publish_selection).chain(),
+        );
+    }
+}
+
+fn prune_selection(mut selection: ResMut<Selection>, selected: Query<(), With<Selected>>) {
+    if selection.entities.iter().any(|e| !selected.contains(*e)) {
+        selection.entities.retain(|e| selected.contains(*e));
+    }
+}
+
+fn apply_selection_events(
+    mut cmd: Commands,
+    mut events: EventReader<SelectionEvent>,
+    mut selection: ResMut<Selection>,
+) {
+    for event in events.read() {
+        match *event {
+            SelectionEvent::Replace(entity) => {
+                deselect_all(&mut cmd, &mut selection);
+                select(&mut cmd, &mut selection, entity);
+            }
+            SelectionEvent::Add(entity) => select(&mut cmd, &mut selection, entity),
+            SelectionEvent::Toggle(entity) => {
+                if selection.contains(entity) {
+                    deselect(&mut cmd, &mut selection, entity);
+                } else {
+                    select(&mut cmd, &mut selection, entity);
+                }
+            }
+            SelectionEvent::Remove(entity) => deselect(&mut cmd, &mut selection, entity),
+            SelectionEvent::Clear => deselect_all(&mut cmd, &mut selection),
+        }
+    }
+}
+
+fn select(cmd: &mut Commands, selection: &mut Selection, entity: Entity) {
+    if let Some(mut e) = cmd.get_entity(entity) {
+        e.insert(Selected);
+        selection.entities.retain(|s| *s != entity);
+        selection.entities.push(entity);
+    }
+}
+
+fn deselect(cmd: &mut Commands, selection: &mut Selection, entity: Entity) {
+    if let Some(mut e) = cmd.get_entity(entity) {
+        e.remove::<Selected>();
+    }
+    selection.entities.retain(|s| *s != entity);
+}
+
+fn deselect_all(cmd: &mut Commands, selection: &mut Selection) {
+    for entity in std::mem::take(&mut selection.entities) {
+        if let Some(mut e) = cmd.get_entity(entity) {
+            e.remove::<Selected>();
+        }
+    }
+}
+
+fn publish_selection(
+    mut cmd: Commands,
+    peer: Res<LocalPeer>,
+    added: Query<Entity, Added<Selected>>,
+    mut removed: RemovedComponents<Selected>,
+    markers: Query<(Entity, &SelectedBy, &Parent)>,
+) {
+    for entity in added.iter() {
+        let marker = cmd
+            .spawn((
+                Name::new("Selection"),
+                SelectedBy { peer: peer.id },
+                SyncMark,
+            ))
+            .id();
+        cmd.entity(entity).add_child(marker);
+    }
+    for entity in removed.read() {
+        for (marker, by, parent) in markers.iter() {
+            if by.peer == peer.id && parent.get() == entity {
+                cmd.entity(marker).despawn_recursive();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_replace_selection() {
+        let mut app = setup();
+        let a = app.world_mut().spawn_empty().id();
+        let b = app.world_mut().spawn_empty().id();
+        send(&mut app, SelectionEvent::Replace(a));
+        send(&mut app, SelectionEvent::Replace(b));
+
+        assert!(app.world().entity(a).get::<Selected>().is_none());
+        assert!(app.world().entity(b).get::<Selected>().is_some());
+        assert_eq!(app.world().resource::<Selection>().entities(), &[b]);
+    }
+
+    #[test]
+    fn test_multi_selection() {
+        let mut app = setup();
+        let a = app.world_mut().spawn_empty().id();
+        let b = app.world_mut().spawn_empty().id();
+        send(&mut app, SelectionEvent::Replace(a));
+        send(&mut app, SelectionEvent::Add(b));
+
+        let selection = app.world().resource::<Selection>();
+        assert_eq!(selection.entities(), &[a, b]);
+        assert_eq!(selection.primary(), Some(b));
+    }
+
+    #[test]
+    fn test_toggle_selection() {
+        let mut app = setup();
+        let a = app.world_mut().spawn_empty().id();
+        send(&mut app, SelectionEvent::Toggle(a));
+        assert!(app.world().entity(a).get::<Selected>().is_some());
+        send(&mut app, SelectionEvent::Toggle(a));
+        assert!(app.world().entity(a).get::<Selected>().is_none());
+        assert!(app.world().resource::<Selection>().is_empty());
+    }
+
+    #[test]
+    fn test_clear_selection() {
+        let mut app = setup();
+        let a = app.world_mut().spawn_empty().id();
+        let b = app.world_mut().spawn_empty().id();
+        send(&mut app, SelectionEvent::Add(a));
+        send(&mut app, SelectionEvent::Add(b));
+        send(&mut app, SelectionEvent::Clear);
+
+        assert!(app.world().entity(a).get::<Selected>().is_none());
+        assert!(app.world().entity(b).get::<Selected>().is_none());
+        assert!(app.world().resource::<Selection>().is_empty());
+    }
+
+    #[test]
+    fn test_despawned_entities_leave_selection() {
+        let mut app = setup();
+        let a = app.world_mut().spawn_empty().id();
+        send(&mut app, SelectionEvent::Add(a));
+        app.world_mut().despawn(a);
+        app.update();
+
+        assert!(app.world().resource::<Selection>().is_empty());
+    }
+
+    #[test]
+    fn test_selection_published_to_peers() {
+        let mut app = setup();
+        let peer = app.world().resource::<LocalPeer>().id;
+        let a = app.world_mut().spawn_empty().id();
+        app.world_mut()
+            .spawn((SelectedBy { peer: 7 }, SyncMark))
+            .set_parent(a);
+        send(&mut app, SelectionEvent::Add(a));
+        assert_eq!(selected_by(&mut app, a), vec![7, peer]);
+
+        send(&mut app, SelectionEvent::Remove(a));
+        assert_eq!(selected_by(&mut app, a), vec![7]);
+    }
+
+    fn selected_by(app: &mut App, entity: Entity) -> Vec<u64> {
+        let mut peers: Vec<u64> = app
+            .world_mut()
+            .query::<(&SelectedBy, &Parent)>()
+            .iter(app.world())
+            .filter(|(_, parent)| parent.get() == entity)
+            .map(|(by, _)| by.peer)
+            .collect();
+        peers.sort();
+        peers
+    }
+
+    fn setup() -> App {
+        let mut app = App::new();
+        app.add_plugins(SelectionPlugin);
+        app.update();
+        app
+    }
+
+    fn send(app: &mut App, event: SelectionEvent) {
+        app.world_mut().send_event(event);
+        app.update();
+    }
+}
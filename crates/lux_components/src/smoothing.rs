@@ -0,0 +1,400 @@
+use std::collections::VecDeque;
+
+use bevy::{prelude::*, transform::TransformSystem};
+
+use crate::{authority::is_remote, Authority, ControlledBy, LocalPeer};
+
+/// Snapshots kept per entity, older ones are dropped first.
+const MAX_SNAPSHOTS: usize = 32;
+
+/// How received transforms are shown.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct TransformSmoothing {
+    /// Seconds the shown transform lags behind the received ones, so that
+    /// there is usually a newer snapshot to interpolate towards.
+    pub delay: f32,
+    /// Seconds past the last snapshot that motion is continued for,
+    /// when updates stop arriving.
+    pub max_extrapolation: f32,
+    /// Jumps longer than this are shown right away instead of smoothed.
+    pub teleport_distance: f32,
+}
+
+impl Default for TransformSmoothing {
+    fn default() -> Self {
+        Self {
+            delay: 0.1,
+            max_extrapolation: 0.25,
+            teleport_distance: 4.0,
+        }
+    }
+}
+
+/// The transform of this entity is received from another peer, for example
+/// physics replicas, and is never sent from here.
+/// Transforms owned by another peer through Authority are smoothed as well.
+#[derive(Component, Default, Debug, Clone, Copy)]
+pub struct SmoothTransform;
+
+/// A transform received at `time`, in seconds of elapsed Time.
+#[derive(Event, Debug, Clone, PartialEq)]
+pub struct TransformSnapshot {
+    pub entity: Entity,
+    pub time: f64,
+    pub transform: Transform,
+}
+
+/// Received transforms are recorded as snapshots, then buffered to be shown.
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SmoothingSet {
+    Record,
+    Buffer,
+}
+
+#[derive(Component, Debug, Default)]
+pub(crate) struct SnapshotBuffer {
+    snapshots: VecDeque<(f64, Transform)>,
+    shown: Option<Transform>,
+}
+
+impl SnapshotBuffer {
+    fn push(&mut self, time: f64, transform: Transform, settings: &TransformSmoothing) {
+        if let Some((last_time, last)) = self.snapshots.back().copied() {
+            if time < last_time {
+                return;
+            }
+            if last.translation.distance(transform.translation) > settings.teleport_distance {
+                self.snapshots.clear();
+            } else if time - last_time > (settings.delay + settings.max_extrapolation) as f64 {
+                // Changes are only sent when they happen: after a long quiet
+                // the entity was standing still, not slowly moving.
+                self.snapshots
+                    .push_back((time - settings.delay as f64, last));
+            }
+        }
+        self.snapshots.push_back((time, transform));
+        if self.snapshots.len() > MAX_SNAPSHOTS {
+            self.snapshots.pop_front();
+        }
+    }
+
+    /// Only the last snapshot before `time` is needed to interpolate from.
+    fn prune(&mut self, time: f64) {
+        while self.snapshots.len() > 2 && self.snapshots[1].0 <= time {
+            self.snapshots.pop_front();
+        }
+    }
+
+    fn sample(&self, time: f64, max_extrapolation: f32) -> Option<Transform> {
+        let (first_time, first) = self.snapshots.front()?;
+        if time <= *first_time {
+            return Some(*first);
+        }
+        let pairs = self.snapshots.iter().zip(self.snapshots.iter().skip(1));
+        for ((a_time, a), (b_time, b)) in pairs {
+            if time <= *b_time {
+                let t = ((time - a_time) / (b_time - a_time).max(f64::EPSILON)) as f32;
+                return Some(lerp(a, b, t));
+            }
+        }
+        let (last_time, last) = self.snapshots.back()?;
+        let ahead = (time - last_time) as f32;
+        if self.snapshots.len() < 2 || ahead > max_extrapolation {
+            return Some(*last);
+        }
+        let (prev_time, prev) = self.snapshots[self.snapshots.len() - 2];
+        let span = (last_time - prev_time).max(f64::EPSILON) as f32;
+        Some(extrapolate(&prev, last, ahead / span))
+    }
+}
+
+fn lerp(a: &Transform, b: &Transform, t: f32) -> Transform {
+    Transform {
+        translation: a.translation.lerp(b.translation, t),
+        rotation: a.rotation.slerp(b.rotation, t),
+        scale: a.scale.lerp(b.scale, t),
+    }
+}
+
+/// Continues the motion from `prev` to `last` by `t` times that step.
+fn extrapolate(prev: &Transform, last: &Transform, t: f32) -> Transform {
+    let (axis, angle) = (last.rotation * prev.rotation.inverse()).to_axis_angle();
+    Transform {
+        translation: last.translation + (last.translation - prev.translation) * t,
+        rotation: (Quat::from_axis_angle(axis, angle * t) * last.rotation).normalize(),
+        scale: last.scale,
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct SmoothingPlugin;
+
+impl Plugin for SmoothingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TransformSmoothing>();
+        app.add_event::<TransformSnapshot>();
+        app.add_systems(Update, exclude_smoothed);
+        app.add_systems(
+            PostUpdate,
+            (
+                record_received.in_set(SmoothingSet::Record),
+                buffer_snapshots.in_set(SmoothingSet::Buffer),
+                smooth_transforms,
+            )
+                .chain()
+                .run_if(resource_exists::<Time>)
+                .before(TransformSystem::TransformPropagate),
+        );
+    }
+}
+
+fn exclude_smoothed(mut commands: Commands, added: Query<Entity, Added<SmoothTransform>>) {
+    for entity in added.iter() {
+        commands
+            .entity(entity)
+            .insert(ControlledBy::<Transform, SmoothTransform>::default());
+    }
+}
+
+/// A transform different from the one shown last frame came from the network.
+/// Only changed entities are looked at, unless the local peer changed.
+#[allow(clippy::type_complexity)]
+fn record_received(
+    mut commands: Commands,
+    time: Res<Time>,
+    peer: Option<Res<LocalPeer>>,
+    query: Query<(
+        Entity,
+        &Transform,
+        Option<&SnapshotBuffer>,
+        Option<&Authority>,
+        Has<SmoothTransform>,
+    )>,
+    changed: Query<
+        Entity,
+        Or<(
+            Changed<Transform>,
+            Changed<Authority>,
+            Added<SmoothTransform>,
+        )>,
+    >,
+    mut unmarked: RemovedComponents<SmoothTransform>,
+    mut snapshots: EventWriter<TransformSnapshot>,
+) {
+    let peer_changed = peer.as_ref().is_some_and(|p| p.is_changed());
+    let entities: Vec<Entity> = if peer_changed {
+        query.iter().map(|(entity, ..)| entity).collect()
+    } else {
+        changed.iter().chain(unmarked.read()).collect()
+    };
+    for (entity, transform, buffer, authority, marked) in query.iter_many(entities) {
+        let smoothed = marked || is_remote::<Transform>(authority, peer.as_deref());
+        match (smoothed, buffer) {
+            (true, None) => {
+                commands.entity(entity).insert(SnapshotBuffer::default());
+            }
+            (false, Some(_)) => {
+                commands.entity(entity).remove::<SnapshotBuffer>();
+                continue;
+            }
+            (false, None) => continue,
+            (true, Some(_)) => (),
+        }
+        if buffer.and_then(|b| b.shown) != Some(*transform) {
+            snapshots.send(TransformSnapshot {
+                entity,
+                time: time.elapsed_seconds_f64(),
+                transform: *transform,
+            });
+        }
+    }
+}
+
+fn buffer_snapshots(
+    mut commands: Commands,
+    settings: Res<TransformSmoothing>,
+    mut snapshots: EventReader<TransformSnapshot>,
+    mut buffers: Query<&mut SnapshotBuffer>,
+) {
+    for snapshot in snapshots.read() {
+        match buffers.get_mut(snapshot.entity) {
+            Ok(mut buffer) => {
+                buffer.push(snapshot.time, snapshot.transform, &settings);
+            }
+            Err(_) => {
+                let Some(mut entity) = commands.get_entity(snapshot.entity) else {
+                    continue;
+                };
+                let mut buffer = SnapshotBuffer::default();
+                buffer.push(snapshot.time, snapshot.transform, &settings);
+                entity.insert(buffer);
+            }
+        }
+    }
+}
+
+fn smooth_transforms(
+    time: Res<Time>,
+    settings: Res<TransformSmoothing>,
+    mut query: Query<(&mut Transform, &mut SnapshotBuffer)>,
+) {
+    let render_time = time.elapsed_seconds_f64() - settings.delay as f64;
+    for (mut transform, mut buffer) in query.iter_mut() {
+        buffer.prune(render_time);
+        let Some(shown) = buffer.sample(render_time, settings.max_extrapolation) else {
+            continue;
+        };
+        buffer.shown = Some(shown);
+        if *transform != shown {
+            *transform = shown;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bevy_sync::SyncExclude;
+    use std::time::Duration;
+
+    #[test]
+    fn test_interpolates_between_snapshots() {
+        let (mut app, entity) = setup();
+        feed(&mut app, entity, 1.0, 0.0);
+        feed(&mut app, entity, 1.2, 2.0);
+        advance_to(&mut app, 1.2);
+
+        assert_x(&app, entity, 1.0);
+    }
+
+    #[test]
+    fn test_extrapolates_when_updates_stop() {
+        let (mut app, entity) = setup();
+        feed(&mut app, entity, 1.0, 0.0);
+        feed(&mut app, entity, 1.1, 1.0);
+        advance_to(&mut app, 1.25);
+
+        assert_x(&app, entity, 1.5);
+    }
+
+    #[test]
+    fn test_extrapolation_is_limited() {
+        let (mut app, entity) = setup();
+        feed(&mut app, entity, 1.0, 0.0);
+        feed(&mut app, entity, 1.1, 1.0);
+        advance_to(&mut app, 2.0);
+
+        assert_x(&app, entity, 1.0);
+    }
+
+    #[test]
+    fn test_teleport_is_not_smoothed() {
+        let (mut app, entity) = setup();
+        feed(&mut app, entity, 1.0, 0.0);
+        feed(&mut app, entity, 1.1, 100.0);
+        advance_to(&mut app, 1.1);
+
+        assert_x(&app, entity, 100.0);
+    }
+
+    #[test]
+    fn test_received_values_are_delayed() {
+        let (mut app, entity) = setup();
+        advance_to(&mut app, 1.0);
+        // As if received from the network.
+        app.world_mut()
+            .get_mut::<Transform>(entity)
+            .unwrap()
+            .translation
+            .x = 1.0;
+        advance_to(&mut app, 1.05);
+        assert_x(&app, entity, 0.0);
+        advance_to(&mut app, 1.1);
+        assert_x(&app, entity, 0.5);
+        advance_to(&mut app, 2.0);
+        assert_x(&app, entity, 1.0);
+    }
+
+    #[test]
+    fn test_smoothed_transform_is_not_sent() {
+        let (app, entity) = setup();
+        let entity = app.world().entity(entity);
+        assert!(entity.get::<SyncExclude<Transform>>().is_some());
+    }
+
+    #[test]
+    fn test_local_transforms_are_left_alone() {
+        let (mut app, _) = setup();
+        let local = app.world_mut().spawn(Transform::default()).id();
+        app.update();
+        app.world_mut()
+            .get_mut::<Transform>(local)
+            .unwrap()
+            .translation
+            .x = 1.0;
+        advance_to(&mut app, 0.01);
+
+        assert_x(&app, local, 1.0);
+        assert!(app.world().get::<SnapshotBuffer>(local).is_none());
+    }
+
+    #[test]
+    fn test_only_changed_transforms_recorded() {
+        let (mut app, entity) = setup();
+        advance_to(&mut app, 1.0);
+        advance_to(&mut app, 2.0);
+        let mut authority = Authority::default();
+        authority.claim::<Transform>(2);
+        let remote = app
+            .world_mut()
+            .spawn((Transform::default(), authority))
+            .id();
+        advance_to(&mut app, 2.1);
+        assert!(app.world().get::<SnapshotBuffer>(remote).is_some());
+
+        let snapshots = app.world().resource::<Events<TransformSnapshot>>();
+        let mut reader = snapshots.get_reader_current();
+        app.world_mut()
+            .get_mut::<Transform>(remote)
+            .unwrap()
+            .translation
+            .x = 1.0;
+        advance_to(&mut app, 2.2);
+        let snapshots = app.world().resource::<Events<TransformSnapshot>>();
+        let recorded: Vec<Entity> = reader.read(snapshots).map(|s| s.entity).collect();
+        assert_eq!(recorded, vec![remote]);
+        assert_x(&app, entity, 0.0);
+    }
+
+    fn setup() -> (App, Entity) {
+        let mut app = App::new();
+        app.init_resource::<Time>();
+        app.add_plugins(SmoothingPlugin);
+        let entity = app
+            .world_mut()
+            .spawn((Transform::default(), SmoothTransform))
+            .id();
+        app.update();
+        (app, entity)
+    }
+
+    fn feed(app: &mut App, entity: Entity, time: f64, x: f32) {
+        app.world_mut().send_event(TransformSnapshot {
+            entity,
+            time,
+            transform: Transform::from_xyz(x, 0.0, 0.0),
+        });
+    }
+
+    fn advance_to(app: &mut App, seconds: f64) {
+        app.world_mut()
+            .resource_mut::<Time>()
+            .advance_to(Duration::from_secs_f64(seconds));
+        app.update();
+    }
+
+    fn assert_x(app: &App, entity: Entity, x: f32) {
+        let actual = app.world().get::<Transform>(entity).unwrap().translation.x;
+        assert!((actual - x).abs() < 1e-4, "expected {} got {}", x, actual);
+    }
+}
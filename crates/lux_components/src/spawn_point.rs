@@ -0,0 +1,84 @@
+use bevy::prelude::*;
+use bevy_sync::SyncComponent;
+
+/// Below this height users are sent back to a spawn point,
+/// unless the world declares its own VoidLevel.
+pub const DEFAULT_VOID_LEVEL: f32 = -20.0;
+
+/// A place where users appear when joining or falling off the world.
+#[derive(Component, Default, Reflect, Debug, Clone, Copy, PartialEq)]
+#[reflect(Component)]
+pub struct SpawnPoint;
+
+/// Height below which users respawn, declared by the world.
+#[derive(Component, Default, Reflect, Debug, Clone, Copy, PartialEq)]
+#[reflect(Component)]
+pub struct VoidLevel {
+    pub y: f32,
+}
+
+#[derive(Default)]
+pub(crate) struct SpawnPointPlugin;
+
+impl Plugin for SpawnPointPlugin {
+    fn build(&self, app: &mut App) {
+        app.sync_component::<SpawnPoint>();
+        app.sync_component::<VoidLevel>();
+    }
+}
+
+/// Picks the first spawn point that has nobody within `radius`,
+/// or the one farthest from everybody when they are all taken.
+pub fn pick_free_spawn(points: &[Vec3], users: &[Vec3], radius: f32) -> Option<Vec3> {
+    let clearance = |p: &Vec3| {
+        users
+            .iter()
+            .map(|u| u.distance(*p))
+            .fold(f32::INFINITY, f32::min)
+    };
+    points
+        .iter()
+        .find(|p| clearance(p) > radius)
+        .or_else(|| {
+            points
+                .iter()
+                .max_by(|a, b| clearance(a).total_cmp(&clearance(b)))
+        })
+        .copied()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_no_spawn_points() {
+        assert_eq!(pick_free_spawn(&[], &[Vec3::ZERO], 1.0), None);
+    }
+
+    #[test]
+    fn test_first_point_when_empty() {
+        let points = [Vec3::X, Vec3::Y];
+        assert_eq!(pick_free_spawn(&points, &[], 1.0), Some(Vec3::X));
+    }
+
+    #[test]
+    fn test_skips_occupied_points() {
+        let points = [Vec3::ZERO, Vec3::new(5.0, 0.0, 0.0)];
+        let users = [Vec3::new(0.2, 0.0, 0.0)];
+        assert_eq!(
+            pick_free_spawn(&points, &users, 1.0),
+            Some(Vec3::new(5.0, 0.0, 0.0))
+        );
+    }
+
+    #[test]
+    fn test_farthest_when_all_occupied() {
+        let points = [Vec3::ZERO, Vec3::new(2.0, 0.0, 0.0)];
+        let users = [Vec3::new(0.1, 0.0, 0.0), Vec3::new(2.5, 0.0, 0.0)];
+        assert_eq!(
+            pick_free_spawn(&points, &users, 1.0),
+            Some(Vec3::new(2.0, 0.0, 0.0))
+        );
+    }
+}
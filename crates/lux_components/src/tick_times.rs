@@ -0,0 +1,100 @@
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+
+/// Ticks measured together, from First to Last.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TickWindow {
+    pub started: Instant,
+    pub total: Duration,
+    pub max: Duration,
+    pub ticks: u32,
+}
+
+impl TickWindow {
+    fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            total: Duration::ZERO,
+            max: Duration::ZERO,
+            ticks: 0,
+        }
+    }
+
+    pub fn average(&self) -> Duration {
+        self.total / self.ticks.max(1)
+    }
+}
+
+/// How long the ticks take, shared by everything reporting it.
+#[derive(Resource, Debug, Clone)]
+pub struct TickTimes {
+    current: TickWindow,
+    last: Option<TickWindow>,
+    start: Option<Instant>,
+}
+
+impl Default for TickTimes {
+    fn default() -> Self {
+        Self {
+            current: TickWindow::new(),
+            last: None,
+            start: None,
+        }
+    }
+}
+
+impl TickTimes {
+    /// The last full second, or the ticks so far in the first one.
+    pub fn latest(&self) -> &TickWindow {
+        self.last.as_ref().unwrap_or(&self.current)
+    }
+}
+
+/// Measures the ticks, once however many crates ask for it.
+pub fn time_ticks(app: &mut App) {
+    if app.world().contains_resource::<TickTimes>() {
+        return;
+    }
+    app.init_resource::<TickTimes>();
+    app.add_systems(First, start_tick);
+    app.add_systems(Last, end_tick);
+}
+
+fn start_tick(mut times: ResMut<TickTimes>) {
+    times.start = Some(Instant::now());
+}
+
+fn end_tick(mut times: ResMut<TickTimes>) {
+    let Some(start) = times.start.take() else {
+        return;
+    };
+    let elapsed = start.elapsed();
+    let current = &mut times.current;
+    current.total += elapsed;
+    current.max = current.max.max(elapsed);
+    current.ticks += 1;
+    if current.started.elapsed() >= Duration::from_secs(1) {
+        times.last = Some(std::mem::replace(&mut times.current, TickWindow::new()));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_ticks_measured_once() {
+        let mut app = App::new();
+        time_ticks(&mut app);
+        time_ticks(&mut app);
+        app.add_systems(Update, || std::thread::sleep(Duration::from_millis(2)));
+        app.update();
+        app.update();
+
+        let ticks = *app.world().resource::<TickTimes>().latest();
+        assert_eq!(ticks.ticks, 2);
+        assert!(ticks.average() >= Duration::from_millis(2));
+        assert!(ticks.max >= ticks.average());
+    }
+}
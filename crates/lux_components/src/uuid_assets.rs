@@ -0,0 +1,38 @@
+use std::collections::HashMap;
+
+use bevy::{
+    asset::{AssetPath, UntypedAssetId},
+    prelude::*,
+};
+use bevy_sync::Uuid;
+
+/// Adds an asset under a random uuid and returns a weak handle to it.
+/// Assets need to be addressed by uuid to be synched across peers.
+pub trait AddByUuid<A: Asset> {
+    fn addu(&mut self, asset: A) -> Handle<A>;
+}
+
+impl<A: Asset> AddByUuid<A> for Assets<A> {
+    fn addu(&mut self, asset: A) -> Handle<A> {
+        let id = AssetId::Uuid {
+            uuid: Uuid::new_v4(),
+        };
+        self.insert(id, asset);
+        Handle::<A>::Weak(id)
+    }
+}
+
+/// Files that assets given a uuid were loaded from, so that recordings can
+/// point at them.
+#[derive(Resource, Debug, Default)]
+pub struct AssetSources(HashMap<UntypedAssetId, String>);
+
+impl AssetSources {
+    pub fn insert<A: Asset>(&mut self, id: AssetId<A>, path: &AssetPath) {
+        self.0.insert(id.untyped(), path.to_string());
+    }
+
+    pub fn get<A: Asset>(&self, id: AssetId<A>) -> Option<&str> {
+        self.0.get(&id.untyped()).map(String::as_str)
+    }
+}
@@ -0,0 +1,68 @@
+use bevy::prelude::*;
+use lux_avatar_generic::bones::{Head, Target};
+use lux_components::LocalUser;
+use lux_desktop_camera::NoClip;
+
+/// Without a headset the camera is the head of the local avatar, the rest
+/// of the body is estimated from it.
+pub(crate) struct CameraAvatarPlugin;
+
+impl Plugin for CameraAvatarPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, camera_to_head);
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn camera_to_head(
+    cameras: Query<&GlobalTransform, With<NoClip>>,
+    mut heads: Query<(&mut Transform, &Parent), (With<Target<Head>>, With<LocalUser>)>,
+    parents: Query<&GlobalTransform, Without<NoClip>>,
+) {
+    let Some(camera) = cameras.iter().next() else {
+        return;
+    };
+    // Cameras look down -Z, avatars face +Z.
+    let turned = camera.compute_transform().rotation * Quat::from_rotation_y(180f32.to_radians());
+    for (mut head, parent) in heads.iter_mut() {
+        let Ok(parent) = parents.get(parent.get()) else {
+            continue;
+        };
+        let (_, parent_rotation, _) = parent.to_scale_rotation_translation();
+        head.translation = parent
+            .affine()
+            .inverse()
+            .transform_point3(camera.translation());
+        head.rotation = parent_rotation.inverse() * turned;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_head_follows_camera() {
+        let mut app = App::new();
+        app.add_plugins(CameraAvatarPlugin);
+        let camera_at = Transform::from_xyz(1.0, 1.7, 2.0);
+        app.world_mut()
+            .spawn((NoClip::default(), GlobalTransform::from(camera_at)));
+        let armature = app
+            .world_mut()
+            .spawn(GlobalTransform::from_xyz(1.0, 0.0, 0.0))
+            .id();
+        let head = app
+            .world_mut()
+            .spawn((Transform::default(), Target::<Head>::default(), LocalUser))
+            .set_parent(armature)
+            .id();
+        app.update();
+
+        let head = app.world().get::<Transform>(head).unwrap();
+        assert_eq!(head.translation, Vec3::new(0.0, 1.7, 2.0));
+        assert!(head
+            .rotation
+            .abs_diff_eq(Quat::from_rotation_y(180f32.to_radians()), 1e-5));
+    }
+}
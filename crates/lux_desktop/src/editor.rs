@@ -0,0 +1,604 @@
+use bevy::{color::ColorToComponents, prelude::*};
+use bevy_egui::{egui, EguiContexts};
+use bevy_sync::{SyncEntity, SyncMark};
+use lux_components::{
+    AddByUuid, CustomComponent, CustomComponents, CustomValue, SelectedBy, Selection,
+    SelectionEvent,
+};
+use lux_desktop_camera::NoClip;
+
+use crate::menu::MenuState;
+
+/// Distance in front of the camera where new entities are spawned.
+const SPAWN_DISTANCE: f32 = 3.0;
+
+pub(crate) struct EditorPlugin;
+
+impl Plugin for EditorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<EditorAction>();
+        app.add_systems(
+            Update,
+            (render_hierarchy, render_inspector)
+                .chain()
+                .run_if(in_state(MenuState::Editor)),
+        );
+        app.add_systems(Update, apply_editor_actions.after(render_inspector));
+    }
+}
+
+/// Structural edits are queued as events and applied on entities
+/// carrying SyncMark, so that the sync layer propagates them to peers.
+#[derive(Event, Debug, Clone, PartialEq)]
+pub(crate) enum EditorAction {
+    SpawnCube,
+    SpawnLight,
+    Duplicate(Entity),
+    Delete(Entity),
+}
+
+type Synced = Or<(With<SyncMark>, With<SyncEntity>)>;
+type HierarchyQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        Option<&'static Name>,
+        Option<&'static Children>,
+        Option<&'static Parent>,
+    ),
+    (Synced, Without<SelectedBy>, Without<CustomComponent>),
+>;
+
+fn render_hierarchy(
+    mut contexts: EguiContexts,
+    selection: Res<Selection>,
+    mut select: EventWriter<SelectionEvent>,
+    mut actions: EventWriter<EditorAction>,
+    nodes: HierarchyQuery,
+) {
+    egui::SidePanel::left("lux_editor_hierarchy").show(contexts.ctx_mut(), |ui| {
+        ui.heading("Hierarchy");
+        ui.horizontal(|ui| {
+            if ui.button("+ Cube").clicked() {
+                actions.send(EditorAction::SpawnCube);
+            }
+            if ui.button("+ Light").clicked() {
+                actions.send(EditorAction::SpawnLight);
+            }
+        });
+        ui.separator();
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for (entity, _, _, parent) in nodes.iter() {
+                let is_root = parent.map(|p| !nodes.contains(p.get())).unwrap_or(true);
+                if is_root {
+                    hierarchy_node(ui, entity, &nodes, &selection, &mut select);
+                }
+            }
+        });
+    });
+}
+
+fn hierarchy_node(
+    ui: &mut egui::Ui,
+    entity: Entity,
+    nodes: &HierarchyQuery,
+    selection: &Selection,
+    select: &mut EventWriter<SelectionEvent>,
+) {
+    let Ok((_, name, children, _)) = nodes.get(entity) else {
+        return;
+    };
+    let label = display_name(entity, name);
+    let selected = selection.contains(entity);
+    let children: Vec<Entity> = children
+        .map(|c| c.iter().copied().filter(|c| nodes.contains(*c)).collect())
+        .unwrap_or_default();
+    if children.is_empty() {
+        let response = ui.selectable_label(selected, label);
+        if response.clicked() {
+            select.send(click_selection(ui, entity));
+        }
+        return;
+    }
+    let id = ui.make_persistent_id(entity);
+    egui::collapsing_header::CollapsingState::load_with_default_open(ui.ctx(), id, false)
+        .show_header(ui, |ui| {
+            let response = ui.selectable_label(selected, label);
+            if response.clicked() {
+                select.send(click_selection(ui, entity));
+            }
+        })
+        .body(|ui| {
+            for child in children {
+                hierarchy_node(ui, child, nodes, selection, select);
+            }
+        });
+}
+
+/// Same modifiers as clicking in the viewport: Shift adds, Ctrl toggles.
+fn click_selection(ui: &egui::Ui, entity: Entity) -> SelectionEvent {
+    let modifiers = ui.input(|i| i.modifiers);
+    if modifiers.shift {
+        SelectionEvent::Add(entity)
+    } else if modifiers.command {
+        SelectionEvent::Toggle(entity)
+    } else {
+        SelectionEvent::Replace(entity)
+    }
+}
+
+fn display_name(entity: Entity, name: Option<&Name>) -> String {
+    match name {
+        Some(name) => format!("{} ({})", name.as_str(), entity),
+        None => format!("{}", entity),
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn render_inspector(
+    mut contexts: EguiContexts,
+    selection: Res<Selection>,
+    mut actions: EventWriter<EditorAction>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut query: Query<
+        (
+            Option<&mut Name>,
+            Option<&mut Transform>,
+            Option<&mut PointLight>,
+            Option<&mut SpotLight>,
+            Option<&mut DirectionalLight>,
+            Option<&Handle<StandardMaterial>>,
+            Option<&mut CustomComponents>,
+        ),
+        Synced,
+    >,
+) {
+    let Some(entity) = selection.primary() else {
+        return;
+    };
+    let Ok((name, transform, point, spot, directional, material, custom)) = query.get_mut(entity)
+    else {
+        return;
+    };
+    egui::SidePanel::right("lux_editor_inspector").show(contexts.ctx_mut(), |ui| {
+        ui.heading("Inspector");
+        ui.horizontal(|ui| {
+            if ui.button("Duplicate").clicked() {
+                actions.send(EditorAction::Duplicate(entity));
+            }
+            if ui.button("Delete").clicked() {
+                actions.send(EditorAction::Delete(entity));
+            }
+        });
+        ui.separator();
+        if let Some(name) = name {
+            inspect_name(ui, name);
+        }
+        if let Some(transform) = transform {
+            inspect_transform(ui, transform);
+        }
+        if let Some(light) = point {
+            inspect_point_light(ui, light);
+        }
+        if let Some(light) = spot {
+            inspect_spot_light(ui, light);
+        }
+        if let Some(light) = directional {
+            inspect_directional_light(ui, light);
+        }
+        if let Some(handle) = material {
+            inspect_material(ui, handle, &mut materials);
+        }
+        if let Some(custom) = custom {
+            inspect_custom(ui, custom);
+        }
+    });
+}
+
+// Every inspector works on a copy and writes back only on an actual edit,
+// otherwise change detection would make the sync layer resend every frame.
+
+fn inspect_name(ui: &mut egui::Ui, mut name: Mut<Name>) {
+    let mut edit = name.as_str().to_string();
+    ui.horizontal(|ui| {
+        ui.label("Name");
+        if ui.text_edit_singleline(&mut edit).changed() {
+            name.set(edit);
+        }
+    });
+}
+
+fn inspect_transform(ui: &mut egui::Ui, mut transform: Mut<Transform>) {
+    let mut edit = *transform;
+    let (y, x, z) = edit.rotation.to_euler(EulerRot::YXZ);
+    let mut euler = Vec3::new(x.to_degrees(), y.to_degrees(), z.to_degrees());
+    let mut changed = false;
+    let mut rotated = false;
+    ui.collapsing("Transform", |ui| {
+        changed |= vec3_row(ui, "Translation", &mut edit.translation, 0.05);
+        rotated |= vec3_row(ui, "Rotation", &mut euler, 1.0);
+        changed |= vec3_row(ui, "Scale", &mut edit.scale, 0.05);
+    });
+    if rotated {
+        edit.rotation = Quat::from_euler(
+            EulerRot::YXZ,
+            euler.y.to_radians(),
+            euler.x.to_radians(),
+            euler.z.to_radians(),
+        );
+    }
+    if changed || rotated {
+        *transform = edit;
+    }
+}
+
+fn inspect_point_light(ui: &mut egui::Ui, mut light: Mut<PointLight>) {
+    let mut edit = *light;
+    let mut changed = false;
+    ui.collapsing("Point Light", |ui| {
+        changed |= color_row(ui, "Color", &mut edit.color);
+        changed |= f32_row(ui, "Intensity", &mut edit.intensity, 1000.0);
+        changed |= f32_row(ui, "Range", &mut edit.range, 0.1);
+        changed |= ui.checkbox(&mut edit.shadows_enabled, "Shadows").changed();
+    });
+    if changed {
+        *light = edit;
+    }
+}
+
+fn inspect_spot_light(ui: &mut egui::Ui, mut light: Mut<SpotLight>) {
+    let mut edit = *light;
+    let mut changed = false;
+    ui.collapsing("Spot Light", |ui| {
+        changed |= color_row(ui, "Color", &mut edit.color);
+        changed |= f32_row(ui, "Intensity", &mut edit.intensity, 1000.0);
+        changed |= f32_row(ui, "Range", &mut edit.range, 0.1);
+        changed |= f32_row(ui, "Inner angle", &mut edit.inner_angle, 0.01);
+        changed |= f32_row(ui, "Outer angle", &mut edit.outer_angle, 0.01);
+        changed |= ui.checkbox(&mut edit.shadows_enabled, "Shadows").changed();
+    });
+    if changed {
+        *light = edit;
+    }
+}
+
+fn inspect_directional_light(ui: &mut egui::Ui, mut light: Mut<DirectionalLight>) {
+    let mut edit = light.clone();
+    let mut changed = false;
+    ui.collapsing("Directional Light", |ui| {
+        changed |= color_row(ui, "Color", &mut edit.color);
+        changed |= f32_row(ui, "Illuminance", &mut edit.illuminance, 100.0);
+        changed |= ui.checkbox(&mut edit.shadows_enabled, "Shadows").changed();
+    });
+    if changed {
+        *light = edit;
+    }
+}
+
+fn inspect_material(
+    ui: &mut egui::Ui,
+    handle: &Handle<StandardMaterial>,
+    materials: &mut Assets<StandardMaterial>,
+) {
+    let Some(material) = materials.get(handle) else {
+        return;
+    };
+    let mut base_color = material.base_color;
+    let mut metallic = material.metallic;
+    let mut roughness = material.perceptual_roughness;
+    let mut changed = false;
+    ui.collapsing("Material", |ui| {
+        changed |= color_row(ui, "Base color", &mut base_color);
+        changed |= ui
+            .add(egui::Slider::new(&mut metallic, 0.0..=1.0).text("Metallic"))
+            .changed();
+        changed |= ui
+            .add(egui::Slider::new(&mut roughness, 0.0..=1.0).text("Roughness"))
+            .changed();
+    });
+    if !changed {
+        return;
+    }
+    if let Some(material) = materials.get_mut(handle) {
+        material.base_color = base_color;
+        material.metallic = metallic;
+        material.perceptual_roughness = roughness;
+    }
+}
+
+/// Fields are edited by their type, the set of fields is fixed by the world.
+fn inspect_custom(ui: &mut egui::Ui, mut custom: Mut<CustomComponents>) {
+    let mut edit = custom.clone();
+    let mut changed = false;
+    for component in edit.components.iter_mut() {
+        ui.collapsing(component.name.as_str(), |ui| {
+            for field in component.fields.iter_mut() {
+                let label = field.name.as_str();
+                changed |= match &mut field.value {
+                    CustomValue::Bool(v) => ui.checkbox(v, label).changed(),
+                    CustomValue::Int(v) => {
+                        ui.horizontal(|ui| {
+                            ui.label(label);
+                            ui.add(egui::DragValue::new(v)).changed()
+                        })
+                        .inner
+                    }
+                    CustomValue::Float(v) => f32_row(ui, label, v, 0.05),
+                    CustomValue::Text(v) => {
+                        ui.horizontal(|ui| {
+                            ui.label(label);
+                            ui.text_edit_singleline(v).changed()
+                        })
+                        .inner
+                    }
+                    CustomValue::Vec3(v) => vec3_row(ui, label, v, 0.05),
+                };
+            }
+        });
+    }
+    if changed {
+        *custom = edit;
+    }
+}
+
+fn vec3_row(ui: &mut egui::Ui, label: &str, value: &mut Vec3, speed: f32) -> bool {
+    ui.horizontal(|ui| {
+        ui.label(label);
+        let x = ui.add(egui::DragValue::new(&mut value.x).speed(speed));
+        let y = ui.add(egui::DragValue::new(&mut value.y).speed(speed));
+        let z = ui.add(egui::DragValue::new(&mut value.z).speed(speed));
+        x.changed() || y.changed() || z.changed()
+    })
+    .inner
+}
+
+fn f32_row(ui: &mut egui::Ui, label: &str, value: &mut f32, speed: f32) -> bool {
+    ui.horizontal(|ui| {
+        ui.label(label);
+        ui.add(egui::DragValue::new(value).speed(speed)).changed()
+    })
+    .inner
+}
+
+fn color_row(ui: &mut egui::Ui, label: &str, color: &mut Color) -> bool {
+    let mut rgba = color.to_srgba().to_f32_array();
+    let changed = ui
+        .horizontal(|ui| {
+            ui.label(label);
+            ui.color_edit_button_rgba_unmultiplied(&mut rgba).changed()
+        })
+        .inner;
+    if changed {
+        *color = Color::srgba(rgba[0], rgba[1], rgba[2], rgba[3]);
+    }
+    changed
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn apply_editor_actions(
+    mut commands: Commands,
+    mut events: EventReader<EditorAction>,
+    mut select: EventWriter<SelectionEvent>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    camera: Query<&Transform, With<NoClip>>,
+    sources: Query<
+        (
+            Option<&Name>,
+            Option<&Transform>,
+            Option<&Handle<Mesh>>,
+            Option<&Handle<StandardMaterial>>,
+            Option<&PointLight>,
+            Option<&SpotLight>,
+            Option<&DirectionalLight>,
+            Option<&CustomComponents>,
+        ),
+        Synced,
+    >,
+) {
+    for action in events.read() {
+        match action {
+            EditorAction::SpawnCube => {
+                let id = commands
+                    .spawn((
+                        PbrBundle {
+                            mesh: meshes.addu(Mesh::from(Cuboid::new(1.0, 1.0, 1.0))),
+                            material: materials.addu(Color::srgb(0.8, 0.8, 0.8).into()),
+                            transform: spawn_transform(&camera),
+                            ..default()
+                        },
+                        SyncMark,
+                        Name::new("Cube"),
+                    ))
+                    .id();
+                select.send(SelectionEvent::Replace(id));
+            }
+            EditorAction::SpawnLight => {
+                let id = commands
+                    .spawn((
+                        PointLightBundle {
+                            transform: spawn_transform(&camera),
+                            ..default()
+                        },
+                        SyncMark,
+                        Name::new("Light"),
+                    ))
+                    .id();
+                select.send(SelectionEvent::Replace(id));
+            }
+            EditorAction::Duplicate(source) => {
+                let Ok((name, transform, mesh, material, point, spot, directional, custom)) =
+                    sources.get(*source)
+                else {
+                    continue;
+                };
+                let mut copy = commands.spawn((
+                    SpatialBundle {
+                        transform: transform.copied().unwrap_or_default(),
+                        ..default()
+                    },
+                    SyncMark,
+                ));
+                if let Some(name) = name {
+                    copy.insert(Name::new(format!("{} (copy)", name.as_str())));
+                }
+                if let Some(mesh) = mesh {
+                    copy.insert(mesh.clone());
+                }
+                // Its own material, editing the copy must not change the source.
+                if let Some(material) = material.and_then(|h| materials.get(h)).cloned() {
+                    copy.insert(materials.addu(material));
+                }
+                if let Some(light) = point {
+                    copy.insert(*light);
+                }
+                if let Some(light) = spot {
+                    copy.insert(*light);
+                }
+                if let Some(light) = directional {
+                    copy.insert(light.clone());
+                }
+                if let Some(custom) = custom {
+                    copy.insert(custom.clone());
+                }
+                select.send(SelectionEvent::Replace(copy.id()));
+            }
+            EditorAction::Delete(entity) => {
+                if !sources.contains(*entity) {
+                    continue;
+                }
+                select.send(SelectionEvent::Remove(*entity));
+                commands.entity(*entity).despawn_recursive();
+            }
+        }
+    }
+}
+
+fn spawn_transform(camera: &Query<&Transform, With<NoClip>>) -> Transform {
+    match camera.get_single() {
+        Ok(camera) => {
+            Transform::from_translation(camera.translation + camera.forward() * SPAWN_DISTANCE)
+        }
+        Err(_) => Transform::from_xyz(0.0, 0.5, 0.0),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_spawn_cube_is_synced_and_selected() {
+        let mut app = setup();
+        send(&mut app, EditorAction::SpawnCube);
+
+        let selected = app.world().resource::<Selection>().primary().unwrap();
+        let entity = app.world().entity(selected);
+        assert!(entity.get::<SyncMark>().is_some());
+        assert!(entity.get::<Handle<Mesh>>().is_some());
+        assert_eq!(entity.get::<Name>().unwrap().as_str(), "Cube");
+    }
+
+    #[test]
+    fn test_spawn_in_front_of_camera() {
+        let mut app = setup();
+        app.world_mut()
+            .spawn((NoClip::default(), Transform::from_xyz(1.0, 2.0, 3.0)));
+        send(&mut app, EditorAction::SpawnLight);
+
+        let selected = app.world().resource::<Selection>().primary().unwrap();
+        let transform = app.world().entity(selected).get::<Transform>().unwrap();
+        assert_eq!(
+            transform.translation,
+            Vec3::new(1.0, 2.0, 3.0 - SPAWN_DISTANCE)
+        );
+    }
+
+    #[test]
+    fn test_duplicate_copies_components() {
+        let mut app = setup();
+        let source = app
+            .world_mut()
+            .spawn((
+                SyncMark,
+                Name::new("Lamp"),
+                Transform::from_xyz(1.0, 0.0, 0.0),
+                PointLight::default(),
+            ))
+            .id();
+        send(&mut app, EditorAction::Duplicate(source));
+
+        let selected = app.world().resource::<Selection>().primary().unwrap();
+        assert_ne!(selected, source);
+        let copy = app.world().entity(selected);
+        assert!(copy.get::<SyncMark>().is_some());
+        assert!(copy.get::<PointLight>().is_some());
+        assert_eq!(copy.get::<Name>().unwrap().as_str(), "Lamp (copy)");
+        assert_eq!(copy.get::<Transform>().unwrap().translation.x, 1.0);
+    }
+
+    #[test]
+    fn test_duplicate_clones_material() {
+        let mut app = setup();
+        let material = app
+            .world_mut()
+            .resource_mut::<Assets<StandardMaterial>>()
+            .addu(Color::srgb(1.0, 0.0, 0.0).into());
+        let source = app
+            .world_mut()
+            .spawn((SyncMark, Transform::default(), material.clone()))
+            .id();
+        send(&mut app, EditorAction::Duplicate(source));
+
+        let selected = app.world().resource::<Selection>().primary().unwrap();
+        let copied = app
+            .world()
+            .get::<Handle<StandardMaterial>>(selected)
+            .unwrap()
+            .clone();
+        assert_ne!(copied.id(), material.id());
+        let mut materials = app.world_mut().resource_mut::<Assets<StandardMaterial>>();
+        materials.get_mut(&copied).unwrap().base_color = Color::srgb(0.0, 0.0, 1.0);
+        assert_eq!(
+            materials.get(&material).unwrap().base_color,
+            Color::srgb(1.0, 0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn test_delete_despawns_and_clears_selection() {
+        let mut app = setup();
+        let entity = app.world_mut().spawn((SyncMark, Name::new("Cube"))).id();
+        app.world_mut().send_event(SelectionEvent::Replace(entity));
+        app.update();
+        send(&mut app, EditorAction::Delete(entity));
+
+        assert!(app.world().get_entity(entity).is_none());
+        assert!(app.world().resource::<Selection>().is_empty());
+    }
+
+    #[test]
+    fn test_delete_ignores_non_synced_entities() {
+        let mut app = setup();
+        let entity = app.world_mut().spawn(Name::new("Local")).id();
+        send(&mut app, EditorAction::Delete(entity));
+
+        assert!(app.world().get_entity(entity).is_some());
+    }
+
+    fn setup() -> App {
+        let mut app = App::new();
+        lux_components::init(&mut app);
+        app.init_resource::<Assets<Mesh>>();
+        app.init_resource::<Assets<StandardMaterial>>();
+        app.add_event::<EditorAction>();
+        app.add_systems(Update, apply_editor_actions);
+        app
+    }
+
+    fn send(app: &mut App, action: EditorAction) {
+        app.world_mut().send_event(action);
+        app.update();
+    }
+}
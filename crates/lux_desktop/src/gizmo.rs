@@ -0,0 +1,627 @@
+use bevy::{
+    color::palettes::css::{BLUE, LIME, RED, YELLOW},
+    math::Affine3A,
+    prelude::*,
+    window::PrimaryWindow,
+};
+use bevy_egui::{egui, EguiContexts};
+use lux_components::Selection;
+use lux_desktop_camera::NoClip;
+
+use crate::{
+    menu::MenuState,
+    picking::{cursor_ray, egui_wants_pointer},
+};
+
+/// Gizmo size relative to the distance from the camera, keeps it a constant size on screen.
+const GIZMO_SCALE: f32 = 0.15;
+/// How close, relative to the gizmo size, the cursor has to be to grab a handle.
+const HANDLE_THICKNESS: f32 = 0.1;
+const MIN_SCALE_FACTOR: f32 = 0.01;
+
+pub(crate) struct GizmoPlugin;
+
+impl Plugin for GizmoPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GizmoSettings>();
+        app.init_resource::<ActiveDrag>();
+        app.add_systems(
+            Update,
+            (gizmo_hotkeys, render_toolbar, gizmo_interact, draw_gizmo)
+                .chain()
+                .run_if(in_state(MenuState::Editor)),
+        );
+        app.add_systems(OnExit(MenuState::Editor), cancel_drag);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum GizmoMode {
+    #[default]
+    Translate,
+    Rotate,
+    Scale,
+}
+
+/// Orientation of the gizmo axes. Scaling always happens on local axes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum GizmoSpace {
+    #[default]
+    World,
+    Local,
+}
+
+#[derive(Resource, Debug, Clone)]
+pub(crate) struct GizmoSettings {
+    pub mode: GizmoMode,
+    pub space: GizmoSpace,
+    pub snap: bool,
+    /// Translation step in units
+    pub grid: f32,
+    /// Rotation step in degrees
+    pub angle: f32,
+    /// Scale factor step
+    pub scale_step: f32,
+}
+
+impl Default for GizmoSettings {
+    fn default() -> Self {
+        Self {
+            mode: GizmoMode::Translate,
+            space: GizmoSpace::World,
+            snap: false,
+            grid: 0.25,
+            angle: 15.0,
+            scale_step: 0.1,
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+pub(crate) struct ActiveDrag(Option<Drag>);
+
+impl ActiveDrag {
+    pub fn is_active(&self) -> bool {
+        self.0.is_some()
+    }
+}
+
+/// State captured when a handle is grabbed, every drag update is computed
+/// from it so that snapping does not accumulate errors.
+#[derive(Debug, Clone)]
+pub(crate) struct Drag {
+    entity: Entity,
+    mode: GizmoMode,
+    axis_index: usize,
+    axis: Vec3,
+    center: Vec3,
+    size: f32,
+    start: Transform,
+    start_param: f32,
+    start_vector: Vec3,
+    parent_inverse: Affine3A,
+    parent_rotation: Quat,
+}
+
+fn gizmo_hotkeys(input: Res<ButtonInput<KeyCode>>, mut settings: ResMut<GizmoSettings>) {
+    if input.just_pressed(KeyCode::Digit1) {
+        settings.mode = GizmoMode::Translate;
+    }
+    if input.just_pressed(KeyCode::Digit2) {
+        settings.mode = GizmoMode::Rotate;
+    }
+    if input.just_pressed(KeyCode::Digit3) {
+        settings.mode = GizmoMode::Scale;
+    }
+    if input.just_pressed(KeyCode::Digit4) {
+        settings.space = match settings.space {
+            GizmoSpace::World => GizmoSpace::Local,
+            GizmoSpace::Local => GizmoSpace::World,
+        };
+    }
+    if input.just_pressed(KeyCode::Digit5) {
+        settings.snap = !settings.snap;
+    }
+}
+
+fn render_toolbar(mut contexts: EguiContexts, mut settings: ResMut<GizmoSettings>) {
+    let mut edit = settings.clone();
+    egui::TopBottomPanel::top("lux_editor_toolbar").show(contexts.ctx_mut(), |ui| {
+        ui.horizontal(|ui| {
+            ui.selectable_value(&mut edit.mode, GizmoMode::Translate, "Move (1)");
+            ui.selectable_value(&mut edit.mode, GizmoMode::Rotate, "Rotate (2)");
+            ui.selectable_value(&mut edit.mode, GizmoMode::Scale, "Scale (3)");
+            ui.separator();
+            ui.selectable_value(&mut edit.space, GizmoSpace::World, "World");
+            ui.selectable_value(&mut edit.space, GizmoSpace::Local, "Local (4)");
+            ui.separator();
+            ui.checkbox(&mut edit.snap, "Snap (5)");
+            ui.add(
+                egui::DragValue::new(&mut edit.grid)
+                    .speed(0.05)
+                    .prefix("grid "),
+            );
+            ui.add(
+                egui::DragValue::new(&mut edit.angle)
+                    .speed(1.0)
+                    .prefix("angle "),
+            );
+            ui.add(
+                egui::DragValue::new(&mut edit.scale_step)
+                    .speed(0.01)
+                    .prefix("scale "),
+            );
+        });
+    });
+    if edit.mode != settings.mode
+        || edit.space != settings.space
+        || edit.snap != settings.snap
+        || edit.grid != settings.grid
+        || edit.angle != settings.angle
+        || edit.scale_step != settings.scale_step
+    {
+        *settings = edit;
+    }
+}
+
+fn cancel_drag(mut drag: ResMut<ActiveDrag>) {
+    drag.0 = None;
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn gizmo_interact(
+    mut contexts: EguiContexts,
+    mouse: Res<ButtonInput<MouseButton>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform), With<NoClip>>,
+    settings: Res<GizmoSettings>,
+    selection: Res<Selection>,
+    mut drag: ResMut<ActiveDrag>,
+    mut transforms: Query<(&mut Transform, &GlobalTransform, Option<&Parent>)>,
+    parents: Query<&GlobalTransform>,
+) {
+    if !mouse.pressed(MouseButton::Left) {
+        drag.0 = None;
+        return;
+    }
+    let Some(ray) = cursor_ray(&windows, &cameras) else {
+        return;
+    };
+    if let Some(active) = &drag.0 {
+        let Ok((mut transform, _, _)) = transforms.get_mut(active.entity) else {
+            drag.0 = None;
+            return;
+        };
+        let Some(dragged) = drag_transform(active, ray, &settings) else {
+            return;
+        };
+        if dragged != *transform {
+            *transform = dragged;
+        }
+        return;
+    }
+    if !mouse.just_pressed(MouseButton::Left) || egui_wants_pointer(&mut contexts) {
+        return;
+    }
+    let Some(entity) = selection.primary() else {
+        return;
+    };
+    let Ok((transform, gt, parent)) = transforms.get(entity) else {
+        return;
+    };
+    let center = gt.translation();
+    let size = gizmo_size(center, ray.origin);
+    let axes = gizmo_axes(gt, &settings);
+    let Some(axis_index) = pick_handle(ray, settings.mode, center, &axes, size) else {
+        return;
+    };
+    let parent_gt = parent
+        .and_then(|p| parents.get(p.get()).ok())
+        .copied()
+        .unwrap_or_default();
+    drag.0 = begin_drag(
+        entity,
+        *transform,
+        &parent_gt,
+        ray,
+        settings.mode,
+        axis_index,
+        axes[axis_index],
+        center,
+        size,
+    );
+}
+
+#[allow(clippy::too_many_arguments)]
+fn begin_drag(
+    entity: Entity,
+    start: Transform,
+    parent: &GlobalTransform,
+    ray: Ray3d,
+    mode: GizmoMode,
+    axis_index: usize,
+    axis: Vec3,
+    center: Vec3,
+    size: f32,
+) -> Option<Drag> {
+    let mut start_param = 0.0;
+    let mut start_vector = Vec3::ZERO;
+    match mode {
+        GizmoMode::Translate | GizmoMode::Scale => {
+            start_param = closest_param_on_line(ray.origin, *ray.direction, center, axis)?;
+        }
+        GizmoMode::Rotate => {
+            start_vector = ray_plane(ray.origin, *ray.direction, center, axis)? - center;
+        }
+    }
+    Some(Drag {
+        entity,
+        mode,
+        axis_index,
+        axis,
+        center,
+        size,
+        start,
+        start_param,
+        start_vector,
+        parent_inverse: parent.affine().inverse(),
+        parent_rotation: parent.to_scale_rotation_translation().1,
+    })
+}
+
+/// Computes the local transform for the current cursor ray of a drag.
+pub(crate) fn drag_transform(
+    drag: &Drag,
+    ray: Ray3d,
+    settings: &GizmoSettings,
+) -> Option<Transform> {
+    let mut result = drag.start;
+    match drag.mode {
+        GizmoMode::Translate => {
+            let param = closest_param_on_line(ray.origin, *ray.direction, drag.center, drag.axis)?;
+            let mut delta = param - drag.start_param;
+            if settings.snap {
+                delta = snap(delta, settings.grid);
+            }
+            let local_delta = drag.parent_inverse.transform_vector3(drag.axis * delta);
+            result.translation = drag.start.translation + local_delta;
+        }
+        GizmoMode::Rotate => {
+            let hit = ray_plane(ray.origin, *ray.direction, drag.center, drag.axis)?;
+            let mut angle = signed_angle(drag.start_vector, hit - drag.center, drag.axis);
+            if settings.snap {
+                angle = snap(angle.to_degrees(), settings.angle).to_radians();
+            }
+            let local_axis = (drag.parent_rotation.inverse() * drag.axis).normalize();
+            result.rotation =
+                (Quat::from_axis_angle(local_axis, angle) * drag.start.rotation).normalize();
+        }
+        GizmoMode::Scale => {
+            let param = closest_param_on_line(ray.origin, *ray.direction, drag.center, drag.axis)?;
+            let mut factor = 1.0 + (param - drag.start_param) / drag.size;
+            if settings.snap {
+                factor = snap(factor, settings.scale_step);
+            }
+            let factor = factor.max(MIN_SCALE_FACTOR);
+            result.scale[drag.axis_index] = drag.start.scale[drag.axis_index] * factor;
+        }
+    }
+    Some(result)
+}
+
+fn gizmo_size(center: Vec3, eye: Vec3) -> f32 {
+    center.distance(eye).max(1.0) * GIZMO_SCALE
+}
+
+fn gizmo_axes(gt: &GlobalTransform, settings: &GizmoSettings) -> [Vec3; 3] {
+    let local = settings.space == GizmoSpace::Local || settings.mode == GizmoMode::Scale;
+    if !local {
+        return [Vec3::X, Vec3::Y, Vec3::Z];
+    }
+    let rotation = gt.to_scale_rotation_translation().1;
+    [rotation * Vec3::X, rotation * Vec3::Y, rotation * Vec3::Z]
+}
+
+/// Finds the handle under the ray: axis lines for translate and scale,
+/// axis rings for rotate.
+fn pick_handle(
+    ray: Ray3d,
+    mode: GizmoMode,
+    center: Vec3,
+    axes: &[Vec3; 3],
+    size: f32,
+) -> Option<usize> {
+    let threshold = size * HANDLE_THICKNESS;
+    let mut best: Option<(usize, f32)> = None;
+    for (index, axis) in axes.iter().enumerate() {
+        let score = match mode {
+            GizmoMode::Translate | GizmoMode::Scale => {
+                let d =
+                    ray_segment_distance(ray.origin, *ray.direction, center, center + *axis * size);
+                (d < threshold).then_some(d)
+            }
+            GizmoMode::Rotate => ray_plane(ray.origin, *ray.direction, center, *axis)
+                .map(|hit| (hit.distance(center) - size).abs())
+                .filter(|d| *d < threshold),
+        };
+        if let Some(score) = score {
+            if best.map(|(_, b)| score < b).unwrap_or(true) {
+                best = Some((index, score));
+            }
+        }
+    }
+    best.map(|(index, _)| index)
+}
+
+fn draw_gizmo(
+    mut gizmos: Gizmos,
+    settings: Res<GizmoSettings>,
+    drag: Res<ActiveDrag>,
+    selection: Res<Selection>,
+    targets: Query<&GlobalTransform>,
+    cameras: Query<&GlobalTransform, With<NoClip>>,
+) {
+    let Some(entity) = selection.primary() else {
+        return;
+    };
+    let Ok(gt) = targets.get(entity) else {
+        return;
+    };
+    let Ok(camera) = cameras.get_single() else {
+        return;
+    };
+    let center = gt.translation();
+    let size = gizmo_size(center, camera.translation());
+    let axes = gizmo_axes(gt, &settings);
+    let colors = [RED, LIME, BLUE];
+    for (index, axis) in axes.iter().enumerate() {
+        let active = drag.0.as_ref().map(|d| d.axis_index) == Some(index);
+        let color = if active { YELLOW } else { colors[index] };
+        let end = center + *axis * size;
+        match settings.mode {
+            GizmoMode::Translate => {
+                gizmos.arrow(center, end, color);
+            }
+            GizmoMode::Rotate => {
+                if let Ok(normal) = Dir3::new(*axis) {
+                    gizmos.circle(center, normal, size, color);
+                }
+            }
+            GizmoMode::Scale => {
+                gizmos.line(center, end, color);
+                gizmos.cuboid(
+                    Transform::from_translation(end).with_scale(Vec3::splat(size * 0.1)),
+                    color,
+                );
+            }
+        }
+    }
+}
+
+/// Rounds a value to the closest multiple of step, a non positive step disables snapping.
+pub(crate) fn snap(value: f32, step: f32) -> f32 {
+    if step <= 0.0 {
+        return value;
+    }
+    (value / step).round() * step
+}
+
+/// Parameter along the line of the point closest to the ray, None when they are parallel.
+pub(crate) fn closest_param_on_line(
+    ray_origin: Vec3,
+    ray_direction: Vec3,
+    line_origin: Vec3,
+    line_direction: Vec3,
+) -> Option<f32> {
+    let w0 = line_origin - ray_origin;
+    let a = line_direction.dot(line_direction);
+    let b = line_direction.dot(ray_direction);
+    let c = ray_direction.dot(ray_direction);
+    let d = line_direction.dot(w0);
+    let e = ray_direction.dot(w0);
+    let denominator = a * c - b * b;
+    if denominator.abs() < f32::EPSILON {
+        return None;
+    }
+    Some((b * e - c * d) / denominator)
+}
+
+pub(crate) fn ray_segment_distance(ray_origin: Vec3, ray_direction: Vec3, a: Vec3, b: Vec3) -> f32 {
+    let segment = b - a;
+    let length = segment.length();
+    let direction = segment / length;
+    let param = closest_param_on_line(ray_origin, ray_direction, a, direction)
+        .unwrap_or(0.0)
+        .clamp(0.0, length);
+    let point = a + direction * param;
+    let t = ((point - ray_origin).dot(ray_direction) / ray_direction.length_squared()).max(0.0);
+    point.distance(ray_origin + ray_direction * t)
+}
+
+pub(crate) fn ray_plane(origin: Vec3, direction: Vec3, point: Vec3, normal: Vec3) -> Option<Vec3> {
+    let denominator = direction.dot(normal);
+    if denominator.abs() < f32::EPSILON {
+        return None;
+    }
+    let t = (point - origin).dot(normal) / denominator;
+    (t >= 0.0).then(|| origin + direction * t)
+}
+
+/// Angle from a to b around axis, in radians.
+pub(crate) fn signed_angle(a: Vec3, b: Vec3, axis: Vec3) -> f32 {
+    a.cross(b).dot(axis).atan2(a.dot(b))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::f32::consts::FRAC_PI_2;
+
+    const EPSILON: f32 = 1e-4;
+
+    #[test]
+    fn test_snap() {
+        assert_eq!(snap(1.13, 0.25), 1.25);
+        assert_eq!(snap(-0.6, 0.5), -0.5);
+        assert_eq!(snap(0.7, 0.0), 0.7);
+    }
+
+    #[test]
+    fn test_snap_angle() {
+        assert_eq!(snap(37.0, 15.0), 30.0);
+        assert_eq!(snap(38.0, 15.0), 45.0);
+    }
+
+    #[test]
+    fn test_closest_param_on_line() {
+        let t = closest_param_on_line(Vec3::new(2.0, 1.0, 5.0), -Vec3::Z, Vec3::ZERO, Vec3::X);
+        assert!((t.unwrap() - 2.0).abs() < EPSILON);
+    }
+
+    #[test]
+    fn test_closest_param_parallel() {
+        let t = closest_param_on_line(Vec3::new(0.0, 1.0, 0.0), Vec3::X, Vec3::ZERO, Vec3::X);
+        assert!(t.is_none());
+    }
+
+    #[test]
+    fn test_ray_segment_distance() {
+        let d = ray_segment_distance(Vec3::new(0.5, 0.2, 5.0), -Vec3::Z, Vec3::ZERO, Vec3::X);
+        assert!((d - 0.2).abs() < EPSILON);
+        let d = ray_segment_distance(Vec3::new(3.0, 0.0, 5.0), -Vec3::Z, Vec3::ZERO, Vec3::X);
+        assert!((d - 2.0).abs() < EPSILON);
+    }
+
+    #[test]
+    fn test_ray_plane() {
+        let hit = ray_plane(Vec3::new(1.0, 5.0, 2.0), -Vec3::Y, Vec3::ZERO, Vec3::Y);
+        assert_eq!(hit, Some(Vec3::new(1.0, 0.0, 2.0)));
+        let miss = ray_plane(Vec3::new(1.0, 5.0, 2.0), Vec3::Y, Vec3::ZERO, Vec3::Y);
+        assert_eq!(miss, None);
+    }
+
+    #[test]
+    fn test_signed_angle() {
+        assert!((signed_angle(Vec3::X, -Vec3::Z, Vec3::Y) - FRAC_PI_2).abs() < EPSILON);
+        assert!((signed_angle(Vec3::X, Vec3::Z, Vec3::Y) + FRAC_PI_2).abs() < EPSILON);
+    }
+
+    #[test]
+    fn test_pick_translate_handle() {
+        let axes = [Vec3::X, Vec3::Y, Vec3::Z];
+        let ray = Ray3d::new(Vec3::new(0.5, 0.01, 5.0), -Vec3::Z);
+        assert_eq!(
+            pick_handle(ray, GizmoMode::Translate, Vec3::ZERO, &axes, 1.0),
+            Some(0)
+        );
+        let ray = Ray3d::new(Vec3::new(0.01, 0.5, 5.0), -Vec3::Z);
+        assert_eq!(
+            pick_handle(ray, GizmoMode::Translate, Vec3::ZERO, &axes, 1.0),
+            Some(1)
+        );
+        let ray = Ray3d::new(Vec3::new(0.5, 0.5, 5.0), -Vec3::Z);
+        assert_eq!(
+            pick_handle(ray, GizmoMode::Translate, Vec3::ZERO, &axes, 1.0),
+            None
+        );
+    }
+
+    #[test]
+    fn test_pick_rotate_ring() {
+        let axes = [Vec3::X, Vec3::Y, Vec3::Z];
+        let ray = Ray3d::new(Vec3::new(0.0, 5.0, 1.0), -Vec3::Y);
+        assert_eq!(
+            pick_handle(ray, GizmoMode::Rotate, Vec3::ZERO, &axes, 1.0),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn test_drag_translate_with_snap() {
+        let settings = GizmoSettings {
+            snap: true,
+            ..default()
+        };
+        let drag = start(GizmoMode::Translate, 0, Vec3::new(0.0, 0.0, 5.0));
+        let moved = drag_transform(&drag, ray_at(1.13, 0.0), &settings).unwrap();
+        assert!((moved.translation.x - 1.25).abs() < EPSILON);
+        assert_eq!(moved.translation.y, 0.0);
+    }
+
+    #[test]
+    fn test_drag_translate_in_parent_space() {
+        let settings = GizmoSettings::default();
+        let parent = GlobalTransform::from_scale(Vec3::splat(2.0));
+        let ray = ray_at(0.0, 0.0);
+        let drag = begin_drag(
+            Entity::PLACEHOLDER,
+            Transform::default(),
+            &parent,
+            ray,
+            GizmoMode::Translate,
+            0,
+            Vec3::X,
+            Vec3::ZERO,
+            1.0,
+        )
+        .unwrap();
+        let moved = drag_transform(&drag, ray_at(1.0, 0.0), &settings).unwrap();
+        assert!((moved.translation.x - 0.5).abs() < EPSILON);
+    }
+
+    #[test]
+    fn test_drag_rotate_with_snap() {
+        let settings = GizmoSettings {
+            snap: true,
+            ..default()
+        };
+        let from = Ray3d::new(Vec3::new(1.0, 5.0, 0.0), -Vec3::Y);
+        let drag = begin_drag(
+            Entity::PLACEHOLDER,
+            Transform::default(),
+            &GlobalTransform::IDENTITY,
+            from,
+            GizmoMode::Rotate,
+            1,
+            Vec3::Y,
+            Vec3::ZERO,
+            1.0,
+        )
+        .unwrap();
+        let to = Ray3d::new(Vec3::new(0.05, 5.0, -1.0), -Vec3::Y);
+        let rotated = drag_transform(&drag, to, &settings).unwrap();
+        let expected = Quat::from_rotation_y(FRAC_PI_2);
+        assert!(rotated.rotation.angle_between(expected) < EPSILON);
+    }
+
+    #[test]
+    fn test_drag_scale() {
+        let settings = GizmoSettings::default();
+        let drag = start(GizmoMode::Scale, 0, Vec3::new(0.0, 0.0, 5.0));
+        let scaled = drag_transform(&drag, ray_at(1.0, 0.0), &settings).unwrap();
+        assert!((scaled.scale.x - 2.0).abs() < EPSILON);
+        assert_eq!(scaled.scale.y, 1.0);
+        let scaled = drag_transform(&drag, ray_at(-5.0, 0.0), &settings).unwrap();
+        assert_eq!(scaled.scale.x, MIN_SCALE_FACTOR);
+    }
+
+    fn ray_at(x: f32, y: f32) -> Ray3d {
+        Ray3d::new(Vec3::new(x, y, 5.0), -Vec3::Z)
+    }
+
+    fn start(mode: GizmoMode, axis_index: usize, origin: Vec3) -> Drag {
+        let axes = [Vec3::X, Vec3::Y, Vec3::Z];
+        begin_drag(
+            Entity::PLACEHOLDER,
+            Transform::default(),
+            &GlobalTransform::IDENTITY,
+            Ray3d::new(origin, -Vec3::Z),
+            mode,
+            axis_index,
+            axes[axis_index],
+            Vec3::ZERO,
+            1.0,
+        )
+        .unwrap()
+    }
+}
@@ -8,8 +8,15 @@ pub fn init(app: &mut App) {
     );
 }
 
-fn render_main_menu(mut contexts: EguiContexts, mut exit: EventWriter<AppExit>) {
+fn render_main_menu(
+    mut contexts: EguiContexts,
+    mut state: ResMut<NextState<crate::menu::MenuState>>,
+    mut exit: EventWriter<AppExit>,
+) {
     egui::Window::new("Menu").show(contexts.ctx_mut(), |ui| {
+        if ui.button("Editor").clicked() {
+            state.set(crate::menu::MenuState::Editor);
+        }
         if ui.button("Quit").clicked() {
             exit.send(AppExit::Success);
         }
@@ -1,10 +1,45 @@
 use bevy::prelude::*;
 use lux_desktop_camera::NoClip;
 
+mod avatar;
+mod editor;
+mod gizmo;
 mod layouts;
 mod menu;
+mod network_stats;
+mod outlines;
+mod picking;
+mod replay;
+mod spawn;
 
 pub fn init(app: &mut App) {
+    spawn_camera(app);
+    app.add_plugins(bevy_egui::EguiPlugin);
+    app.add_plugins(menu::MenuPlugin);
+    app.add_plugins(editor::EditorPlugin);
+    app.add_plugins(gizmo::GizmoPlugin);
+    app.add_plugins(picking::PickingPlugin);
+    app.add_plugins(outlines::OutlinesPlugin);
+    app.add_plugins(network_stats::NetworkStatsPlugin);
+    app.add_plugins(spawn::SpawnCameraPlugin);
+    app.add_plugins(lux_desktop_camera::DesktopCameraPlugin);
+    layouts::init(app);
+}
+
+/// The local avatar follows the camera, for when there is no headset.
+pub fn init_avatar(app: &mut App) {
+    app.add_plugins(avatar::CameraAvatarPlugin);
+}
+
+/// Read-only viewer for `lux replay`: the camera and the playback controls.
+pub fn init_replay(app: &mut App) {
+    spawn_camera(app);
+    app.add_plugins(bevy_egui::EguiPlugin);
+    app.add_plugins(replay::ReplayPlugin);
+    app.add_plugins(lux_desktop_camera::DesktopCameraPlugin);
+}
+
+fn spawn_camera(app: &mut App) {
     app.world_mut().spawn((
         NoClip::default(),
         Camera3dBundle {
@@ -12,8 +47,4 @@ pub fn init(app: &mut App) {
             ..default()
         },
     ));
-    app.add_plugins(menu::MenuPlugin);
-    app.add_plugins(bevy_editor_pls::EditorPlugin::default());
-    app.add_plugins(lux_desktop_camera::DesktopCameraPlugin);
-    layouts::init(app);
 }
@@ -7,6 +7,7 @@ pub enum MenuState {
     #[default]
     Off,
     Main,
+    Editor,
 }
 
 impl Plugin for MenuPlugin {
@@ -17,6 +18,7 @@ impl Plugin for MenuPlugin {
             (
                 esc_to_enter_menu.run_if(in_state(MenuState::Off)),
                 esc_to_exit_menu.run_if(not(in_state(MenuState::Off))),
+                f1_to_toggle_editor,
                 ctrl_q,
             ),
         );
@@ -35,6 +37,19 @@ fn esc_to_exit_menu(input: Res<ButtonInput<KeyCode>>, mut state: ResMut<NextStat
     }
 }
 
+fn f1_to_toggle_editor(
+    input: Res<ButtonInput<KeyCode>>,
+    current: Res<State<MenuState>>,
+    mut state: ResMut<NextState<MenuState>>,
+) {
+    if input.just_pressed(KeyCode::F1) {
+        match current.get() {
+            MenuState::Editor => state.set(MenuState::Off),
+            _ => state.set(MenuState::Editor),
+        }
+    }
+}
+
 fn ctrl_q(input: Res<ButtonInput<KeyCode>>, mut event: EventWriter<AppExit>) {
     if input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight])
         && input.just_pressed(KeyCode::KeyQ)
@@ -70,6 +85,29 @@ mod test {
         state_is(&app, MenuState::Off);
     }
 
+    #[test]
+    fn test_transition_to_editor() {
+        let mut app = setup();
+        press(&mut app, KeyCode::F1);
+        state_is(&app, MenuState::Editor);
+    }
+
+    #[test]
+    fn test_transition_back_from_editor() {
+        let mut app = setup();
+        press(&mut app, KeyCode::F1);
+        press(&mut app, KeyCode::F1);
+        state_is(&app, MenuState::Off);
+    }
+
+    #[test]
+    fn test_esc_exits_editor() {
+        let mut app = setup();
+        press(&mut app, KeyCode::F1);
+        press_esc(&mut app);
+        state_is(&app, MenuState::Off);
+    }
+
     fn setup() -> App {
         let mut app = App::new();
         app.add_plugins(InputPlugin);
@@ -85,9 +123,13 @@ mod test {
     }
 
     fn press_esc(app: &mut App) {
+        press(app, KeyCode::Escape);
+    }
+
+    fn press(app: &mut App, key: KeyCode) {
         let mut input = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
-        input.press(KeyCode::Escape);
-        input.release(KeyCode::Escape);
+        input.press(key);
+        input.release(key);
         app.update();
     }
 }
@@ -0,0 +1,51 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use lux_networking::NetworkStats;
+
+pub(crate) struct NetworkStatsPlugin;
+
+impl Plugin for NetworkStatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ShowNetworkStats>();
+        app.add_systems(PreUpdate, f3_to_toggle_stats);
+        app.add_systems(
+            Update,
+            render_stats.run_if(
+                resource_exists::<NetworkStats>.and_then(|show: Res<ShowNetworkStats>| show.0),
+            ),
+        );
+    }
+}
+
+#[derive(Resource, Default)]
+struct ShowNetworkStats(bool);
+
+fn f3_to_toggle_stats(input: Res<ButtonInput<KeyCode>>, mut show: ResMut<ShowNetworkStats>) {
+    if input.just_pressed(KeyCode::F3) {
+        show.0 = !show.0;
+    }
+}
+
+fn render_stats(mut contexts: EguiContexts, stats: Res<NetworkStats>) {
+    let mut components: Vec<_> = stats.components.iter().collect();
+    components.sort_by(|a, b| b.1.bytes_per_second.total_cmp(&a.1.bytes_per_second));
+    egui::Window::new("Network").show(contexts.ctx_mut(), |ui| {
+        ui.label(format!(
+            "Sending about {:.1} KB/s (estimated)",
+            stats.total_bytes_per_second() / 1024.0
+        ));
+        ui.separator();
+        egui::Grid::new("lux_network_stats").show(ui, |ui| {
+            ui.strong("Component");
+            ui.strong("Changes/s");
+            ui.strong("Est. KB/s");
+            ui.end_row();
+            for (name, component) in components {
+                ui.label(name);
+                ui.label(format!("{:.0}", component.changes_per_second));
+                ui.label(format!("{:.2}", component.bytes_per_second / 1024.0));
+                ui.end_row();
+            }
+        });
+    });
+}
@@ -0,0 +1,53 @@
+use std::collections::HashMap;
+
+use bevy::{prelude::*, render::primitives::Aabb};
+use lux_components::{peer_color, LocalPeer, Selected, SelectedBy};
+
+pub(crate) struct OutlinesPlugin;
+
+impl Plugin for OutlinesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (draw_local_selection, draw_remote_selections));
+    }
+}
+
+fn draw_local_selection(
+    mut gizmos: Gizmos,
+    selected: Query<(&Aabb, &GlobalTransform), With<Selected>>,
+) {
+    for (aabb, gt) in selected.iter() {
+        outline(&mut gizmos, aabb, gt, Color::WHITE);
+    }
+}
+
+/// Selections of other peers are outlined in their colour,
+/// slightly inflated per peer so that overlapping ones stay visible.
+fn draw_remote_selections(
+    mut gizmos: Gizmos,
+    peer: Res<LocalPeer>,
+    markers: Query<(&SelectedBy, &Parent)>,
+    selected: Query<(&Aabb, &GlobalTransform)>,
+) {
+    let mut drawn = HashMap::<Entity, usize>::new();
+    for (by, parent) in markers.iter() {
+        if by.peer == peer.id {
+            continue;
+        }
+        let Ok((aabb, gt)) = selected.get(parent.get()) else {
+            continue;
+        };
+        let index = drawn.entry(parent.get()).or_default();
+        let grown = Aabb {
+            center: aabb.center,
+            half_extents: aabb.half_extents * (1.02 + 0.02 * *index as f32),
+        };
+        *index += 1;
+        outline(&mut gizmos, &grown, gt, peer_color(by.peer));
+    }
+}
+
+fn outline(gizmos: &mut Gizmos, aabb: &Aabb, gt: &GlobalTransform, color: Color) {
+    let bounds = Transform::from_translation(aabb.center.into())
+        .with_scale((aabb.half_extents * 2.0).into());
+    gizmos.cuboid(gt.mul_transform(bounds), color);
+}
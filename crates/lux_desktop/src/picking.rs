@@ -0,0 +1,115 @@
+use bevy::{prelude::*, window::PrimaryWindow};
+use bevy_egui::EguiContexts;
+use lux_components::{PickRequest, PickSelect, PickSource};
+use lux_desktop_camera::NoClip;
+
+use crate::{gizmo::ActiveDrag, menu::MenuState};
+
+pub(crate) struct PickingPlugin;
+
+impl Plugin for PickingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            pick_on_click
+                .after(crate::gizmo::gizmo_interact)
+                .run_if(in_state(MenuState::Editor)),
+        );
+        app.add_systems(Update, interact_on_click.run_if(in_state(MenuState::Off)));
+    }
+}
+
+fn pick_on_click(
+    mut contexts: EguiContexts,
+    mouse: Res<ButtonInput<MouseButton>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    drag: Res<ActiveDrag>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform), With<NoClip>>,
+    mut requests: EventWriter<PickRequest>,
+) {
+    if !mouse.just_pressed(MouseButton::Left) || drag.is_active() {
+        return;
+    }
+    if egui_wants_pointer(&mut contexts) {
+        return;
+    }
+    let Some(ray) = cursor_ray(&windows, &cameras) else {
+        return;
+    };
+    requests.send(PickRequest {
+        ray,
+        source: PickSource::Desktop,
+        select: Some(select_mode(&keys)),
+    });
+}
+
+/// Outside of the editor clicks interact with the world, like scripted objects.
+fn interact_on_click(
+    mouse: Res<ButtonInput<MouseButton>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform), With<NoClip>>,
+    mut requests: EventWriter<PickRequest>,
+) {
+    if !mouse.just_pressed(MouseButton::Left) {
+        return;
+    }
+    let Some(ray) = cursor_ray(&windows, &cameras) else {
+        return;
+    };
+    requests.send(PickRequest {
+        ray,
+        source: PickSource::Desktop,
+        select: None,
+    });
+}
+
+/// Shift adds to the selection, Ctrl toggles, a plain click replaces it.
+fn select_mode(keys: &ButtonInput<KeyCode>) -> PickSelect {
+    if keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        PickSelect::Add
+    } else if keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        PickSelect::Toggle
+    } else {
+        PickSelect::Replace
+    }
+}
+
+pub(crate) fn egui_wants_pointer(contexts: &mut EguiContexts) -> bool {
+    let ctx = contexts.ctx_mut();
+    ctx.wants_pointer_input() || ctx.is_pointer_over_area()
+}
+
+pub(crate) fn cursor_ray(
+    windows: &Query<&Window, With<PrimaryWindow>>,
+    cameras: &Query<(&Camera, &GlobalTransform), With<NoClip>>,
+) -> Option<Ray3d> {
+    let cursor = windows.get_single().ok()?.cursor_position()?;
+    let (camera, camera_gt) = cameras.get_single().ok()?;
+    camera.viewport_to_world(camera_gt, cursor)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_plain_click_replaces() {
+        let keys = ButtonInput::<KeyCode>::default();
+        assert_eq!(select_mode(&keys), PickSelect::Replace);
+    }
+
+    #[test]
+    fn test_shift_click_adds() {
+        let mut keys = ButtonInput::<KeyCode>::default();
+        keys.press(KeyCode::ShiftLeft);
+        assert_eq!(select_mode(&keys), PickSelect::Add);
+    }
+
+    #[test]
+    fn test_ctrl_click_toggles() {
+        let mut keys = ButtonInput::<KeyCode>::default();
+        keys.press(KeyCode::ControlRight);
+        assert_eq!(select_mode(&keys), PickSelect::Toggle);
+    }
+}
@@ -0,0 +1,48 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use lux_networking::{Replay, Replayed};
+
+pub(crate) struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (render_controls, draw_replayed));
+    }
+}
+
+fn render_controls(mut contexts: EguiContexts, mut replay: ResMut<Replay>) {
+    egui::TopBottomPanel::bottom("lux_replay").show(contexts.ctx_mut(), |ui| {
+        ui.horizontal(|ui| {
+            let label = if replay.playing { "Pause" } else { "Play" };
+            if ui.button(label).clicked() {
+                if !replay.playing && replay.time >= replay.duration() {
+                    replay.time = 0.0;
+                }
+                replay.playing = !replay.playing;
+            }
+            ui.add(
+                egui::Slider::new(&mut replay.speed, 0.1..=8.0)
+                    .logarithmic(true)
+                    .text("Speed"),
+            );
+            let duration = replay.duration();
+            let mut time = replay.time;
+            ui.spacing_mut().slider_width = ui.available_width() - 80.0;
+            let scrub = ui.add(egui::Slider::new(&mut time, 0.0..=duration).suffix("s"));
+            if scrub.changed() {
+                replay.time = time;
+            }
+        });
+    });
+}
+
+/// Entities without a mesh loaded from a file show as their axes.
+#[allow(clippy::type_complexity)]
+fn draw_replayed(
+    mut gizmos: Gizmos,
+    replayed: Query<&GlobalTransform, (With<Replayed>, Without<Handle<Mesh>>)>,
+) {
+    for transform in replayed.iter() {
+        gizmos.axes(*transform, 0.3);
+    }
+}
@@ -0,0 +1,79 @@
+use bevy::prelude::*;
+use lux_components::SpawnPoint;
+use lux_desktop_camera::NoClip;
+
+/// Camera height above the spawn point, roughly where eyes would be.
+const EYE_HEIGHT: f32 = 1.7;
+
+pub(crate) struct SpawnCameraPlugin;
+
+impl Plugin for SpawnCameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, camera_to_first_spawn_point);
+    }
+}
+
+/// Moves the camera once, when the first spawn point of the world shows up.
+fn camera_to_first_spawn_point(
+    mut placed: Local<bool>,
+    points: Query<&GlobalTransform, Added<SpawnPoint>>,
+    mut cameras: Query<&mut Transform, With<NoClip>>,
+) {
+    if *placed {
+        return;
+    }
+    let Some(point) = points.iter().next() else {
+        return;
+    };
+    let (_, rotation, translation) = point.to_scale_rotation_translation();
+    let (yaw, _, _) = rotation.to_euler(EulerRot::YXZ);
+    for mut camera in cameras.iter_mut() {
+        camera.translation = translation + Vec3::Y * EYE_HEIGHT;
+        camera.rotation = Quat::from_rotation_y(yaw);
+    }
+    *placed = true;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_camera_moves_to_spawn_point() {
+        let mut app = setup();
+        let camera = app
+            .world_mut()
+            .spawn((NoClip::default(), Transform::from_xyz(-2.0, 2.5, 5.0)))
+            .id();
+        spawn_point(&mut app, Vec3::new(3.0, 1.0, 0.0));
+
+        let transform = app.world().entity(camera).get::<Transform>().unwrap();
+        assert_eq!(transform.translation, Vec3::new(3.0, 1.0 + EYE_HEIGHT, 0.0));
+    }
+
+    #[test]
+    fn test_camera_moves_only_once() {
+        let mut app = setup();
+        let camera = app
+            .world_mut()
+            .spawn((NoClip::default(), Transform::default()))
+            .id();
+        spawn_point(&mut app, Vec3::ZERO);
+        spawn_point(&mut app, Vec3::new(10.0, 0.0, 0.0));
+
+        let transform = app.world().entity(camera).get::<Transform>().unwrap();
+        assert_eq!(transform.translation, Vec3::new(0.0, EYE_HEIGHT, 0.0));
+    }
+
+    fn setup() -> App {
+        let mut app = App::new();
+        app.add_plugins(SpawnCameraPlugin);
+        app
+    }
+
+    fn spawn_point(app: &mut App, at: Vec3) {
+        app.world_mut()
+            .spawn((SpawnPoint, GlobalTransform::from_translation(at)));
+        app.update();
+    }
+}
@@ -7,6 +7,7 @@ use bevy::{
 
 pub fn init(app: &mut App) {
     app.add_plugins(StatesPlugin);
+    app.add_plugins((TransformPlugin, HierarchyPlugin));
     app.add_plugins(AssetPlugin::default());
     app.init_asset::<Scene>();
     app.init_asset::<Shader>();
@@ -2,13 +2,40 @@ use bevy::prelude::*;
 use clap::Parser;
 use lux_cli::{Args, Command};
 
+mod logging;
+
 pub fn app() -> App {
     let mut app = App::new();
     let args = Args::parse();
     app.insert_resource(args.clone());
+    if args.height.is_some() && args.avatar_file().is_some_and(|f| f.ends_with(".vrm")) {
+        eprintln!("--height calibrates .glb/.gltf avatars only, not .vrm ones.");
+        std::process::exit(1);
+    }
+
+    if let Some(Command::Bot { .. }) = args.command {
+        app.add_plugins(logging::plugin(&args));
+        lux_headless::init(&mut app);
+        lux_bot::init(&args, &mut app);
+        return app;
+    }
+    if let Some(Command::Replay { file, speed }) = &args.command {
+        app.add_plugins(DefaultPlugins.set(logging::plugin(&args)));
+        if let Err(e) = lux_networking::init_replay(file, *speed, &mut app) {
+            eprintln!("Cannot replay {}: {}", file, e);
+            std::process::exit(1);
+        }
+        lux_desktop::init_replay(&mut app);
+        return app;
+    }
 
     base_init(&args, &mut app);
     lux_networking::init(&args, &mut app);
+    lux_physics::init(&args, &mut app);
+    lux_scripting::init(&args, &mut app);
+    lux_plugins::init(&args, &mut app);
+    lux_bot::init(&args, &mut app);
+    lux_metrics::init(&args, &mut app);
     lux_components::init(&mut app);
     lux_avatar_generic::init(&mut app);
     #[cfg(feature = "vrm")]
@@ -24,16 +51,20 @@ fn base_init(args: &Args, app: &mut App) {
             world_file: _,
             headless,
             ip: _,
+            port: _,
             avatar_file: _,
+            metrics: _,
+            metrics_ip: _,
         }) => headless,
         _ => false,
     };
     if headless {
+        app.add_plugins(logging::plugin(args));
         lux_headless::init(app);
     } else if args.xr_enabled {
         cfg_if::cfg_if! {
             if #[cfg(feature="xr")] {
-                lux_xr::init(app, DefaultPlugins);
+                lux_xr::init(app, DefaultPlugins.set(logging::plugin(args)));
                 lux_desktop::init(app);
             } else {
                 eprintln!("XR feature is not compiled in.");
@@ -41,7 +72,8 @@ fn base_init(args: &Args, app: &mut App) {
             }
         }
     } else {
-        app.add_plugins(DefaultPlugins);
+        app.add_plugins(DefaultPlugins.set(logging::plugin(args)));
         lux_desktop::init(app);
+        lux_desktop::init_avatar(app);
     }
 }
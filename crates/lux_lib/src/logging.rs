@@ -0,0 +1,157 @@
+use std::{
+    fmt::Debug,
+    fs::{File, OpenOptions},
+    io::Write,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::{
+    log::{
+        tracing_subscriber::{field::Visit, fmt, layer::Context, Layer},
+        BoxedLayer, Level, LogPlugin,
+    },
+    prelude::*,
+    utils::tracing::{field::Field, Event, Subscriber},
+};
+use lux_cli::Args;
+use serde_json::{json, Map, Value};
+
+/// Same as the bevy default, the graphics crates are too verbose below these.
+const DEFAULT_FILTER: &str = "wgpu=error,naga=warn";
+
+/// Logging as asked on the command line. `RUST_LOG` still wins when set.
+pub(crate) fn plugin(args: &Args) -> LogPlugin {
+    let mut filter = DEFAULT_FILTER.to_owned();
+    if let Some(extra) = &args.log_filter {
+        filter.push(',');
+        filter.push_str(extra);
+    }
+    LogPlugin {
+        filter,
+        level: args.log_level.unwrap_or(Level::INFO),
+        custom_layer: file_layer,
+    }
+}
+
+/// The console keeps the readable format, the file gets a copy.
+fn file_layer(app: &mut App) -> Option<BoxedLayer> {
+    let args = app.world().get_resource::<Args>()?;
+    let path = args.log_file.as_ref()?;
+    let file = match OpenOptions::new().create(true).append(true).open(path) {
+        Ok(file) => file,
+        Err(e) => {
+            // No logger is set yet to report this.
+            eprintln!("Cannot log to {}: {}", path, e);
+            return None;
+        }
+    };
+    if args.log_json {
+        Some(Box::new(JsonLayer(Mutex::new(file))))
+    } else {
+        Some(Box::new(
+            fmt::layer().with_ansi(false).with_writer(Mutex::new(file)),
+        ))
+    }
+}
+
+/// One JSON object per event, with its level, target, message and fields.
+struct JsonLayer(Mutex<File>);
+
+impl<S: Subscriber> Layer<S> for JsonLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let line = event_to_json(event);
+        if let Ok(mut file) = self.0.lock() {
+            let _ = writeln!(file, "{}", line);
+        }
+    }
+}
+
+fn event_to_json(event: &Event<'_>) -> Value {
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs_f64())
+        .unwrap_or(0.0);
+    let metadata = event.metadata();
+    let mut fields = JsonFields(Map::new());
+    event.record(&mut fields);
+    let mut line = json!({
+        "time": time,
+        "level": metadata.level().as_str(),
+        "target": metadata.target(),
+    });
+    if let Value::Object(line) = &mut line {
+        line.extend(fields.0);
+    }
+    line
+}
+
+struct JsonFields(Map<String, Value>);
+
+impl Visit for JsonFields {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.insert(field.name().to_owned(), json!(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().to_owned(), json!(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().to_owned(), json!(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().to_owned(), json!(value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_owned(), json!(value));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.0
+            .insert(field.name().to_owned(), json!(format!("{:?}", value)));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bevy::{
+        log::tracing_subscriber::{layer::SubscriberExt, Registry},
+        utils::tracing,
+    };
+    use clap::Parser;
+
+    #[test]
+    fn test_filter_from_args() {
+        let args = Args::parse_from([
+            "lux",
+            "--log-level",
+            "debug",
+            "--log-filter",
+            "lux_networking=trace",
+        ]);
+        let plugin = plugin(&args);
+        assert_eq!(plugin.level, Level::DEBUG);
+        assert_eq!(plugin.filter, "wgpu=error,naga=warn,lux_networking=trace");
+    }
+
+    #[test]
+    fn test_json_lines() {
+        let path = std::env::temp_dir().join(format!("lux_log_{}.jsonl", std::process::id()));
+        let file = File::create(&path).unwrap();
+        let subscriber = Registry::default().with(JsonLayer(Mutex::new(file)));
+        tracing::subscriber::with_default(subscriber, || {
+            warn!(entity = 7, "Cannot load {}", "a.glb");
+        });
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        let line: Value = serde_json::from_str(text.trim()).unwrap();
+        assert_eq!(line["level"], "WARN");
+        assert_eq!(line["message"], "Cannot load a.glb");
+        assert_eq!(line["entity"], 7);
+        assert!(line["target"].as_str().unwrap().starts_with("lux_lib"));
+    }
+}
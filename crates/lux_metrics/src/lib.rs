@@ -0,0 +1,195 @@
+mod server;
+
+use std::{
+    fmt::Write,
+    net::{IpAddr, Ipv4Addr, TcpListener},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use bevy::{ecs::entity::Entities, prelude::*, time::common_conditions::on_timer};
+use bevy_sync::{SyncEntity, SyncMark};
+use lux_cli::{Args, Command};
+use lux_components::{time_ticks, TickTimes, User};
+use lux_networking::NetworkStats;
+
+use server::Shared;
+
+/// What a host is doing, as served at `/metrics`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Metrics {
+    pub uptime_seconds: f64,
+    pub users: usize,
+    /// Average and longest tick over the last second.
+    pub tick_seconds: f64,
+    pub max_tick_seconds: f64,
+    pub entities: u32,
+    pub synced_entities: usize,
+    /// Estimated from the changes of synched components, see NetworkStats.
+    /// The transport does not tell what it receives, so there are no
+    /// received bytes.
+    pub sent_bytes_per_second: f32,
+    pub meshes: usize,
+    pub materials: usize,
+    pub images: usize,
+}
+
+impl Metrics {
+    /// Prometheus text format.
+    pub fn to_prometheus(&self) -> String {
+        let mut text = String::new();
+        let mut gauge = |name: &str, help: &str, value: String| {
+            let _ = writeln!(text, "# HELP lux_{} {}", name, help);
+            let _ = writeln!(text, "# TYPE lux_{} gauge", name);
+            let _ = writeln!(text, "lux_{} {}", name, value);
+        };
+        gauge(
+            "uptime_seconds",
+            "Seconds since the host started.",
+            self.uptime_seconds.to_string(),
+        );
+        gauge("users", "Users in the session.", self.users.to_string());
+        gauge(
+            "tick_seconds",
+            "Average tick duration over the last second.",
+            self.tick_seconds.to_string(),
+        );
+        gauge(
+            "max_tick_seconds",
+            "Longest tick over the last second.",
+            self.max_tick_seconds.to_string(),
+        );
+        gauge(
+            "entities",
+            "Entities in the world.",
+            self.entities.to_string(),
+        );
+        gauge(
+            "synced_entities",
+            "Entities shared with the peers.",
+            self.synced_entities.to_string(),
+        );
+        gauge(
+            "sent_bytes_per_second",
+            "Estimated bytes per second sent to the peers, received bytes are not known.",
+            self.sent_bytes_per_second.to_string(),
+        );
+        let _ = writeln!(text, "# HELP lux_assets Loaded assets by kind.");
+        let _ = writeln!(text, "# TYPE lux_assets gauge");
+        for (kind, count) in [
+            ("mesh", self.meshes),
+            ("material", self.materials),
+            ("image", self.images),
+        ] {
+            let _ = writeln!(text, "lux_assets{{kind=\"{}\"}} {}", kind, count);
+        }
+        text
+    }
+}
+
+#[derive(Resource, Clone)]
+struct MetricsHandle(Arc<Mutex<Shared>>);
+
+/// Serves the metrics when hosting headless with `--metrics <port>`.
+pub fn init(args: &Args, app: &mut App) {
+    let Some(Command::Host {
+        headless: true,
+        metrics: Some(port),
+        metrics_ip,
+        ..
+    }) = &args.command
+    else {
+        return;
+    };
+    let ip = metrics_ip.unwrap_or(IpAddr::V4(Ipv4Addr::LOCALHOST));
+    let listener = match TcpListener::bind((ip, *port)) {
+        Ok(listener) => listener,
+        Err(e) => {
+            error!("Cannot serve metrics on {}:{}: {}", ip, port, e);
+            return;
+        }
+    };
+    info!("Serving metrics on http://{}:{}/metrics", ip, port);
+    let shared = Arc::new(Mutex::new(Shared::default()));
+    server::serve(listener, shared.clone());
+    add_systems(app, shared);
+}
+
+fn add_systems(app: &mut App, shared: Arc<Mutex<Shared>>) {
+    app.insert_resource(MetricsHandle(shared));
+    time_ticks(app);
+    app.add_systems(
+        Update,
+        update_metrics.run_if(resource_exists::<Time>.and_then(on_timer(Duration::from_secs(1)))),
+    );
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn update_metrics(
+    time: Res<Time<Real>>,
+    handle: Res<MetricsHandle>,
+    times: Res<TickTimes>,
+    entities: &Entities,
+    users: Query<(), With<User>>,
+    synced: Query<(), Or<(With<SyncMark>, With<SyncEntity>)>>,
+    stats: Option<Res<NetworkStats>>,
+    meshes: Option<Res<Assets<Mesh>>>,
+    materials: Option<Res<Assets<StandardMaterial>>>,
+    images: Option<Res<Assets<Image>>>,
+) {
+    let ticks = times.latest();
+    let metrics = Metrics {
+        uptime_seconds: time.elapsed_seconds_f64(),
+        users: users.iter().count(),
+        tick_seconds: ticks.average().as_secs_f64(),
+        max_tick_seconds: ticks.max.as_secs_f64(),
+        entities: entities.len(),
+        synced_entities: synced.iter().count(),
+        sent_bytes_per_second: stats.map(|s| s.total_bytes_per_second()).unwrap_or(0.0),
+        meshes: meshes.map(|a| a.len()).unwrap_or(0),
+        materials: materials.map(|a| a.len()).unwrap_or(0),
+        images: images.map(|a| a.len()).unwrap_or(0),
+    };
+    if let Ok(mut shared) = handle.0.lock() {
+        shared.metrics = metrics;
+        shared.updated = Instant::now();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+
+    #[test]
+    fn test_prometheus_format() {
+        let metrics = Metrics {
+            users: 3,
+            meshes: 2,
+            ..default()
+        };
+        let text = metrics.to_prometheus();
+        assert!(text.contains("# TYPE lux_users gauge\nlux_users 3\n"));
+        assert!(text.contains("lux_assets{kind=\"mesh\"} 2\n"));
+    }
+
+    #[test]
+    fn test_metrics_of_the_world() {
+        let shared = Arc::new(Mutex::new(Shared::default()));
+        let mut app = App::new();
+        app.init_resource::<Time>();
+        app.init_resource::<Time<Real>>();
+        add_systems(&mut app, shared.clone());
+        app.world_mut().spawn((User, SyncMark));
+        app.world_mut().spawn(SyncMark);
+        app.world_mut().spawn_empty();
+        app.update();
+        app.world_mut().run_system_once(update_metrics);
+
+        let metrics = shared.lock().unwrap().metrics.clone();
+        assert_eq!(metrics.users, 1);
+        assert_eq!(metrics.synced_entities, 2);
+        assert_eq!(metrics.entities, 3);
+        assert!(metrics.tick_seconds > 0.0);
+    }
+}
@@ -0,0 +1,115 @@
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use bevy::log::{error, warn};
+
+use crate::Metrics;
+
+/// Without updates for this long the host is considered stuck.
+const HEALTH_TIMEOUT: Duration = Duration::from_secs(5);
+const READ_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// The last metrics of the app, read by the server thread.
+#[derive(Debug)]
+pub(crate) struct Shared {
+    pub(crate) metrics: Metrics,
+    pub(crate) updated: Instant,
+}
+
+impl Default for Shared {
+    fn default() -> Self {
+        Self {
+            metrics: Metrics::default(),
+            updated: Instant::now(),
+        }
+    }
+}
+
+/// Answers on its own thread, one request at a time.
+pub(crate) fn serve(listener: TcpListener, shared: Arc<Mutex<Shared>>) {
+    let spawned = thread::Builder::new()
+        .name("metrics".to_owned())
+        .spawn(move || {
+            for stream in listener.incoming() {
+                match stream {
+                    Ok(stream) => {
+                        if let Err(e) = answer(stream, &shared) {
+                            warn!("Metrics request failed: {}", e);
+                        }
+                    }
+                    Err(e) => warn!("Metrics connection failed: {}", e),
+                }
+            }
+        });
+    if let Err(e) = spawned {
+        error!("Cannot serve metrics: {}", e);
+    }
+}
+
+fn answer(mut stream: TcpStream, shared: &Mutex<Shared>) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut request = String::new();
+    BufReader::new(&stream).read_line(&mut request)?;
+    let path = request.split_whitespace().nth(1).unwrap_or("");
+    let (status, body) = match (request.split_whitespace().next(), path) {
+        (Some("GET"), "/metrics") => match shared.lock() {
+            Ok(shared) => ("200 OK", shared.metrics.to_prometheus()),
+            Err(_) => ("500 Internal Server Error", String::new()),
+        },
+        (Some("GET"), "/health") => match shared.lock() {
+            Ok(shared) if shared.updated.elapsed() < HEALTH_TIMEOUT => {
+                ("200 OK", "ok\n".to_owned())
+            }
+            _ => ("503 Service Unavailable", "stalled\n".to_owned()),
+        },
+        _ => ("404 Not Found", String::new()),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn test_health_and_metrics() {
+        let (address, shared) = start();
+        assert!(get(&address, "/health").starts_with("HTTP/1.1 200"));
+        let metrics = get(&address, "/metrics");
+        assert!(metrics.starts_with("HTTP/1.1 200"));
+        assert!(metrics.contains("lux_users 0"));
+        assert!(get(&address, "/nothing").starts_with("HTTP/1.1 404"));
+
+        shared.lock().unwrap().updated = Instant::now() - HEALTH_TIMEOUT;
+        assert!(get(&address, "/health").starts_with("HTTP/1.1 503"));
+    }
+
+    fn start() -> (String, Arc<Mutex<Shared>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let shared = Arc::new(Mutex::new(Shared::default()));
+        serve(listener, shared.clone());
+        (address, shared)
+    }
+
+    fn get(address: &str, path: &str) -> String {
+        let mut stream = TcpStream::connect(address).unwrap();
+        write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+}
@@ -0,0 +1,404 @@
+use std::{any::type_name, collections::HashMap, marker::PhantomData};
+
+use bevy::{
+    prelude::*,
+    reflect::{serde::TypedReflectSerializer, GetTypeRegistration, TypePath},
+};
+use bevy_sync::{SyncEntity, SyncExclude, SyncMark};
+use lux_components::{ControlledBy, LocalUser};
+
+/// Rough size of the message around each component change: entity id and header.
+const MESSAGE_OVERHEAD: usize = 24;
+
+/// How often changes of a component are sent.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SendRate {
+    /// Every change as it happens.
+    OnChange,
+    /// At most this many times per second, the latest value wins.
+    Hz(f32),
+}
+
+/// Send rates per component, and how distance lowers them.
+#[derive(Resource, Debug, Clone)]
+pub struct SyncRates {
+    rates: HashMap<&'static str, SendRate>,
+    /// Entities closer than this to the local user send at full rate.
+    pub near: f32,
+    /// From here on entities send at `min_factor` of their rate.
+    pub far: f32,
+    pub min_factor: f32,
+}
+
+impl Default for SyncRates {
+    fn default() -> Self {
+        let mut rates = Self {
+            rates: HashMap::new(),
+            near: 10.0,
+            far: 50.0,
+            min_factor: 0.2,
+        };
+        rates.set::<Transform>(SendRate::Hz(30.0));
+        rates
+    }
+}
+
+impl SyncRates {
+    pub fn set<C: Component>(&mut self, rate: SendRate) {
+        self.rates.insert(type_name::<C>(), rate);
+    }
+
+    pub fn get<C: Component>(&self) -> SendRate {
+        self.rates
+            .get(type_name::<C>())
+            .copied()
+            .unwrap_or(SendRate::OnChange)
+    }
+
+    /// Seconds between two sends, longer for distant entities.
+    pub fn interval(&self, hz: f32, distance: Option<f32>) -> f32 {
+        let factor = match distance {
+            Some(d) if d > self.near => {
+                let t = ((d - self.near) / (self.far - self.near).max(f32::EPSILON)).min(1.0);
+                1.0 - t * (1.0 - self.min_factor)
+            }
+            _ => 1.0,
+        };
+        1.0 / (hz * factor).max(f32::EPSILON)
+    }
+}
+
+/// Bytes and changes per second going out, per component type.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ComponentStats {
+    pub bytes_per_second: f32,
+    pub changes_per_second: f32,
+}
+
+/// Bytes and changes going out per component type. Those sent by bevy_sync
+/// are estimated from the changes of synched components that are not
+/// excluded, sizes come from the JSON form of the first change of each type.
+/// Transforms of the transform channel are counted as they are sent.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct NetworkStats {
+    pub components: HashMap<String, ComponentStats>,
+    counting: HashMap<String, (usize, usize)>,
+    sizes: HashMap<&'static str, usize>,
+    elapsed: f32,
+}
+
+impl NetworkStats {
+    pub fn total_bytes_per_second(&self) -> f32 {
+        self.components.values().map(|c| c.bytes_per_second).sum()
+    }
+
+    fn count(&mut self, name: &str, changes: usize, size: usize) {
+        let counting = self.counting.entry(name.to_owned()).or_default();
+        counting.0 += changes;
+        counting.1 += changes * (size + MESSAGE_OVERHEAD);
+    }
+
+    /// Counts a message of `bytes` carrying `changes` as it is sent.
+    pub(crate) fn count_sent(&mut self, name: &str, changes: usize, bytes: usize) {
+        let counting = self.counting.entry(name.to_owned()).or_default();
+        counting.0 += changes;
+        counting.1 += bytes;
+    }
+}
+
+/// Marks the SyncExclude held by the throttling, see ControlledBy.
+#[derive(Component, Default)]
+pub struct Throttled;
+
+#[derive(Component)]
+struct SendWindow<C> {
+    last_sent: f64,
+    held: bool,
+    dirty: bool,
+    _c: PhantomData<C>,
+}
+
+pub trait SyncBandwidthExt {
+    /// Counts the changes of C in NetworkStats.
+    fn track_sync<C: Component + Reflect + GetTypeRegistration>(&mut self) -> &mut Self;
+    /// Sends C at most at its rate in SyncRates. The value itself is never
+    /// changed, the transport sends it as it is.
+    fn throttle_sync<C: Component + Default + TypePath>(&mut self) -> &mut Self;
+}
+
+impl SyncBandwidthExt for App {
+    fn track_sync<C: Component + Reflect + GetTypeRegistration>(&mut self) -> &mut Self {
+        self.add_systems(Last, track_changes::<C>)
+    }
+
+    fn throttle_sync<C: Component + Default + TypePath>(&mut self) -> &mut Self {
+        self.add_systems(PostUpdate, throttle::<C>)
+    }
+}
+
+pub(crate) fn init(app: &mut App) {
+    app.init_resource::<SyncRates>();
+    app.init_resource::<NetworkStats>();
+    app.add_systems(First, publish_stats);
+    app.throttle_sync::<Transform>();
+}
+
+type Synced = Or<(With<SyncMark>, With<SyncEntity>)>;
+
+#[allow(clippy::type_complexity)]
+fn throttle<C: Component + Default + TypePath>(
+    mut commands: Commands,
+    time: Res<Time>,
+    rates: Res<SyncRates>,
+    viewers: Query<&GlobalTransform, Or<(With<LocalUser>, With<Camera3d>)>>,
+    mut query: Query<
+        (
+            Entity,
+            &mut C,
+            Option<&GlobalTransform>,
+            Option<&mut SendWindow<C>>,
+            Has<SyncExclude<C>>,
+        ),
+        Synced,
+    >,
+) {
+    let SendRate::Hz(hz) = rates.get::<C>() else {
+        return;
+    };
+    let now = time.elapsed_seconds_f64();
+    let viewer = viewers.iter().next().map(|v| v.translation());
+    for (entity, mut value, position, window, excluded) in query.iter_mut() {
+        let Some(mut window) = window else {
+            commands.entity(entity).insert(SendWindow::<C> {
+                last_sent: f64::NEG_INFINITY,
+                held: false,
+                dirty: false,
+                _c: PhantomData,
+            });
+            continue;
+        };
+        // Excluded by someone else, like another peer owning it.
+        if excluded && !window.held {
+            continue;
+        }
+        let distance = viewer
+            .zip(position)
+            .map(|(v, p)| v.distance(p.translation()));
+        let due = now - window.last_sent >= rates.interval(hz, distance) as f64;
+        let changed = value.is_changed();
+        if !window.held {
+            if !changed {
+                continue;
+            }
+            if due {
+                window.last_sent = now;
+            } else {
+                window.held = true;
+                window.dirty = true;
+                commands
+                    .entity(entity)
+                    .insert(ControlledBy::<C, Throttled>::default());
+            }
+            continue;
+        }
+        window.dirty |= changed;
+        if due {
+            commands
+                .entity(entity)
+                .remove::<ControlledBy<C, Throttled>>();
+            window.held = false;
+            if window.dirty {
+                // Marks it changed, so the latest value goes out now.
+                value.set_changed();
+                window.last_sent = now;
+            }
+            window.dirty = false;
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn track_changes<C: Component + Reflect + GetTypeRegistration>(
+    mut stats: ResMut<NetworkStats>,
+    registry: Res<AppTypeRegistry>,
+    changed: Query<&C, (Changed<C>, Synced, Without<SyncExclude<C>>)>,
+) {
+    let name = type_name::<C>();
+    let changes = changed.iter().count();
+    if changes == 0 {
+        return;
+    }
+    let size = match stats.sizes.get(name) {
+        Some(size) => *size,
+        None => {
+            let value = changed.iter().next().unwrap();
+            let registry = registry.read();
+            let serializer = TypedReflectSerializer::new(value.as_reflect(), &registry);
+            let size = serde_json::to_string(&serializer)
+                .map(|s| s.len())
+                .unwrap_or(std::mem::size_of::<C>());
+            stats.sizes.insert(name, size);
+            size
+        }
+    };
+    let short = name.rsplit("::").next().unwrap_or(name);
+    stats.count(short, changes, size);
+}
+
+/// Counts of the past frames are turned into rates once per second.
+fn publish_stats(time: Res<Time>, mut stats: ResMut<NetworkStats>) {
+    stats.elapsed += time.delta_seconds();
+    if stats.elapsed < 1.0 {
+        return;
+    }
+    let elapsed = std::mem::take(&mut stats.elapsed);
+    let counting = std::mem::take(&mut stats.counting);
+    for component in stats.components.values_mut() {
+        *component = ComponentStats::default();
+    }
+    for (name, (changes, bytes)) in counting {
+        stats.components.insert(
+            name,
+            ComponentStats {
+                bytes_per_second: bytes as f32 / elapsed,
+                changes_per_second: changes as f32 / elapsed,
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_distant_entities_send_less() {
+        let rates = SyncRates::default();
+        assert_eq!(rates.interval(10.0, None), 0.1);
+        assert_eq!(rates.interval(10.0, Some(5.0)), 0.1);
+        assert!((rates.interval(10.0, Some(50.0)) - 0.5).abs() < 1e-5);
+        assert!((rates.interval(10.0, Some(500.0)) - 0.5).abs() < 1e-5);
+        let halfway = rates.interval(10.0, Some(30.0));
+        assert!(halfway > 0.1 && halfway < 0.5);
+    }
+
+    #[test]
+    fn test_unknown_components_send_on_change() {
+        let rates = SyncRates::default();
+        assert_eq!(rates.get::<Name>(), SendRate::OnChange);
+        assert_eq!(rates.get::<Transform>(), SendRate::Hz(30.0));
+    }
+
+    #[test]
+    fn test_changes_held_until_due() {
+        let mut app = setup(10.0);
+        let entity = app.world_mut().spawn((SyncMark, Transform::default())).id();
+        app.update();
+
+        move_and_advance(&mut app, entity, 0.02);
+        assert!(!excluded(&app, entity), "first change goes right away");
+        move_and_advance(&mut app, entity, 0.02);
+        assert!(
+            excluded(&app, entity),
+            "next change within the window is held"
+        );
+        for _ in 0..5 {
+            move_and_advance(&mut app, entity, 0.02);
+        }
+        assert!(!excluded(&app, entity), "released once due");
+    }
+
+    #[test]
+    fn test_local_value_left_exact() {
+        let mut app = setup(10.0);
+        let entity = app.world_mut().spawn((SyncMark, Transform::default())).id();
+        app.update();
+        for _ in 0..10 {
+            app.world_mut()
+                .get_mut::<Transform>(entity)
+                .unwrap()
+                .translation
+                .x += 0.000_123;
+            app.world_mut()
+                .resource_mut::<Time>()
+                .advance_by(Duration::from_secs_f32(0.03));
+            app.update();
+        }
+        let x = app.world().get::<Transform>(entity).unwrap().translation.x;
+        assert!((x - 0.001_23).abs() < 1e-7, "{}", x);
+    }
+
+    #[test]
+    fn test_remote_excluded_left_alone() {
+        let mut app = setup(10.0);
+        let entity = app
+            .world_mut()
+            .spawn((
+                SyncMark,
+                Transform::default(),
+                ControlledBy::<Transform, Name>::default(),
+            ))
+            .id();
+        app.update();
+        for _ in 0..10 {
+            move_and_advance(&mut app, entity, 0.02);
+        }
+        assert!(excluded(&app, entity));
+        assert!(
+            !app.world()
+                .get::<SendWindow<Transform>>(entity)
+                .unwrap()
+                .held
+        );
+    }
+
+    #[test]
+    fn test_stats_count_changes() {
+        let mut app = setup(1000.0);
+        app.add_systems(Last, track_changes::<Transform>);
+        let entity = app.world_mut().spawn((SyncMark, Transform::default())).id();
+        app.update();
+        for _ in 0..11 {
+            move_and_advance(&mut app, entity, 0.1);
+        }
+
+        let stats = app.world().resource::<NetworkStats>();
+        let transform = stats.components["Transform"];
+        assert!(
+            transform.changes_per_second >= 9.0 && transform.changes_per_second <= 11.0,
+            "{:?}",
+            transform
+        );
+        assert!(transform.bytes_per_second > transform.changes_per_second * 24.0);
+        assert_eq!(stats.total_bytes_per_second(), transform.bytes_per_second);
+    }
+
+    fn setup(hz: f32) -> App {
+        let mut app = App::new();
+        app.init_resource::<Time>();
+        app.init_resource::<AppTypeRegistry>();
+        app.register_type::<Transform>();
+        init(&mut app);
+        app.world_mut()
+            .resource_mut::<SyncRates>()
+            .set::<Transform>(SendRate::Hz(hz));
+        app
+    }
+
+    fn move_and_advance(app: &mut App, entity: Entity, seconds: f32) {
+        app.world_mut()
+            .get_mut::<Transform>(entity)
+            .unwrap()
+            .translation
+            .x += 1.0;
+        app.world_mut()
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs_f32(seconds));
+        app.update();
+    }
+
+    fn excluded(app: &App, entity: Entity) -> bool {
+        app.world().get::<SyncExclude<Transform>>(entity).is_some()
+    }
+}
@@ -0,0 +1,364 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::{
+    pbr::wireframe::Wireframe, prelude::*, reflect::TypePath, render::primitives::Aabb,
+    transform::TransformSystem,
+};
+use bevy_sync::{SyncEntity, SyncMark};
+use lux_components::{Authority, ControlledBy, LocalPeer, User};
+
+/// Radius used when none is given with `--interest-radius`.
+pub const DEFAULT_INTEREST_RADIUS: f32 = 200.0;
+
+/// How far from a user entities are still relevant to them.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct InterestSettings {
+    pub radius: f32,
+}
+
+impl Default for InterestSettings {
+    fn default() -> Self {
+        Self {
+            radius: DEFAULT_INTEREST_RADIUS,
+        }
+    }
+}
+
+/// Relevant to every user wherever they are.
+/// Users and directional lights are always relevant without it.
+#[derive(Component, Default, Debug, Clone, Copy)]
+pub struct AlwaysRelevant;
+
+/// A box volume, scaled and rotated with the entity: users inside it get
+/// every entity inside it, however far.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct InterestZone {
+    pub half_extents: Vec3,
+}
+
+impl InterestZone {
+    fn contains(&self, zone: &GlobalTransform, point: Vec3) -> bool {
+        let local = zone.affine().inverse().transform_point3(point);
+        local.abs().cmple(self.half_extents).all()
+    }
+}
+
+/// Which synched entities are relevant to each peer, by the position of
+/// their users. The transform channel sends each client only the transforms
+/// relevant to it. bevy_sync sends to every client alike, so the other
+/// components are sent to everybody as long as anybody is interested, see
+/// SyncInterestExt.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct Interest {
+    pub peers: HashMap<u64, HashSet<Entity>>,
+}
+
+impl Interest {
+    pub fn is_relevant(&self, peer: u64, entity: Entity) -> bool {
+        self.peers
+            .get(&peer)
+            .map(|e| e.contains(&entity))
+            .unwrap_or(false)
+    }
+
+    pub fn relevant_to_anyone(&self, entity: Entity) -> bool {
+        self.peers.values().any(|e| e.contains(&entity))
+    }
+}
+
+/// An entity came into or went out of the interest of a peer.
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InterestChanged {
+    pub peer: u64,
+    pub entity: Entity,
+    pub relevant: bool,
+}
+
+/// Marks the SyncExclude held by interest management, see ControlledBy.
+#[derive(Component, Default)]
+pub struct OutOfInterest;
+
+pub trait SyncInterestExt {
+    /// Stops sending changes of C for entities that are relevant to no peer.
+    /// Spawns, despawns and assets still go out to everybody.
+    fn interest_sync<C: Component + Default + TypePath>(&mut self) -> &mut Self;
+}
+
+impl SyncInterestExt for App {
+    fn interest_sync<C: Component + Default + TypePath>(&mut self) -> &mut Self {
+        self.add_systems(PostUpdate, filter_interest::<C>.after(update_interest))
+    }
+}
+
+pub(crate) fn init(app: &mut App, radius: Option<f32>) {
+    app.insert_resource(InterestSettings {
+        radius: radius.unwrap_or(DEFAULT_INTEREST_RADIUS),
+    });
+    app.init_resource::<Interest>();
+    app.add_event::<InterestChanged>();
+    app.add_systems(
+        PostUpdate,
+        update_interest.after(TransformSystem::TransformPropagate),
+    );
+    app.interest_sync::<Visibility>()
+        .interest_sync::<Name>()
+        .interest_sync::<Aabb>()
+        .interest_sync::<Wireframe>()
+        .interest_sync::<PointLight>()
+        .interest_sync::<SpotLight>()
+        .interest_sync::<Handle<Mesh>>()
+        .interest_sync::<Handle<StandardMaterial>>();
+}
+
+type Synced = Or<(With<SyncMark>, With<SyncEntity>)>;
+
+#[allow(clippy::type_complexity)]
+pub(crate) fn update_interest(
+    settings: Res<InterestSettings>,
+    peer: Option<Res<LocalPeer>>,
+    mut interest: ResMut<Interest>,
+    mut changes: EventWriter<InterestChanged>,
+    users: Query<(&GlobalTransform, Option<&Authority>), With<User>>,
+    zones: Query<(&GlobalTransform, &InterestZone)>,
+    entities: Query<
+        (
+            Entity,
+            &GlobalTransform,
+            Has<AlwaysRelevant>,
+            Has<User>,
+            Has<DirectionalLight>,
+        ),
+        Synced,
+    >,
+) {
+    let local = peer.map(|p| p.id).unwrap_or_default();
+    let mut viewers: HashMap<u64, Vec<Vec3>> = HashMap::new();
+    for (transform, authority) in users.iter() {
+        // Users are claimed by their peer, unclaimed ones are from here.
+        let owner = authority
+            .and_then(|a| a.owner::<Transform>())
+            .unwrap_or(local);
+        viewers
+            .entry(owner)
+            .or_default()
+            .push(transform.translation());
+    }
+    let zones: Vec<_> = zones.iter().collect();
+    let mut peers = HashMap::new();
+    for (peer, positions) in viewers {
+        let relevant: HashSet<Entity> = entities
+            .iter()
+            .filter(|(_, transform, always, user, light)| {
+                let at = transform.translation();
+                *always
+                    || *user
+                    || *light
+                    || positions.iter().any(|p| {
+                        p.distance(at) <= settings.radius
+                            || zones
+                                .iter()
+                                .any(|(z, zone)| zone.contains(z, *p) && zone.contains(z, at))
+                    })
+            })
+            .map(|(entity, ..)| entity)
+            .collect();
+        peers.insert(peer, relevant);
+    }
+
+    for (peer, relevant) in peers.iter() {
+        let before = interest.peers.get(peer);
+        for entity in relevant.iter() {
+            if !before.map(|b| b.contains(entity)).unwrap_or(false) {
+                changes.send(InterestChanged {
+                    peer: *peer,
+                    entity: *entity,
+                    relevant: true,
+                });
+            }
+        }
+    }
+    for (peer, before) in interest.peers.iter() {
+        let now = peers.get(peer);
+        for entity in before.iter() {
+            if !now.map(|n| n.contains(entity)).unwrap_or(false) {
+                changes.send(InterestChanged {
+                    peer: *peer,
+                    entity: *entity,
+                    relevant: false,
+                });
+            }
+        }
+    }
+    interest.peers = peers;
+}
+
+/// bevy_sync sends to every client alike, so an entity is sent to all of
+/// them as long as any peer is interested in it.
+#[allow(clippy::type_complexity)]
+fn filter_interest<C: Component + Default + TypePath>(
+    mut commands: Commands,
+    interest: Res<Interest>,
+    mut query: Query<
+        (Entity, &mut C, Has<ControlledBy<C, OutOfInterest>>),
+        (Synced, With<GlobalTransform>),
+    >,
+) {
+    for (entity, mut value, out) in query.iter_mut() {
+        let relevant = interest.relevant_to_anyone(entity);
+        if !relevant && !out {
+            commands
+                .entity(entity)
+                .insert(ControlledBy::<C, OutOfInterest>::default());
+        } else if relevant && out {
+            commands
+                .entity(entity)
+                .remove::<ControlledBy<C, OutOfInterest>>();
+            // Streams it back in with its latest value.
+            value.set_changed();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bevy_sync::SyncExclude;
+
+    const FAR: f32 = 1000.0;
+
+    #[test]
+    fn test_each_peer_gets_what_is_near() {
+        let mut app = setup();
+        user(&mut app, 1, Vec3::ZERO);
+        user(&mut app, 2, Vec3::X * FAR);
+        let near_first = synced(&mut app, Vec3::X * 10.0);
+        let near_second = synced(&mut app, Vec3::X * (FAR - 10.0));
+        app.update();
+
+        let interest = app.world().resource::<Interest>();
+        assert!(interest.is_relevant(1, near_first));
+        assert!(!interest.is_relevant(1, near_second));
+        assert!(interest.is_relevant(2, near_second));
+        assert!(!interest.is_relevant(2, near_first));
+    }
+
+    #[test]
+    fn test_always_relevant() {
+        let mut app = setup();
+        let first = user(&mut app, 1, Vec3::ZERO);
+        user(&mut app, 2, Vec3::X * FAR);
+        let global = synced(&mut app, Vec3::Y * FAR);
+        app.world_mut().entity_mut(global).insert(AlwaysRelevant);
+        app.update();
+
+        let interest = app.world().resource::<Interest>();
+        assert!(interest.is_relevant(1, global));
+        assert!(interest.is_relevant(2, global));
+        assert!(interest.is_relevant(2, first), "users see each other");
+    }
+
+    #[test]
+    fn test_zone_shares_interest() {
+        let mut app = setup();
+        user(&mut app, 1, Vec3::ZERO);
+        let inside = synced(&mut app, Vec3::X * 500.0);
+        let outside = synced(&mut app, Vec3::X * 700.0);
+        app.world_mut().spawn((
+            GlobalTransform::from_translation(Vec3::X * 250.0),
+            InterestZone {
+                half_extents: Vec3::splat(300.0),
+            },
+        ));
+        app.update();
+
+        let interest = app.world().resource::<Interest>();
+        assert!(interest.is_relevant(1, inside));
+        assert!(!interest.is_relevant(1, outside));
+    }
+
+    #[test]
+    fn test_entities_stream_in_and_out() {
+        let mut app = setup();
+        let user = user(&mut app, 1, Vec3::ZERO);
+        let entity = synced(&mut app, Vec3::X * 10.0);
+        app.update();
+        assert!(!excluded(&app, entity));
+
+        move_to(&mut app, user, Vec3::X * FAR);
+        assert!(excluded(&app, entity), "nobody is near, not sent");
+        assert!(app
+            .world_mut()
+            .resource_mut::<Events<InterestChanged>>()
+            .drain()
+            .any(|c| c.entity == entity && !c.relevant));
+
+        move_to(&mut app, user, Vec3::ZERO);
+        assert!(!excluded(&app, entity), "streamed back in");
+    }
+
+    #[test]
+    fn test_what_clients_are_sent() {
+        let mut app = setup();
+        user(&mut app, 1, Vec3::ZERO);
+        user(&mut app, 2, Vec3::X * FAR);
+        let near_first = synced(&mut app, Vec3::X * 10.0);
+        let near_nobody = synced(&mut app, Vec3::Y * FAR);
+        for entity in [near_first, near_nobody] {
+            app.world_mut().entity_mut(entity).insert(Name::new(""));
+        }
+        app.update();
+        app.update();
+
+        // One bevy_sync for all: what the first is near goes to the second too.
+        let sent = |app: &App, entity| {
+            let e = app.world().entity(entity);
+            !e.contains::<SyncExclude<Visibility>>() && !e.contains::<SyncExclude<Name>>()
+        };
+        assert!(sent(&app, near_first));
+        let e = app.world().entity(near_nobody);
+        assert!(e.contains::<SyncExclude<Visibility>>());
+        assert!(e.contains::<SyncExclude<Name>>());
+    }
+
+    fn setup() -> App {
+        let mut app = App::new();
+        init(&mut app, None);
+        app
+    }
+
+    fn user(app: &mut App, peer: u64, at: Vec3) -> Entity {
+        let mut authority = Authority::default();
+        authority.claim::<Transform>(peer);
+        app.world_mut()
+            .spawn((
+                SyncMark,
+                User,
+                authority,
+                Transform::from_translation(at),
+                GlobalTransform::from_translation(at),
+            ))
+            .id()
+    }
+
+    fn synced(app: &mut App, at: Vec3) -> Entity {
+        app.world_mut()
+            .spawn((
+                SyncMark,
+                Transform::from_translation(at),
+                GlobalTransform::from_translation(at),
+                Visibility::default(),
+            ))
+            .id()
+    }
+
+    fn move_to(app: &mut App, entity: Entity, at: Vec3) {
+        *app.world_mut().get_mut::<GlobalTransform>(entity).unwrap() =
+            GlobalTransform::from_translation(at);
+        app.update();
+        app.update();
+    }
+
+    fn excluded(app: &App, entity: Entity) -> bool {
+        app.world().get::<SyncExclude<Visibility>>(entity).is_some()
+    }
+}
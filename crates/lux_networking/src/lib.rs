@@ -1,6 +1,14 @@
+mod bandwidth;
+mod interest;
+mod recording;
+mod replay;
+mod simulation;
+mod transforms;
+
 use bevy::{
     pbr::wireframe::Wireframe,
     prelude::*,
+    reflect::{GetTypeRegistration, TypePath},
     render::{
         mesh::{morph::MeshMorphWeights, skinning::SkinnedMesh},
         primitives::Aabb,
@@ -8,10 +16,26 @@ use bevy::{
 };
 use bevy_sync::prelude::*;
 use lux_cli::{Args, Command};
-use std::net::{IpAddr, Ipv6Addr};
+use std::{
+    net::{IpAddr, Ipv6Addr},
+    path::Path,
+};
 
-static SYNC_PORT: u16 = 4001;
-static WEB_PORT: u16 = 4002;
+pub use bandwidth::{
+    ComponentStats, NetworkStats, SendRate, SyncBandwidthExt, SyncRates, Throttled,
+};
+pub use interest::{
+    AlwaysRelevant, Interest, InterestChanged, InterestSettings, InterestZone, OutOfInterest,
+    SyncInterestExt, DEFAULT_INTEREST_RADIUS,
+};
+pub use recording::{
+    record, RecordedChange, RecordedEvent, Recording, SyncRecordingExt, RECORDING_VERSION,
+};
+pub use replay::{replay, Replay, ReplayHandleExt, Replayed};
+pub use simulation::{simulate_link, LinkConditions, SimulatedLink};
+pub use transforms::{
+    host_transforms, join_transforms, transforms_address, StreamedOut, TransformChannel,
+};
 
 pub fn init(args: &Args, app: &mut App) {
     setup_sync(args, app);
@@ -22,45 +46,101 @@ fn setup_sync(args: &Args, app: &mut App) {
         return;
     }
     app.add_plugins(SyncPlugin);
-    app.sync_component::<Name>();
-    app.sync_component::<Aabb>();
-    app.sync_component::<Visibility>();
-    app.sync_component::<Transform>();
-    app.sync_component::<Wireframe>();
-    app.sync_component::<PointLight>();
-    app.sync_component::<SpotLight>();
-    app.sync_component::<DirectionalLight>();
-    app.sync_component::<MeshMorphWeights>();
-    app.sync_component::<SkinnedMesh>();
-    app.sync_component::<Handle<StandardMaterial>>();
-    app.sync_component::<Handle<Mesh>>();
-    app.sync_component::<Handle<Image>>();
+    bandwidth::init(app);
+    sync::<Name>(app);
+    sync::<Aabb>(app);
+    sync::<Visibility>(app);
+    sync::<Transform>(app);
+    sync::<Wireframe>(app);
+    sync::<PointLight>(app);
+    sync::<SpotLight>(app);
+    sync::<DirectionalLight>(app);
+    sync::<MeshMorphWeights>(app);
+    sync::<SkinnedMesh>(app);
+    sync_handle::<StandardMaterial>(app);
+    sync_handle::<Mesh>(app);
+    sync_handle::<Image>(app);
     app.sync_materials(true);
     app.sync_meshes(true);
+    if let Some(Command::Host { .. }) = &args.command {
+        interest::init(app, args.interest_radius);
+    }
+    if let Some(path) = &args.record {
+        if let Err(e) = record(app, Path::new(path)) {
+            error!("Cannot record to {}: {}", path, e);
+        }
+    }
+    if let Some(conditions) = LinkConditions::from_args(args) {
+        simulate_link(app, conditions);
+    }
 
     let localhost = IpAddr::V6(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 0));
+    let channel = match &args.command {
+        Some(Command::Host { ip, port, .. }) => {
+            host_transforms(app, transforms_address(ip.unwrap_or(localhost), *port))
+        }
+        Some(Command::Join { ip, port, .. }) => {
+            join_transforms(app, transforms_address(*ip, *port))
+        }
+        _ => Ok(()),
+    };
+    if let Err(e) = channel {
+        error!("Cannot open the transform channel: {}", e);
+    }
     match &args.command {
         Some(Command::Host {
             world_file: _,
             headless: _,
             ip,
+            port,
             avatar_file: _,
+            metrics: _,
+            metrics_ip: _,
         }) => app.add_plugins(ServerPlugin {
             parameters: SyncConnectionParameters::Socket {
                 ip: ip.unwrap_or(localhost),
-                port: SYNC_PORT,
-                web_port: WEB_PORT,
+                port: *port,
+                web_port: port + 1,
                 max_transfer: 1_000_000_000,
             },
         }),
-        Some(Command::Join { ip, avatar_file: _ }) => app.add_plugins(ClientPlugin {
+        Some(Command::Join {
+            ip,
+            port,
+            avatar_file: _,
+            web_port,
+        }) => app.add_plugins(ClientPlugin {
             parameters: SyncConnectionParameters::Socket {
                 ip: ip.clone().to_owned(),
-                port: SYNC_PORT,
-                web_port: WEB_PORT + 1,
+                port: *port,
+                web_port: web_port.unwrap_or(port + 2),
                 max_transfer: 1_000_000_000,
             },
         }),
         _ => app,
     };
 }
+
+/// Synched, counted in the network statistics and recorded.
+fn sync<C: Component + TypePath + Reflect + FromReflect + GetTypeRegistration>(app: &mut App) {
+    app.sync_component::<C>();
+    app.track_sync::<C>();
+    app.record_sync::<C>();
+}
+
+/// Handles are recorded by the path of their asset, which replays load.
+fn sync_handle<A: Asset>(app: &mut App) {
+    app.sync_component::<Handle<A>>();
+    app.track_sync::<Handle<A>>();
+    app.record_handle::<A>();
+}
+
+/// Reads the recording for `lux replay`.
+pub fn init_replay(file: &str, speed: f32, app: &mut App) -> Result<(), String> {
+    let recording = Recording::read(Path::new(file))?;
+    replay(app, recording, speed);
+    app.replay_handle::<StandardMaterial>();
+    app.replay_handle::<Mesh>();
+    app.replay_handle::<Image>();
+    Ok(())
+}
@@ -0,0 +1,398 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::Path,
+};
+
+use bevy::{
+    ecs::entity::Entities,
+    prelude::*,
+    reflect::{serde::TypedReflectSerializer, GetTypeRegistration, TypePath},
+};
+use bevy_sync::{SyncEntity, SyncMark};
+use lux_components::AssetSources;
+use serde_json::{json, Value};
+
+/// First line of every recording.
+pub const RECORDING_VERSION: u64 = 1;
+
+/// What happened to an entity.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RecordedChange {
+    /// A component was added or changed, with its reflected value.
+    Set {
+        component: String,
+        value: Value,
+    },
+    Remove {
+        component: String,
+    },
+    Parent(Option<u64>),
+    Despawn,
+}
+
+/// A change at `time` seconds since the recording started. Entities are
+/// told apart by their id in the recorded session.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedEvent {
+    pub time: f64,
+    pub entity: u64,
+    pub change: RecordedChange,
+}
+
+impl RecordedEvent {
+    fn to_json(&self) -> Value {
+        let mut line = json!({"t": self.time, "e": self.entity});
+        match &self.change {
+            RecordedChange::Set { component, value } => {
+                line["set"] = json!(component);
+                line["value"] = value.clone();
+            }
+            RecordedChange::Remove { component } => line["remove"] = json!(component),
+            RecordedChange::Parent(parent) => line["parent"] = json!(parent),
+            RecordedChange::Despawn => line["despawn"] = json!(true),
+        }
+        line
+    }
+
+    fn from_json(line: &Value) -> Option<Self> {
+        let change = if let Some(component) = line.get("set") {
+            RecordedChange::Set {
+                component: component.as_str()?.to_owned(),
+                value: line.get("value")?.clone(),
+            }
+        } else if let Some(component) = line.get("remove") {
+            RecordedChange::Remove {
+                component: component.as_str()?.to_owned(),
+            }
+        } else if let Some(parent) = line.get("parent") {
+            RecordedChange::Parent(parent.as_u64())
+        } else if line.get("despawn").is_some() {
+            RecordedChange::Despawn
+        } else {
+            return None;
+        };
+        Some(Self {
+            time: line.get("t")?.as_f64()?,
+            entity: line.get("e")?.as_u64()?,
+            change,
+        })
+    }
+}
+
+/// A recorded session, one JSON object per line.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Recording {
+    pub events: Vec<RecordedEvent>,
+}
+
+impl Recording {
+    pub fn read(path: &Path) -> Result<Self, String> {
+        let file = File::open(path).map_err(|e| e.to_string())?;
+        let mut lines = BufReader::new(file).lines();
+        let header = lines
+            .next()
+            .ok_or("empty recording")?
+            .map_err(|e| e.to_string())?;
+        let version = serde_json::from_str::<Value>(&header)
+            .ok()
+            .and_then(|h| h.get("lux_recording").and_then(Value::as_u64));
+        if version != Some(RECORDING_VERSION) {
+            return Err(format!("not a recording of version {}", RECORDING_VERSION));
+        }
+        let mut events = vec![];
+        for (number, line) in lines.enumerate() {
+            let line = line.map_err(|e| e.to_string())?;
+            // A session closed abruptly can leave half a line at the end.
+            let Some(event) = serde_json::from_str::<Value>(&line)
+                .ok()
+                .and_then(|l| RecordedEvent::from_json(&l))
+            else {
+                warn!("Skipping line {} of the recording", number + 2);
+                continue;
+            };
+            events.push(event);
+        }
+        Ok(Self { events })
+    }
+
+    pub fn duration(&self) -> f64 {
+        self.events.last().map(|e| e.time).unwrap_or(0.0)
+    }
+}
+
+#[derive(Resource)]
+struct Recorder {
+    writer: BufWriter<File>,
+    start: Option<f64>,
+    events: Vec<RecordedEvent>,
+}
+
+impl Recorder {
+    fn push(&mut self, time: f64, entity: Entity, change: RecordedChange) {
+        let start = *self.start.get_or_insert(time);
+        self.events.push(RecordedEvent {
+            time: time - start,
+            entity: entity.to_bits(),
+            change,
+        });
+    }
+}
+
+/// Component recorders go first, despawns and writing after them.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+struct RecordComponents;
+
+pub trait SyncRecordingExt {
+    /// Records the changes of C when recording.
+    fn record_sync<C: Component + Reflect + TypePath + GetTypeRegistration>(&mut self)
+        -> &mut Self;
+    /// Records which asset each `Handle<A>` points to when recording.
+    fn record_handle<A: Asset>(&mut self) -> &mut Self;
+}
+
+impl SyncRecordingExt for App {
+    fn record_sync<C: Component + Reflect + TypePath + GetTypeRegistration>(
+        &mut self,
+    ) -> &mut Self {
+        self.add_systems(
+            Last,
+            record_component::<C>
+                .in_set(RecordComponents)
+                .run_if(resource_exists::<Recorder>),
+        )
+    }
+
+    fn record_handle<A: Asset>(&mut self) -> &mut Self {
+        self.add_systems(
+            Last,
+            record_handle::<A>
+                .in_set(RecordComponents)
+                .run_if(resource_exists::<Recorder>),
+        )
+    }
+}
+
+/// Starts writing the changes of synched entities to `path`.
+pub fn record(app: &mut App, path: &Path) -> Result<(), String> {
+    let mut writer = BufWriter::new(File::create(path).map_err(|e| e.to_string())?);
+    writeln!(writer, "{}", json!({"lux_recording": RECORDING_VERSION}))
+        .map_err(|e| e.to_string())?;
+    app.insert_resource(Recorder {
+        writer,
+        start: None,
+        events: vec![],
+    });
+    app.add_systems(
+        Last,
+        (record_parents, record_despawns, write_recording)
+            .chain()
+            .after(RecordComponents)
+            .run_if(resource_exists::<Recorder>.and_then(resource_exists::<Time>)),
+    );
+    Ok(())
+}
+
+type Synced = Or<(With<SyncMark>, With<SyncEntity>)>;
+
+fn record_component<C: Component + Reflect + TypePath + GetTypeRegistration>(
+    time: Option<Res<Time>>,
+    registry: Res<AppTypeRegistry>,
+    entities: &Entities,
+    mut recorder: ResMut<Recorder>,
+    changed: Query<(Entity, &C), (Changed<C>, Synced)>,
+    mut removed: RemovedComponents<C>,
+) {
+    let Some(time) = time else {
+        return;
+    };
+    let now = time.elapsed_seconds_f64();
+    let component = C::type_path().to_owned();
+    let registry = registry.read();
+    for (entity, value) in changed.iter() {
+        let serializer = TypedReflectSerializer::new(value.as_reflect(), &registry);
+        match serde_json::to_value(&serializer) {
+            Ok(value) => {
+                let component = component.clone();
+                recorder.push(now, entity, RecordedChange::Set { component, value });
+            }
+            Err(e) => warn!("Cannot record {}: {}", component, e),
+        }
+    }
+    for entity in removed.read() {
+        if entities.contains(entity) {
+            let component = component.clone();
+            recorder.push(now, entity, RecordedChange::Remove { component });
+        }
+    }
+}
+
+/// Handles are recorded as the path of their asset, or of the file it was
+/// loaded from before getting a uuid. Assets made at runtime or received
+/// from another peer have none and are left out.
+#[allow(clippy::type_complexity)]
+fn record_handle<A: Asset>(
+    time: Option<Res<Time>>,
+    entities: &Entities,
+    sources: Option<Res<AssetSources>>,
+    mut recorder: ResMut<Recorder>,
+    changed: Query<(Entity, &Handle<A>), (Changed<Handle<A>>, Synced)>,
+    mut removed: RemovedComponents<Handle<A>>,
+) {
+    let Some(time) = time else {
+        return;
+    };
+    let now = time.elapsed_seconds_f64();
+    let component = Handle::<A>::type_path().to_owned();
+    for (entity, handle) in changed.iter() {
+        let path = handle.path().map(|p| p.to_string()).or_else(|| {
+            let sources = sources.as_ref()?;
+            sources.get(handle.id()).map(str::to_owned)
+        });
+        let Some(path) = path else {
+            continue;
+        };
+        let value = json!(path);
+        let component = component.clone();
+        recorder.push(now, entity, RecordedChange::Set { component, value });
+    }
+    for entity in removed.read() {
+        if entities.contains(entity) {
+            let component = component.clone();
+            recorder.push(now, entity, RecordedChange::Remove { component });
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn record_parents(
+    time: Res<Time>,
+    entities: &Entities,
+    mut recorder: ResMut<Recorder>,
+    changed: Query<(Entity, &Parent), (Changed<Parent>, Synced)>,
+    mut removed: RemovedComponents<Parent>,
+) {
+    let now = time.elapsed_seconds_f64();
+    for (entity, parent) in changed.iter() {
+        let parent = Some(parent.get().to_bits());
+        recorder.push(now, entity, RecordedChange::Parent(parent));
+    }
+    for entity in removed.read() {
+        if entities.contains(entity) {
+            recorder.push(now, entity, RecordedChange::Parent(None));
+        }
+    }
+}
+
+fn record_despawns(
+    time: Res<Time>,
+    entities: &Entities,
+    mut recorder: ResMut<Recorder>,
+    mut marks: RemovedComponents<SyncMark>,
+    mut synced: RemovedComponents<SyncEntity>,
+) {
+    let now = time.elapsed_seconds_f64();
+    let mut despawned: Vec<_> = marks
+        .read()
+        .chain(synced.read())
+        .filter(|e| !entities.contains(*e))
+        .collect();
+    despawned.dedup();
+    for entity in despawned {
+        recorder.push(now, entity, RecordedChange::Despawn);
+    }
+}
+
+fn write_recording(mut recorder: ResMut<Recorder>) {
+    let recorder = &mut *recorder;
+    for event in recorder.events.drain(..) {
+        let written = writeln!(recorder.writer, "{}", event.to_json());
+        if let Err(e) = written {
+            error!("Recording stopped: {}", e);
+            return;
+        }
+    }
+    if let Err(e) = recorder.writer.flush() {
+        error!("Recording stopped: {}", e);
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_event_roundtrip() {
+        for change in [
+            RecordedChange::Set {
+                component: "Name".to_owned(),
+                value: json!({"a": 1}),
+            },
+            RecordedChange::Remove {
+                component: "Name".to_owned(),
+            },
+            RecordedChange::Parent(Some(3)),
+            RecordedChange::Parent(None),
+            RecordedChange::Despawn,
+        ] {
+            let event = RecordedEvent {
+                time: 1.5,
+                entity: 7,
+                change,
+            };
+            assert_eq!(RecordedEvent::from_json(&event.to_json()), Some(event));
+        }
+    }
+
+    #[test]
+    fn test_records_changes() {
+        let path = temp_file("records_changes");
+        let mut app = recording_app(&path);
+        let entity = app.world_mut().spawn((SyncMark, Transform::default())).id();
+        app.world_mut().spawn(Transform::default());
+        app.update();
+        app.world_mut()
+            .get_mut::<Transform>(entity)
+            .unwrap()
+            .translation
+            .x = 1.0;
+        app.update();
+        app.world_mut().despawn(entity);
+        app.update();
+
+        let recording = Recording::read(&path).unwrap();
+        let changes: Vec<_> = recording.events.iter().map(|e| &e.change).collect();
+        assert_eq!(changes.len(), 3, "{:?}", changes);
+        assert!(matches!(changes[0], RecordedChange::Set { .. }));
+        assert!(matches!(changes[1], RecordedChange::Set { .. }));
+        assert_eq!(changes[2], &RecordedChange::Despawn);
+        assert!(recording
+            .events
+            .iter()
+            .all(|e| e.entity == entity.to_bits()));
+    }
+
+    #[test]
+    fn test_not_a_recording() {
+        let path = temp_file("not_a_recording");
+        std::fs::write(&path, "{}\n").unwrap();
+        assert!(Recording::read(&path).is_err());
+    }
+
+    pub(crate) fn recording_app(path: &Path) -> App {
+        let mut app = App::new();
+        app.init_resource::<Time>();
+        app.init_resource::<AppTypeRegistry>();
+        app.register_type::<Transform>();
+        app.register_type::<Name>();
+        record(&mut app, path).unwrap();
+        app.record_sync::<Transform>();
+        app.record_sync::<Name>();
+        app
+    }
+
+    pub(crate) fn temp_file(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("lux_{}_{}.jsonl", name, std::process::id()))
+    }
+}
@@ -0,0 +1,480 @@
+use std::collections::HashMap;
+
+use bevy::{
+    ecs::{entity::EntityHashMap, reflect::ReflectMapEntities},
+    prelude::*,
+    reflect::{serde::TypedReflectDeserializer, TypePath, TypeRegistration, TypeRegistry},
+};
+use serde::de::DeserializeSeed;
+use serde_json::Value;
+
+use crate::{RecordedChange, RecordedEvent, Recording};
+
+/// Entities spawned by the replay.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Replayed(pub u64);
+
+/// Playback of a recording. Setting `time` backwards replays from the start.
+#[derive(Resource, Debug)]
+pub struct Replay {
+    pub recording: Recording,
+    pub time: f64,
+    pub speed: f32,
+    pub playing: bool,
+    applied: usize,
+    entities: HashMap<u64, Entity>,
+}
+
+impl Replay {
+    pub fn new(recording: Recording, speed: f32) -> Self {
+        Self {
+            recording,
+            time: 0.0,
+            speed,
+            playing: true,
+            applied: 0,
+            entities: HashMap::new(),
+        }
+    }
+
+    pub fn duration(&self) -> f64 {
+        self.recording.duration()
+    }
+
+    pub fn finished(&self) -> bool {
+        self.applied == self.recording.events.len()
+    }
+}
+
+/// Sets or, without a path, removes the handle of an entity.
+type LoadHandle = fn(&mut World, Entity, Option<&str>);
+
+/// How the recorded handles are loaded, by the type path of the handle.
+#[derive(Resource, Default)]
+struct ReplayedHandles(HashMap<String, LoadHandle>);
+
+pub trait ReplayHandleExt {
+    /// Loads the assets of the recorded `Handle<A>` from their path.
+    fn replay_handle<A: Asset>(&mut self) -> &mut Self;
+}
+
+impl ReplayHandleExt for App {
+    fn replay_handle<A: Asset>(&mut self) -> &mut Self {
+        let mut handles = self
+            .world_mut()
+            .get_resource_or_insert_with(ReplayedHandles::default);
+        handles
+            .0
+            .insert(Handle::<A>::type_path().to_owned(), load_handle::<A>);
+        self
+    }
+}
+
+fn load_handle<A: Asset>(world: &mut World, entity: Entity, path: Option<&str>) {
+    let Some(path) = path else {
+        world.entity_mut(entity).remove::<Handle<A>>();
+        return;
+    };
+    let Some(assets) = world.get_resource::<AssetServer>() else {
+        return;
+    };
+    let handle = assets.load::<A>(path.to_owned());
+    world.entity_mut(entity).insert(handle);
+}
+
+/// Shows the recording in this app, nothing is sent or changed by it.
+pub fn replay(app: &mut App, recording: Recording, speed: f32) {
+    app.insert_resource(Replay::new(recording, speed));
+    app.add_systems(
+        PreUpdate,
+        (advance_replay.run_if(resource_exists::<Time>), apply_replay).chain(),
+    );
+}
+
+fn advance_replay(time: Res<Time>, mut replay: ResMut<Replay>) {
+    if !replay.playing {
+        return;
+    }
+    let duration = replay.duration();
+    replay.time = (replay.time + time.delta_seconds_f64() * replay.speed as f64).min(duration);
+    if replay.time >= duration {
+        replay.playing = false;
+    }
+}
+
+fn apply_replay(world: &mut World) {
+    world.resource_scope(|world, mut replay: Mut<Replay>| {
+        let rewound =
+            replay.applied > 0 && replay.recording.events[replay.applied - 1].time > replay.time;
+        if rewound {
+            for (_, entity) in replay.entities.drain() {
+                if let Some(entity) = world.get_entity_mut(entity) {
+                    entity.despawn_recursive();
+                }
+            }
+            replay.applied = 0;
+        }
+        let replay = &mut *replay;
+        while let Some(event) = replay.recording.events.get(replay.applied) {
+            if event.time > replay.time {
+                break;
+            }
+            apply_event(world, &mut replay.entities, event);
+            replay.applied += 1;
+        }
+    });
+}
+
+fn apply_event(world: &mut World, entities: &mut HashMap<u64, Entity>, event: &RecordedEvent) {
+    if event.change == RecordedChange::Despawn {
+        if let Some(entity) = entities.remove(&event.entity) {
+            if let Some(entity) = world.get_entity_mut(entity) {
+                entity.despawn_recursive();
+            }
+        }
+        return;
+    }
+    let entity = spawned(world, entities, event.entity);
+    let load = match &event.change {
+        RecordedChange::Set { component, .. } | RecordedChange::Remove { component } => world
+            .get_resource::<ReplayedHandles>()
+            .and_then(|handles| handles.0.get(component))
+            .copied(),
+        _ => None,
+    };
+    match &event.change {
+        RecordedChange::Set { component, value } => {
+            if let Some(load) = load {
+                load(world, entity, value.as_str());
+                return;
+            }
+            let registry = world.resource::<AppTypeRegistry>().clone();
+            let registry = registry.read();
+            let Some(registration) = registry.get_with_type_path(component) else {
+                warn!("Replay skips unknown component {}", component);
+                return;
+            };
+            if let Err(e) = insert_mapped(world, entities, entity, registration, &registry, value) {
+                warn!("Replay skips {}: {}", component, e);
+            }
+        }
+        RecordedChange::Remove { component } => {
+            if let Some(load) = load {
+                load(world, entity, None);
+                return;
+            }
+            let registry = world.resource::<AppTypeRegistry>().clone();
+            let registry = registry.read();
+            if let Some(reflect) = registry
+                .get_with_type_path(component)
+                .and_then(|r| r.data::<ReflectComponent>())
+            {
+                reflect.remove(&mut world.entity_mut(entity));
+            }
+        }
+        RecordedChange::Parent(Some(parent)) => {
+            let parent = spawned(world, entities, *parent);
+            world.entity_mut(entity).set_parent(parent);
+        }
+        RecordedChange::Parent(None) => {
+            world.entity_mut(entity).remove_parent();
+        }
+        RecordedChange::Despawn => (),
+    }
+}
+
+/// Inserts the recorded component, with the entities it refers to, like the
+/// joints of a skinned mesh, replaced by the replayed ones.
+fn insert_mapped(
+    world: &mut World,
+    entities: &mut HashMap<u64, Entity>,
+    entity: Entity,
+    registration: &TypeRegistration,
+    registry: &TypeRegistry,
+    value: &Value,
+) -> Result<(), String> {
+    let Some(reflect) = registration.data::<ReflectComponent>() else {
+        return Ok(());
+    };
+    let value = TypedReflectDeserializer::new(registration, registry)
+        .deserialize(value)
+        .map_err(|e| e.to_string())?;
+    reflect.insert(&mut world.entity_mut(entity), &*value, registry);
+    let Some(map_entities) = registration.data::<ReflectMapEntities>() else {
+        return Ok(());
+    };
+    let mut mapped = mapped_entities(entities);
+    map_entities.map_entities(world, &mut mapped, &[entity]);
+    // Entities not replayed yet were mapped to dead ones, they are spawned
+    // and the component mapped again.
+    let unknown: Vec<u64> = mapped
+        .keys()
+        .map(|recorded| recorded.to_bits())
+        .filter(|recorded| !entities.contains_key(recorded))
+        .collect();
+    if unknown.is_empty() {
+        return Ok(());
+    }
+    for recorded in unknown {
+        spawned(world, entities, recorded);
+    }
+    reflect.insert(&mut world.entity_mut(entity), &*value, registry);
+    map_entities.map_entities(world, &mut mapped_entities(entities), &[entity]);
+    Ok(())
+}
+
+fn mapped_entities(entities: &HashMap<u64, Entity>) -> EntityHashMap<Entity> {
+    entities
+        .iter()
+        .filter_map(|(recorded, entity)| Some((Entity::try_from_bits(*recorded).ok()?, *entity)))
+        .collect()
+}
+
+fn spawned(world: &mut World, entities: &mut HashMap<u64, Entity>, id: u64) -> Entity {
+    *entities
+        .entry(id)
+        .or_insert_with(|| world.spawn((Replayed(id), SpatialBundle::default())).id())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        recording::{test::recording_app, test::temp_file},
+        SyncRecordingExt,
+    };
+    use bevy::render::mesh::skinning::SkinnedMesh;
+    use bevy_sync::SyncMark;
+    use lux_components::{AddByUuid, AssetSources};
+    use std::{path::Path, time::Duration};
+
+    #[test]
+    fn test_replay_reproduces_final_transforms() {
+        let path = temp_file("replay_final");
+        let mut recorded = recording_app(&path);
+        let moving = recorded
+            .world_mut()
+            .spawn((SyncMark, Name::new("moving"), Transform::default()))
+            .id();
+        let child = recorded
+            .world_mut()
+            .spawn((
+                SyncMark,
+                Name::new("child"),
+                Transform::from_xyz(0.0, 1.0, 0.0),
+            ))
+            .set_parent(moving)
+            .id();
+        let gone = recorded
+            .world_mut()
+            .spawn((SyncMark, Name::new("gone"), Transform::default()))
+            .id();
+        for frame in 0..30 {
+            let mut transform = recorded.world_mut().get_mut::<Transform>(moving).unwrap();
+            transform.translation.x += 0.123;
+            transform.rotate_y(0.05);
+            if frame == 10 {
+                recorded.world_mut().despawn(gone);
+            }
+            if frame == 20 {
+                recorded
+                    .world_mut()
+                    .get_mut::<Transform>(child)
+                    .unwrap()
+                    .scale = Vec3::splat(2.0);
+            }
+            advance(&mut recorded);
+        }
+
+        let mut replayed = App::new();
+        replayed.init_resource::<Time>();
+        replayed.init_resource::<AppTypeRegistry>();
+        replayed.register_type::<Transform>();
+        replayed.register_type::<Name>();
+        replay(&mut replayed, Recording::read(&path).unwrap(), 2.0);
+        for _ in 0..30 {
+            advance(&mut replayed);
+        }
+
+        assert!(replayed.world().resource::<Replay>().finished());
+        let expected = recorded_transforms(&mut recorded);
+        let actual = recorded_transforms(&mut replayed);
+        assert_eq!(actual.len(), 2);
+        assert_eq!(actual, expected);
+        let child = find(&mut replayed, "child");
+        assert!(replayed.world().get::<Parent>(child).is_some());
+    }
+
+    #[test]
+    fn test_seeking_back_replays_from_start() {
+        let path = temp_file("replay_seek");
+        let mut recorded = recording_app(&path);
+        let entity = recorded
+            .world_mut()
+            .spawn((SyncMark, Name::new("moving"), Transform::default()))
+            .id();
+        for _ in 0..10 {
+            recorded
+                .world_mut()
+                .get_mut::<Transform>(entity)
+                .unwrap()
+                .translation
+                .x += 1.0;
+            advance(&mut recorded);
+        }
+
+        let mut replayed = App::new();
+        replayed.init_resource::<AppTypeRegistry>();
+        replayed.register_type::<Transform>();
+        replayed.register_type::<Name>();
+        replay(&mut replayed, Recording::read(&path).unwrap(), 1.0);
+        replayed.world_mut().resource_mut::<Replay>().time = 1.0;
+        replayed.update();
+        replayed.world_mut().resource_mut::<Replay>().time = 0.35;
+        replayed.update();
+
+        let entity = find(&mut replayed, "moving");
+        let x = replayed
+            .world()
+            .get::<Transform>(entity)
+            .unwrap()
+            .translation
+            .x;
+        assert_eq!(x, 4.0);
+        let mut query = replayed.world_mut().query::<&Replayed>();
+        assert_eq!(query.iter(replayed.world()).count(), 1);
+    }
+
+    #[test]
+    fn test_joints_refer_to_replayed_entities() {
+        let path = temp_file("replay_joints");
+        let mut recorded = recording_app(&path);
+        recorded.register_type::<SkinnedMesh>();
+        recorded.record_sync::<SkinnedMesh>();
+        let joints = [
+            recorded.world_mut().spawn(Transform::default()).id(),
+            recorded.world_mut().spawn(Transform::default()).id(),
+        ];
+        recorded.world_mut().spawn((
+            SyncMark,
+            Name::new("skin"),
+            SkinnedMesh {
+                joints: joints.to_vec(),
+                ..default()
+            },
+        ));
+        advance(&mut recorded);
+        // The joints are only synched after the mesh referring to them.
+        for (i, joint) in joints.iter().enumerate() {
+            recorded
+                .world_mut()
+                .entity_mut(*joint)
+                .insert((SyncMark, Name::new(format!("joint {}", i))));
+        }
+        advance(&mut recorded);
+
+        let mut replayed = replay_app(&path);
+        replayed.register_type::<SkinnedMesh>();
+        for _ in 0..3 {
+            advance(&mut replayed);
+        }
+
+        let skin = find(&mut replayed, "skin");
+        let expected = vec![
+            find(&mut replayed, "joint 0"),
+            find(&mut replayed, "joint 1"),
+        ];
+        let skinned = replayed.world().get::<SkinnedMesh>(skin).unwrap();
+        assert_eq!(skinned.joints, expected);
+    }
+
+    #[test]
+    fn test_meshes_loaded_from_their_file() {
+        const MESH: &str = "world.glb#Mesh0/Primitive0";
+        const IMPORTED: &str = "world.glb#Mesh1/Primitive0";
+        let path = temp_file("replay_meshes");
+        let mut recorded = recording_app(&path);
+        recorded.add_plugins((TaskPoolPlugin::default(), AssetPlugin::default()));
+        recorded.init_asset::<Mesh>();
+        recorded.record_handle::<Mesh>();
+        let loaded: Handle<Mesh> = recorded.world().resource::<AssetServer>().load(MESH);
+        let made = recorded
+            .world_mut()
+            .resource_mut::<Assets<Mesh>>()
+            .add(Cuboid::default());
+        recorded
+            .world_mut()
+            .spawn((SyncMark, Name::new("loaded"), loaded));
+        recorded
+            .world_mut()
+            .spawn((SyncMark, Name::new("made"), made));
+        // Imported meshes are given a uuid, their file is kept aside.
+        let imported = recorded
+            .world_mut()
+            .resource_mut::<Assets<Mesh>>()
+            .addu(Cuboid::default().into());
+        let mut sources = AssetSources::default();
+        sources.insert(imported.id(), &IMPORTED.into());
+        recorded.insert_resource(sources);
+        recorded
+            .world_mut()
+            .spawn((SyncMark, Name::new("imported"), imported));
+        advance(&mut recorded);
+
+        let mut replayed = replay_app(&path);
+        replayed.add_plugins((TaskPoolPlugin::default(), AssetPlugin::default()));
+        replayed.init_asset::<Mesh>();
+        replayed.replay_handle::<Mesh>();
+        for _ in 0..3 {
+            advance(&mut replayed);
+        }
+
+        let loaded = find(&mut replayed, "loaded");
+        let handle = replayed.world().get::<Handle<Mesh>>(loaded).unwrap();
+        assert_eq!(handle.path().map(|p| p.to_string()), Some(MESH.to_owned()));
+        let made = find(&mut replayed, "made");
+        assert!(replayed.world().get::<Handle<Mesh>>(made).is_none());
+        let imported = find(&mut replayed, "imported");
+        let handle = replayed.world().get::<Handle<Mesh>>(imported).unwrap();
+        assert_eq!(
+            handle.path().map(|p| p.to_string()),
+            Some(IMPORTED.to_owned())
+        );
+    }
+
+    fn replay_app(path: &Path) -> App {
+        let mut app = App::new();
+        app.init_resource::<Time>();
+        app.init_resource::<AppTypeRegistry>();
+        app.register_type::<Transform>();
+        app.register_type::<Name>();
+        replay(&mut app, Recording::read(path).unwrap(), 1.0);
+        app
+    }
+
+    fn advance(app: &mut App) {
+        app.world_mut()
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_millis(100));
+        app.update();
+    }
+
+    fn recorded_transforms(app: &mut App) -> Vec<(String, Transform)> {
+        let mut query = app.world_mut().query::<(&Name, &Transform)>();
+        let mut transforms: Vec<_> = query
+            .iter(app.world())
+            .map(|(n, t)| (n.to_string(), *t))
+            .collect();
+        transforms.sort_by(|a, b| a.0.cmp(&b.0));
+        transforms
+    }
+
+    fn find(app: &mut App, name: &str) -> Entity {
+        let mut query = app.world_mut().query::<(Entity, &Name)>();
+        query
+            .iter(app.world())
+            .find(|(_, n)| n.as_str() == name)
+            .unwrap()
+            .0
+    }
+}
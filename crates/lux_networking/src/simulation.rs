@@ -0,0 +1,169 @@
+use std::{collections::VecDeque, net::SocketAddr};
+
+use bevy::prelude::*;
+use lux_cli::Args;
+
+/// Some packets always make it, or the link would never deliver.
+const MAX_LOSS: f32 = 0.9;
+
+/// Conditions of a simulated bad link, applied to the datagrams of the
+/// transform channel before they reach the socket.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct LinkConditions {
+    /// Seconds each message takes to arrive.
+    pub latency: f32,
+    /// Up to this many seconds more or less than the latency.
+    pub jitter: f32,
+    /// Chance from 0 to 1 that a message is lost.
+    pub loss: f32,
+    /// Bytes per second, None for unlimited.
+    pub bandwidth: Option<u32>,
+}
+
+impl LinkConditions {
+    /// The conditions from the `--sim-*` flags, if any is given.
+    pub fn from_args(args: &Args) -> Option<Self> {
+        if args.sim_latency.is_none()
+            && args.sim_jitter.is_none()
+            && args.sim_loss.is_none()
+            && args.sim_bandwidth.is_none()
+        {
+            return None;
+        }
+        Some(Self {
+            latency: args.sim_latency.unwrap_or_default() as f32 / 1000.0,
+            jitter: args.sim_jitter.unwrap_or_default() as f32 / 1000.0,
+            loss: args.sim_loss.unwrap_or_default(),
+            bandwidth: args.sim_bandwidth,
+        })
+    }
+}
+
+/// Delivers messages as datagrams would go over a link with the given
+/// conditions: lost ones never arrive and jitter can reorder them.
+pub struct SimulatedLink<T> {
+    pub conditions: LinkConditions,
+    queue: VecDeque<(f64, T)>,
+    busy_until: f64,
+    seed: u64,
+}
+
+impl<T> SimulatedLink<T> {
+    /// The same seed gives the same jitter and losses.
+    pub fn new(conditions: LinkConditions, seed: u64) -> Self {
+        Self {
+            conditions,
+            queue: VecDeque::new(),
+            busy_until: f64::NEG_INFINITY,
+            seed: seed.max(1),
+        }
+    }
+
+    pub fn send(&mut self, now: f64, message: T, size: usize) {
+        let conditions = self.conditions;
+        let mut sent = now;
+        if let Some(bandwidth) = conditions.bandwidth {
+            sent = self.busy_until.max(now) + size as f64 / bandwidth.max(1) as f64;
+            self.busy_until = sent;
+        }
+        let jitter = conditions.jitter as f64 * (self.random() * 2.0 - 1.0);
+        let arrival = sent + (conditions.latency as f64 + jitter).max(0.0);
+        if (self.random() as f32) < conditions.loss.min(MAX_LOSS) {
+            return;
+        }
+        let at = self.queue.partition_point(|(t, _)| *t <= arrival);
+        self.queue.insert(at, (arrival, message));
+    }
+
+    /// Messages arrived by `now`, in the order they arrived.
+    pub fn receive(&mut self, now: f64) -> Vec<T> {
+        let mut arrived = vec![];
+        while self.queue.front().is_some_and(|(at, _)| *at <= now) {
+            arrived.extend(self.queue.pop_front().map(|(_, m)| m));
+        }
+        arrived
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Xorshift, from 0 to 1.
+    fn random(&mut self) -> f64 {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 7;
+        self.seed ^= self.seed << 17;
+        (self.seed >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// Datagrams of the transform channel on their way to their address.
+#[derive(Resource)]
+pub(crate) struct SimulatedDatagrams(pub SimulatedLink<(SocketAddr, Vec<u8>)>);
+
+/// What the transform channel sends here goes through a link with these
+/// conditions, for hosts and joined clients alike. Other components, spawns
+/// and assets go through bevy_sync and are not affected.
+pub fn simulate_link(app: &mut App, conditions: LinkConditions) {
+    warn!(
+        "Simulating a bad link for sent transforms: {:?}",
+        conditions
+    );
+    app.insert_resource(SimulatedDatagrams(SimulatedLink::new(conditions, 1)));
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_latency_delays_delivery() {
+        let mut link = SimulatedLink::new(conditions(0.1, 0.0, 0.0, None), 7);
+        link.send(0.0, 1, 10);
+        assert!(link.receive(0.09).is_empty());
+        assert_eq!(link.receive(0.11), vec![1]);
+    }
+
+    #[test]
+    fn test_jitter_reorders() {
+        let mut link = SimulatedLink::new(conditions(0.1, 0.05, 0.0, None), 7);
+        for i in 0..100 {
+            link.send(i as f64 * 0.01, i, 10);
+        }
+        let mut received = link.receive(1000.0);
+        assert_ne!(received, (0..100).collect::<Vec<_>>());
+        received.sort();
+        assert_eq!(received, (0..100).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_bandwidth_spreads_messages() {
+        let mut link = SimulatedLink::new(conditions(0.0, 0.0, 0.0, Some(1000)), 7);
+        for i in 0..10 {
+            link.send(0.0, i, 100);
+        }
+        assert_eq!(link.receive(0.5).len(), 5);
+        assert_eq!(link.in_flight(), 5);
+    }
+
+    #[test]
+    fn test_lost_messages_never_arrive() {
+        let mut link = SimulatedLink::new(conditions(0.05, 0.0, 0.5, None), 7);
+        let mut received = 0;
+        for i in 0..100 {
+            link.send(i as f64, i, 10);
+            received += link.receive(i as f64 + 0.06).len();
+        }
+        assert!(received > 20 && received < 80, "{}", received);
+        assert_eq!(link.in_flight(), 0);
+    }
+
+    fn conditions(latency: f32, jitter: f32, loss: f32, bandwidth: Option<u32>) -> LinkConditions {
+        LinkConditions {
+            latency,
+            jitter,
+            loss,
+            bandwidth,
+        }
+    }
+}
@@ -0,0 +1,1038 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    io::{self, ErrorKind},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket},
+};
+
+use bevy::{prelude::*, transform::TransformSystem};
+use bevy_sync::{SyncEntity, Uuid};
+use lux_components::{Authority, ControlledBy, LocalPeer, SmoothTransform, User};
+
+use crate::{
+    interest::update_interest, simulation::SimulatedDatagrams, Interest, NetworkStats, SendRate,
+    SyncRates,
+};
+
+/// Largest datagram sent, below the usual MTU.
+const MAX_DATAGRAM: usize = 1200;
+/// Snapshots kept per client, to encode the next ones against.
+const KEPT_SNAPSHOTS: usize = 64;
+/// Clients tell the host they are there this often, even when idle.
+const HELLO_INTERVAL: f64 = 0.5;
+/// Clients not heard from for this long are forgotten.
+const CLIENT_TIMEOUT: f64 = 5.0;
+/// Translation and scale are sent in steps of a millimeter.
+const LENGTH_STEPS: f32 = 1000.0;
+const ROTATION_STEPS: f32 = i16::MAX as f32;
+
+const HELLO: u8 = 0;
+const SNAPSHOT: u8 = 1;
+const TRANSLATION: u8 = 1;
+const ROTATION: u8 = 2;
+const SCALE: u8 = 4;
+
+/// A transform as it goes on the wire.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Quantised {
+    translation: [i32; 3],
+    rotation: [i16; 4],
+    scale: [i32; 3],
+}
+
+impl Quantised {
+    fn new(transform: &Transform) -> Self {
+        let length = |v: Vec3| v.to_array().map(|x| (x * LENGTH_STEPS).round() as i32);
+        // q and -q are the same rotation, w is kept positive.
+        let rotation = transform.rotation.normalize();
+        let rotation = if rotation.w < 0.0 {
+            -rotation
+        } else {
+            rotation
+        };
+        Self {
+            translation: length(transform.translation),
+            rotation: rotation
+                .to_array()
+                .map(|x| (x * ROTATION_STEPS).round() as i16),
+            scale: length(transform.scale),
+        }
+    }
+
+    fn transform(&self) -> Transform {
+        let length = |v: [i32; 3]| Vec3::from_array(v.map(|x| x as f32 / LENGTH_STEPS));
+        let rotation = Quat::from_array(self.rotation.map(|x| x as f32 / ROTATION_STEPS));
+        Transform {
+            translation: length(self.translation),
+            rotation: rotation.normalize(),
+            scale: length(self.scale),
+        }
+    }
+
+    /// Fields that differ from `other`.
+    fn changed(&self, other: &Self) -> u8 {
+        let mut mask = 0;
+        if self.translation != other.translation {
+            mask |= TRANSLATION;
+        }
+        if self.rotation != other.rotation {
+            mask |= ROTATION;
+        }
+        if self.scale != other.scale {
+            mask |= SCALE;
+        }
+        mask
+    }
+}
+
+type Snapshot = HashMap<Uuid, Quantised>;
+
+/// Transforms of a snapshot, each with only the fields that differ from
+/// the snapshot it is encoded against, and the entities no longer in it.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Delta {
+    seq: u32,
+    /// 0 when encoded against nothing.
+    baseline: u32,
+    changes: Vec<(Uuid, u8, Quantised)>,
+    removed: Vec<Uuid>,
+}
+
+impl Delta {
+    fn entry_size(mask: u8) -> usize {
+        let fields = [(TRANSLATION, 12), (ROTATION, 8), (SCALE, 12)];
+        17 + fields
+            .iter()
+            .filter(|(field, _)| mask & field != 0)
+            .map(|(_, size)| size)
+            .sum::<usize>()
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut bytes = vec![SNAPSHOT];
+        bytes.extend(self.seq.to_le_bytes());
+        bytes.extend(self.baseline.to_le_bytes());
+        bytes.extend((self.changes.len() as u16).to_le_bytes());
+        for (uuid, mask, value) in self.changes.iter() {
+            bytes.extend(uuid.as_bytes());
+            bytes.push(*mask);
+            if mask & TRANSLATION != 0 {
+                value
+                    .translation
+                    .iter()
+                    .for_each(|x| bytes.extend(x.to_le_bytes()));
+            }
+            if mask & ROTATION != 0 {
+                value
+                    .rotation
+                    .iter()
+                    .for_each(|x| bytes.extend(x.to_le_bytes()));
+            }
+            if mask & SCALE != 0 {
+                value
+                    .scale
+                    .iter()
+                    .for_each(|x| bytes.extend(x.to_le_bytes()));
+            }
+        }
+        bytes.extend((self.removed.len() as u16).to_le_bytes());
+        for uuid in self.removed.iter() {
+            bytes.extend(uuid.as_bytes());
+        }
+        bytes
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        let mut reader = Reader(bytes);
+        if reader.take::<1>()? != [SNAPSHOT] {
+            return None;
+        }
+        let seq = u32::from_le_bytes(reader.take()?);
+        let baseline = u32::from_le_bytes(reader.take()?);
+        let mut changes = vec![];
+        for _ in 0..u16::from_le_bytes(reader.take()?) {
+            let uuid = Uuid::from_bytes(reader.take()?);
+            let [mask] = reader.take()?;
+            let mut value = Quantised {
+                translation: [0; 3],
+                rotation: [0; 4],
+                scale: [0; 3],
+            };
+            if mask & TRANSLATION != 0 {
+                for x in value.translation.iter_mut() {
+                    *x = i32::from_le_bytes(reader.take()?);
+                }
+            }
+            if mask & ROTATION != 0 {
+                for x in value.rotation.iter_mut() {
+                    *x = i16::from_le_bytes(reader.take()?);
+                }
+            }
+            if mask & SCALE != 0 {
+                for x in value.scale.iter_mut() {
+                    *x = i32::from_le_bytes(reader.take()?);
+                }
+            }
+            changes.push((uuid, mask, value));
+        }
+        let mut removed = vec![];
+        for _ in 0..u16::from_le_bytes(reader.take()?) {
+            removed.push(Uuid::from_bytes(reader.take()?));
+        }
+        Some(Self {
+            seq,
+            baseline,
+            changes,
+            removed,
+        })
+    }
+
+    /// The snapshot it stands for, None if an entity new to `baseline`
+    /// misses fields.
+    fn apply(&self, baseline: &Snapshot) -> Option<Snapshot> {
+        let mut snapshot = baseline.clone();
+        for (uuid, mask, value) in self.changes.iter() {
+            let full = TRANSLATION | ROTATION | SCALE;
+            let current = match snapshot.get_mut(uuid) {
+                Some(current) => current,
+                None if *mask == full => snapshot.entry(*uuid).or_insert(*value),
+                None => return None,
+            };
+            if mask & TRANSLATION != 0 {
+                current.translation = value.translation;
+            }
+            if mask & ROTATION != 0 {
+                current.rotation = value.rotation;
+            }
+            if mask & SCALE != 0 {
+                current.scale = value.scale;
+            }
+        }
+        for uuid in self.removed.iter() {
+            snapshot.remove(uuid);
+        }
+        Some(snapshot)
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl Reader<'_> {
+    fn take<const N: usize>(&mut self) -> Option<[u8; N]> {
+        let (taken, rest) = self.0.split_at_checked(N)?;
+        self.0 = rest;
+        taken.try_into().ok()
+    }
+}
+
+/// A client telling the host who it is and the last snapshot it got.
+fn hello(peer: u64, ack: u32) -> Vec<u8> {
+    let mut bytes = vec![HELLO];
+    bytes.extend(peer.to_le_bytes());
+    bytes.extend(ack.to_le_bytes());
+    bytes
+}
+
+fn read_hello(bytes: &[u8]) -> Option<(u64, u32)> {
+    let mut reader = Reader(bytes);
+    if reader.take::<1>()? != [HELLO] {
+        return None;
+    }
+    Some((
+        u64::from_le_bytes(reader.take()?),
+        u32::from_le_bytes(reader.take()?),
+    ))
+}
+
+/// Marks the SyncExclude held by the transform channel, see ControlledBy.
+#[derive(Component, Default)]
+pub struct TransformChannel;
+
+/// The host stopped sending the transform of this entity here, it is no
+/// longer near any user of this peer. Hidden until it is sent again.
+#[derive(Component, Default)]
+pub struct StreamedOut {
+    visibility: Visibility,
+}
+
+struct Client {
+    peer: u64,
+    last_heard: f64,
+    last_sent: f64,
+    acked: u32,
+    sent: VecDeque<(u32, Snapshot)>,
+    /// What the client should show, entities update in it at their rate.
+    shown: Snapshot,
+    updated: HashMap<Uuid, f64>,
+}
+
+#[derive(Resource)]
+struct HostChannel {
+    socket: UdpSocket,
+    clients: HashMap<SocketAddr, Client>,
+    seq: u32,
+}
+
+#[derive(Resource)]
+struct JoinedChannel {
+    socket: UdpSocket,
+    host: SocketAddr,
+    received: VecDeque<(u32, Snapshot)>,
+    applied: Snapshot,
+    last_hello: f64,
+}
+
+impl JoinedChannel {
+    fn latest(&self) -> u32 {
+        self.received.back().map(|(seq, _)| *seq).unwrap_or(0)
+    }
+}
+
+/// Where the host listens for the transform channel: the UDP port after the
+/// one of bevy_sync.
+pub fn transforms_address(ip: IpAddr, port: u16) -> SocketAddr {
+    SocketAddr::new(ip, port.wrapping_add(1))
+}
+
+/// Sends the transforms driven here to the joined clients, next to bevy_sync
+/// which leaves them out. Each client only gets the entities of its Interest,
+/// when there is one. Values are quantised on the wire only and only the
+/// fields that changed since what each client acknowledged are sent, at the
+/// rates of SyncRates.
+pub fn host_transforms(app: &mut App, address: SocketAddr) -> io::Result<()> {
+    let socket = UdpSocket::bind(address)?;
+    socket.set_nonblocking(true)?;
+    app.insert_resource(HostChannel {
+        socket,
+        clients: HashMap::new(),
+        seq: 0,
+    });
+    app.add_systems(
+        PostUpdate,
+        (
+            claim_transforms,
+            send_transforms
+                .after(TransformSystem::TransformPropagate)
+                .after(update_interest)
+                .run_if(resource_exists::<Time>),
+        ),
+    );
+    Ok(())
+}
+
+/// Receives the transforms of the host listening at `host`, they are
+/// smoothed like any other received transform. Entities the host stops
+/// sending are StreamedOut.
+pub fn join_transforms(app: &mut App, host: SocketAddr) -> io::Result<()> {
+    let any = match host.ip() {
+        IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+        IpAddr::V6(_) => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+    };
+    let socket = UdpSocket::bind(SocketAddr::new(any, 0))?;
+    socket.set_nonblocking(true)?;
+    app.insert_resource(JoinedChannel {
+        socket,
+        host,
+        received: VecDeque::new(),
+        applied: Snapshot::new(),
+        last_hello: f64::NEG_INFINITY,
+    });
+    app.add_systems(
+        PreUpdate,
+        (receive_transforms, keep_streamed_out_hidden)
+            .chain()
+            .run_if(resource_exists::<Time>),
+    );
+    Ok(())
+}
+
+fn driven_here(authority: Option<&Authority>, peer: Option<&LocalPeer>) -> bool {
+    let owner = authority.and_then(|a| a.owner::<Transform>());
+    owner.is_none() || owner == peer.map(|p| p.id)
+}
+
+/// Transforms driven here go through the channel, the others are left to
+/// bevy_sync, like those of users owned by a client.
+#[allow(clippy::type_complexity)]
+fn claim_transforms(
+    mut commands: Commands,
+    peer: Option<Res<LocalPeer>>,
+    query: Query<
+        (
+            Entity,
+            Option<&Authority>,
+            Has<ControlledBy<Transform, TransformChannel>>,
+        ),
+        (With<SyncEntity>, With<Transform>),
+    >,
+) {
+    for (entity, authority, claimed) in query.iter() {
+        let driven = driven_here(authority, peer.as_deref());
+        if driven && !claimed {
+            commands
+                .entity(entity)
+                .insert(ControlledBy::<Transform, TransformChannel>::default());
+        } else if !driven && claimed {
+            commands
+                .entity(entity)
+                .remove::<ControlledBy<Transform, TransformChannel>>();
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn send_transforms(
+    time: Res<Time>,
+    rates: Res<SyncRates>,
+    peer: Option<Res<LocalPeer>>,
+    interest: Option<Res<Interest>>,
+    mut channel: ResMut<HostChannel>,
+    mut link: Option<ResMut<SimulatedDatagrams>>,
+    mut stats: ResMut<NetworkStats>,
+    users: Query<(&GlobalTransform, Option<&Authority>), With<User>>,
+    entities: Query<(
+        Entity,
+        &SyncEntity,
+        &Transform,
+        Option<&GlobalTransform>,
+        Option<&Authority>,
+    )>,
+) {
+    let now = time.elapsed_seconds_f64();
+    let channel = &mut *channel;
+    deliver_simulated(&channel.socket, link.as_deref_mut(), now);
+    receive_hellos(channel, now);
+    if channel.clients.is_empty() {
+        return;
+    }
+    let hz = match rates.get::<Transform>() {
+        SendRate::Hz(hz) => hz,
+        SendRate::OnChange => f32::INFINITY,
+    };
+    let local = peer.as_deref();
+    let current: Vec<(Entity, Uuid, Quantised, Option<Vec3>)> = entities
+        .iter()
+        .filter(|(.., authority)| driven_here(*authority, local))
+        .map(|(entity, synced, transform, global, _)| {
+            let at = global.map(|g| g.translation());
+            (entity, synced.uuid, Quantised::new(transform), at)
+        })
+        .collect();
+    let mut viewers: HashMap<u64, Vec<Vec3>> = HashMap::new();
+    for (transform, authority) in users.iter() {
+        if let Some(owner) = authority.and_then(|a| a.owner::<Transform>()) {
+            viewers
+                .entry(owner)
+                .or_default()
+                .push(transform.translation());
+        }
+    }
+    for (address, client) in channel.clients.iter_mut() {
+        if now - client.last_sent < 1.0 / hz as f64 {
+            continue;
+        }
+        let seen_from = viewers.get(&client.peer).map(Vec::as_slice).unwrap_or(&[]);
+        let distance = |at: Option<Vec3>| {
+            let at = at?;
+            seen_from.iter().map(|v| v.distance(at)).reduce(f32::min)
+        };
+        let mut kept = Snapshot::new();
+        let mut nearest_first = vec![];
+        let relevant = current.iter().filter(|(entity, ..)| {
+            interest
+                .as_ref()
+                .is_none_or(|i| i.is_relevant(client.peer, *entity))
+        });
+        for (_, uuid, value, at) in relevant {
+            let distance = distance(*at);
+            let due = client
+                .updated
+                .get(uuid)
+                .is_none_or(|updated| now - updated >= rates.interval(hz, distance) as f64);
+            let value = match client.shown.get(uuid) {
+                Some(shown) if !due => *shown,
+                _ => *value,
+            };
+            if client.shown.get(uuid) != Some(&value) {
+                client.updated.insert(*uuid, now);
+            }
+            kept.insert(*uuid, value);
+            nearest_first.push((distance.unwrap_or(f32::INFINITY), *uuid));
+        }
+        client.shown = kept;
+        client
+            .updated
+            .retain(|uuid, _| client.shown.contains_key(uuid));
+        nearest_first.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        channel.seq = channel.seq.wrapping_add(1).max(1);
+        let baseline = client
+            .sent
+            .iter()
+            .find(|(seq, _)| *seq == client.acked)
+            .cloned();
+        let (baseline_seq, mut snapshot) = baseline.unwrap_or_default();
+        let mut delta = Delta {
+            seq: channel.seq,
+            baseline: baseline_seq,
+            ..default()
+        };
+        let mut size = delta.encode().len();
+        for uuid in snapshot.keys() {
+            if !client.shown.contains_key(uuid) && size + 16 <= MAX_DATAGRAM {
+                delta.removed.push(*uuid);
+                size += 16;
+            }
+        }
+        for uuid in delta.removed.iter() {
+            snapshot.remove(uuid);
+        }
+        // Nearer entities first, what does not fit goes in the next one.
+        for (_, uuid) in nearest_first {
+            let value = client.shown[&uuid];
+            let mask = match snapshot.get(&uuid) {
+                Some(sent) => value.changed(sent),
+                None => TRANSLATION | ROTATION | SCALE,
+            };
+            if mask == 0 {
+                continue;
+            }
+            let entry = Delta::entry_size(mask);
+            if size + entry > MAX_DATAGRAM {
+                break;
+            }
+            size += entry;
+            delta.changes.push((uuid, mask, value));
+            snapshot.insert(uuid, value);
+        }
+        if delta.changes.is_empty() && delta.removed.is_empty() && baseline_seq != 0 {
+            continue;
+        }
+        let bytes = delta.encode();
+        let size = bytes.len();
+        let sent = send(&channel.socket, link.as_deref_mut(), now, bytes, *address);
+        if let Err(e) = sent {
+            warn!("Cannot send transforms to {}: {}", address, e);
+            continue;
+        }
+        stats.count_sent("Transform", delta.changes.len(), size);
+        client.last_sent = now;
+        client.sent.push_back((delta.seq, snapshot));
+        if client.sent.len() > KEPT_SNAPSHOTS {
+            client.sent.pop_front();
+        }
+    }
+}
+
+/// Sends right away, or into the simulated link when there is one.
+fn send(
+    socket: &UdpSocket,
+    link: Option<&mut SimulatedDatagrams>,
+    now: f64,
+    bytes: Vec<u8>,
+    address: SocketAddr,
+) -> io::Result<()> {
+    match link {
+        Some(link) => {
+            let size = bytes.len();
+            link.0.send(now, (address, bytes), size);
+            Ok(())
+        }
+        None => socket.send_to(&bytes, address).map(|_| ()),
+    }
+}
+
+/// Datagrams through the simulated link by now go out on the socket.
+fn deliver_simulated(socket: &UdpSocket, link: Option<&mut SimulatedDatagrams>, now: f64) {
+    let Some(link) = link else {
+        return;
+    };
+    for (address, bytes) in link.0.receive(now) {
+        if let Err(e) = socket.send_to(&bytes, address) {
+            debug!("Cannot send simulated datagram to {}: {}", address, e);
+        }
+    }
+}
+
+fn receive_hellos(channel: &mut HostChannel, now: f64) {
+    let mut buffer = [0; MAX_DATAGRAM];
+    loop {
+        let (size, address) = match channel.socket.recv_from(&mut buffer) {
+            Ok(received) => received,
+            Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+            // Errors of earlier sends show up here on some platforms.
+            Err(_) => continue,
+        };
+        let Some((peer, ack)) = read_hello(&buffer[..size]) else {
+            continue;
+        };
+        let client = channel.clients.entry(address).or_insert_with(|| {
+            info!("Sending transforms to {}", address);
+            Client {
+                peer,
+                last_heard: now,
+                last_sent: f64::NEG_INFINITY,
+                acked: 0,
+                sent: VecDeque::new(),
+                shown: Snapshot::new(),
+                updated: HashMap::new(),
+            }
+        });
+        client.peer = peer;
+        client.last_heard = now;
+        // Older acks arrive late, the newest one is kept.
+        if client.sent.iter().any(|(seq, _)| *seq == ack)
+            && (client.acked == 0 || ack.wrapping_sub(client.acked) < u32::MAX / 2)
+        {
+            client.acked = ack;
+        }
+    }
+    channel
+        .clients
+        .retain(|_, client| now - client.last_heard < CLIENT_TIMEOUT);
+}
+
+#[allow(clippy::type_complexity)]
+fn receive_transforms(
+    mut commands: Commands,
+    time: Res<Time>,
+    peer: Option<Res<LocalPeer>>,
+    mut channel: ResMut<JoinedChannel>,
+    mut link: Option<ResMut<SimulatedDatagrams>>,
+    mut entities: Query<(
+        Entity,
+        &SyncEntity,
+        Option<&mut Transform>,
+        Option<&Visibility>,
+        Has<SmoothTransform>,
+        Has<StreamedOut>,
+    )>,
+) {
+    let now = time.elapsed_seconds_f64();
+    let peer = peer.map(|p| p.id).unwrap_or_default();
+    deliver_simulated(&channel.socket, link.as_deref_mut(), now);
+    let mut buffer = [0; MAX_DATAGRAM];
+    let mut received = false;
+    loop {
+        let size = match channel.socket.recv_from(&mut buffer) {
+            Ok((size, from)) if from == channel.host => size,
+            Ok(_) => continue,
+            Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+            Err(_) => continue,
+        };
+        let Some(delta) = Delta::decode(&buffer[..size]) else {
+            continue;
+        };
+        // Late ones are dropped, a newer snapshot is already shown.
+        let latest = channel.latest();
+        if latest != 0 && delta.seq.wrapping_sub(latest).wrapping_sub(1) >= u32::MAX / 2 {
+            continue;
+        }
+        let empty = Snapshot::new();
+        let baseline = match delta.baseline {
+            0 => Some(&empty),
+            seq => channel
+                .received
+                .iter()
+                .find(|(s, _)| *s == seq)
+                .map(|(_, snapshot)| snapshot),
+        };
+        let Some(snapshot) = baseline.and_then(|b| delta.apply(b)) else {
+            continue;
+        };
+        channel.received.push_back((delta.seq, snapshot));
+        if channel.received.len() > KEPT_SNAPSHOTS {
+            channel.received.pop_front();
+        }
+        received = true;
+    }
+    if received || now - channel.last_hello >= HELLO_INTERVAL {
+        let hello = hello(peer, channel.latest());
+        let sent = send(
+            &channel.socket,
+            link.as_deref_mut(),
+            now,
+            hello,
+            channel.host,
+        );
+        if let Err(e) = sent {
+            debug!("Cannot reach the host for transforms: {}", e);
+        }
+        channel.last_hello = now;
+    }
+
+    let Some((_, latest)) = channel.received.back() else {
+        return;
+    };
+    let latest = latest.clone();
+    let channel = &mut *channel;
+    for (entity, synced, transform, visibility, smoothed, streamed_out) in entities.iter_mut() {
+        let Some(value) = latest.get(&synced.uuid) else {
+            if channel.applied.remove(&synced.uuid).is_some() {
+                commands.entity(entity).insert((
+                    StreamedOut {
+                        visibility: visibility.copied().unwrap_or_default(),
+                    },
+                    ControlledBy::<Visibility, StreamedOut>::default(),
+                    Visibility::Hidden,
+                ));
+            }
+            continue;
+        };
+        if streamed_out {
+            commands.entity(entity).add(stream_in);
+        }
+        if channel.applied.get(&synced.uuid) == Some(value) {
+            continue;
+        }
+        if !smoothed {
+            commands.entity(entity).insert(SmoothTransform);
+        }
+        match transform {
+            Some(mut transform) => *transform = value.transform(),
+            None => {
+                commands.entity(entity).insert(value.transform());
+            }
+        }
+        channel.applied.insert(synced.uuid, *value);
+    }
+    // Entities not spawned yet are applied once they are.
+    channel.applied.retain(|uuid, _| latest.contains_key(uuid));
+}
+
+fn stream_in(mut entity: EntityWorldMut) {
+    if let Some(out) = entity.take::<StreamedOut>() {
+        entity.insert(out.visibility);
+        entity.remove::<ControlledBy<Visibility, StreamedOut>>();
+    }
+}
+
+/// Visibility received through bevy_sync while streamed out is shown once
+/// the entity is streamed back in.
+fn keep_streamed_out_hidden(
+    mut query: Query<(&mut Visibility, &mut StreamedOut), Changed<Visibility>>,
+) {
+    for (mut visibility, mut out) in query.iter_mut() {
+        if *visibility != Visibility::Hidden {
+            out.visibility = std::mem::replace(&mut *visibility, Visibility::Hidden);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{bandwidth, interest, simulate_link, LinkConditions};
+    use bevy_sync::SyncExclude;
+    use std::time::Duration;
+
+    const FAR: f32 = 1000.0;
+
+    #[test]
+    fn test_quantised_roundtrip() {
+        let transform = Transform {
+            translation: Vec3::new(1.234_56, -200.0, 0.000_4),
+            rotation: Quat::from_euler(EulerRot::XYZ, 0.3, -1.2, 2.0),
+            scale: Vec3::splat(1.5),
+        };
+        let sent = Quantised::new(&transform).transform();
+        assert!(sent
+            .translation
+            .abs_diff_eq(transform.translation, 1.0 / LENGTH_STEPS));
+        assert!(sent.rotation.angle_between(transform.rotation) < 1e-3);
+        assert_eq!(sent.scale, transform.scale);
+        let flipped = Transform::from_rotation(-transform.rotation);
+        assert_eq!(
+            Quantised::new(&flipped).rotation,
+            Quantised::new(&transform).rotation
+        );
+    }
+
+    #[test]
+    fn test_delta_roundtrip() {
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        let start = Quantised::new(&Transform::from_xyz(1.0, 2.0, 3.0));
+        let moved = Quantised::new(&Transform::from_xyz(1.5, 2.0, 3.0));
+        let baseline = Snapshot::from([(a, start), (b, start)]);
+        let delta = Delta {
+            seq: 7,
+            baseline: 6,
+            changes: vec![(a, moved.changed(&start), moved)],
+            removed: vec![b],
+        };
+        let decoded = Delta::decode(&delta.encode()).unwrap();
+        assert_eq!(decoded.seq, 7);
+        assert_eq!(decoded.baseline, 6);
+        assert_eq!(decoded.apply(&baseline), Some(Snapshot::from([(a, moved)])));
+        assert_eq!(decoded.apply(&Snapshot::new()), None, "needs its baseline");
+    }
+
+    #[test]
+    fn test_unchanged_fields_are_not_sent() {
+        let start = Quantised::new(&Transform::from_xyz(1.0, 2.0, 3.0));
+        let moved = Quantised::new(&Transform::from_xyz(1.5, 2.0, 3.0));
+        assert_eq!(moved.changed(&start), TRANSLATION);
+        let full = Delta::entry_size(TRANSLATION | ROTATION | SCALE);
+        assert_eq!(Delta::entry_size(TRANSLATION), full - 20);
+    }
+
+    #[test]
+    fn test_client_gets_quantised_host_keeps_exact() {
+        let (mut host, address) = host_app();
+        let uuid = Uuid::new_v4();
+        let sent = host
+            .world_mut()
+            .spawn((SyncEntity { uuid }, Transform::default()))
+            .id();
+        let mut client = client_app(address, 2);
+        let shown = client
+            .world_mut()
+            .spawn((SyncEntity { uuid }, Transform::default()))
+            .id();
+        let exact = Vec3::new(0.123_456, 5.0, -0.000_7);
+        host.world_mut()
+            .get_mut::<Transform>(sent)
+            .unwrap()
+            .translation = exact;
+        run(&mut [&mut host, &mut client], 60);
+
+        let host_entity = host.world().entity(sent);
+        assert_eq!(host_entity.get::<Transform>().unwrap().translation, exact);
+        assert!(host_entity.contains::<SyncExclude<Transform>>());
+        let received = client.world().get::<Transform>(shown).unwrap().translation;
+        assert_eq!(
+            received,
+            Quantised::new(&Transform::from_translation(exact))
+                .transform()
+                .translation
+        );
+        assert_ne!(received, exact);
+    }
+
+    #[test]
+    fn test_only_changes_are_sent() {
+        let (mut host, address) = host_app();
+        for _ in 0..10 {
+            let uuid = Uuid::new_v4();
+            host.world_mut()
+                .spawn((SyncEntity { uuid }, Transform::default()));
+        }
+        let mut client = client_app(address, 2);
+        // Stats are published once per second.
+        run(&mut [&mut host, &mut client], 70);
+        let first = sent_per_second(&host);
+        run(&mut [&mut host, &mut client], 70);
+        let idle = sent_per_second(&host);
+        assert!(first > 10.0 * 40.0, "{}", first);
+        assert_eq!(idle, 0.0, "nothing changed");
+    }
+
+    #[test]
+    fn test_each_client_gets_what_is_near() {
+        let (mut host, address) = host_app();
+        interest::init(&mut host, None);
+        let first_user = user(&mut host, 2, Vec3::ZERO);
+        user(&mut host, 3, Vec3::X * FAR);
+        let near_first = Uuid::new_v4();
+        let near_second = Uuid::new_v4();
+        for (uuid, at) in [(near_first, Vec3::X), (near_second, Vec3::X * (FAR - 1.0))] {
+            host.world_mut().spawn((
+                SyncEntity { uuid },
+                Transform::from_translation(at),
+                GlobalTransform::from_translation(at),
+            ));
+        }
+        // Spawns come through bevy_sync, spawned here by hand.
+        let mut first = client_app(address, 2);
+        let mut second = client_app(address, 3);
+        for client in [&mut first, &mut second] {
+            for uuid in [near_first, near_second] {
+                client.world_mut().spawn((
+                    SyncEntity { uuid },
+                    Transform::default(),
+                    Visibility::Visible,
+                ));
+            }
+        }
+        run(&mut [&mut host, &mut first, &mut second], 60);
+
+        assert_eq!(shown(&mut first, near_first), Some(Vec3::X));
+        assert_eq!(shown(&mut first, near_second), None, "never sent");
+        assert_eq!(shown(&mut second, near_first), None, "never sent");
+        assert_eq!(shown(&mut second, near_second), Some(Vec3::X * (FAR - 1.0)));
+
+        // The first user walks away, what it left is streamed out.
+        *host
+            .world_mut()
+            .get_mut::<GlobalTransform>(first_user)
+            .unwrap() = GlobalTransform::from_translation(Vec3::Y * FAR);
+        run(&mut [&mut host, &mut first, &mut second], 30);
+        assert_eq!(shown(&mut first, near_first), None, "streamed out");
+        assert_eq!(shown(&mut second, near_second), Some(Vec3::X * (FAR - 1.0)));
+
+        *host
+            .world_mut()
+            .get_mut::<GlobalTransform>(first_user)
+            .unwrap() = GlobalTransform::IDENTITY;
+        run(&mut [&mut host, &mut first, &mut second], 30);
+        assert_eq!(shown(&mut first, near_first), Some(Vec3::X), "back in");
+    }
+
+    #[test]
+    fn test_converges_over_a_good_link() {
+        assert_converges(None);
+    }
+
+    #[test]
+    fn test_converges_over_a_bad_link() {
+        assert_converges(Some(LinkConditions {
+            latency: 0.15,
+            jitter: 0.05,
+            loss: 0.1,
+            bandwidth: Some(2000),
+        }));
+    }
+
+    /// A host moving an entity for two seconds and a client showing it over
+    /// loopback, both sending through the link: the client ends where the
+    /// host stopped and never lags far behind.
+    fn assert_converges(conditions: Option<LinkConditions>) {
+        let (mut host, address) = host_app();
+        let uuid = Uuid::new_v4();
+        let sent = host
+            .world_mut()
+            .spawn((SyncEntity { uuid }, Transform::default()))
+            .id();
+        let mut client = client_app(address, 2);
+        let shown = client
+            .world_mut()
+            .spawn((SyncEntity { uuid }, Transform::default()))
+            .id();
+        if let Some(conditions) = conditions {
+            simulate_link(&mut host, conditions);
+            simulate_link(&mut client, conditions);
+        }
+
+        let step = 1.0 / 60.0;
+        let mut max_lag: f32 = 0.0;
+        for frame in 0..240 {
+            if frame < 120 {
+                host.world_mut()
+                    .get_mut::<Transform>(sent)
+                    .unwrap()
+                    .translation
+                    .x += step;
+            }
+            run(&mut [&mut host, &mut client], 1);
+            let x = |app: &App, e| app.world().get::<Transform>(e).unwrap().translation.x;
+            max_lag = max_lag.max(x(&host, sent) - x(&client, shown));
+        }
+
+        let target = host.world().get::<Transform>(sent).unwrap().translation;
+        let actual = client.world().get::<Transform>(shown).unwrap().translation;
+        assert!(
+            target.distance(actual) < 1e-3,
+            "expected {} got {}",
+            target,
+            actual
+        );
+        assert!(max_lag < 1.0, "lagging {} behind", max_lag);
+    }
+
+    #[test]
+    fn test_client_owned_left_to_sync() {
+        let (mut host, _) = host_app();
+        let mut authority = Authority::default();
+        authority.claim::<Transform>(2);
+        let owned = host
+            .world_mut()
+            .spawn((
+                SyncEntity {
+                    uuid: Uuid::new_v4(),
+                },
+                Transform::default(),
+                authority,
+            ))
+            .id();
+        host.update();
+        host.update();
+        assert!(host
+            .world()
+            .get::<ControlledBy<Transform, TransformChannel>>(owned)
+            .is_none());
+    }
+
+    fn host_app() -> (App, SocketAddr) {
+        let mut app = App::new();
+        app.init_resource::<Time>();
+        app.init_resource::<AppTypeRegistry>();
+        app.insert_resource(LocalPeer { id: 1 });
+        bandwidth::init(&mut app);
+        let mut address = SocketAddr::from((Ipv4Addr::LOCALHOST, 0));
+        host_transforms(&mut app, address).unwrap();
+        address = app
+            .world()
+            .resource::<HostChannel>()
+            .socket
+            .local_addr()
+            .unwrap();
+        (app, address)
+    }
+
+    fn client_app(host: SocketAddr, peer: u64) -> App {
+        let mut app = App::new();
+        app.init_resource::<Time>();
+        lux_components::init(&mut app);
+        app.insert_resource(LocalPeer { id: peer });
+        join_transforms(&mut app, host).unwrap();
+        app
+    }
+
+    /// All apps ticking at 60 Hz, with real time for the sockets.
+    fn run(apps: &mut [&mut App], frames: usize) {
+        for _ in 0..frames {
+            for app in apps.iter_mut() {
+                app.world_mut()
+                    .resource_mut::<Time>()
+                    .advance_by(Duration::from_secs_f32(1.0 / 60.0));
+                app.update();
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    fn user(app: &mut App, peer: u64, at: Vec3) -> Entity {
+        let mut authority = Authority::default();
+        authority.claim::<Transform>(peer);
+        app.world_mut()
+            .spawn((
+                SyncEntity {
+                    uuid: Uuid::new_v4(),
+                },
+                User,
+                authority,
+                Transform::from_translation(at),
+                GlobalTransform::from_translation(at),
+            ))
+            .id()
+    }
+
+    /// Where a client shows the entity, None while it is not shown.
+    fn shown(client: &mut App, uuid: Uuid) -> Option<Vec3> {
+        let mut query =
+            client
+                .world_mut()
+                .query::<(&SyncEntity, &Transform, &Visibility, Has<SmoothTransform>)>();
+        let (_, transform, visibility, received) = query
+            .iter(client.world())
+            .find(|(synced, ..)| synced.uuid == uuid)?;
+        (received && *visibility != Visibility::Hidden).then_some(transform.translation)
+    }
+
+    fn sent_per_second(host: &App) -> f32 {
+        let stats = host.world().resource::<NetworkStats>();
+        stats
+            .components
+            .get("Transform")
+            .map_or(0.0, |c| c.bytes_per_second)
+    }
+}
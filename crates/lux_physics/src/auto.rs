@@ -0,0 +1,111 @@
+use bevy::prelude::*;
+
+use crate::{BodyKind, ColliderShape, PhysicsBody};
+
+/// Mesh entities with this get a static collider generated from their
+/// geometry, unless they or an ancestor already define a PhysicsBody
+/// or opted out. The importer puts it on world scenes.
+#[derive(Component, Default, Debug, Clone, Copy)]
+pub struct AutoCollider;
+
+/// Opt-out from the generated colliders, for this entity and its children.
+#[derive(Component, Default, Debug, Clone, Copy)]
+pub struct NoAutoCollider;
+
+pub(crate) fn init(app: &mut App) {
+    app.add_systems(Update, auto_colliders);
+}
+
+#[allow(clippy::type_complexity)]
+fn auto_colliders(
+    mut commands: Commands,
+    candidates: Query<
+        (Entity, Option<&Parent>),
+        (
+            With<AutoCollider>,
+            With<Handle<Mesh>>,
+            Without<PhysicsBody>,
+            Without<NoAutoCollider>,
+        ),
+    >,
+    ancestors: Query<(Option<&Parent>, Has<PhysicsBody>, Has<NoAutoCollider>)>,
+) {
+    for (entity, parent) in candidates.iter() {
+        let mut skip = false;
+        let mut next = parent.map(|p| p.get());
+        while let Some(ancestor) = next {
+            let Ok((parent, has_body, opted_out)) = ancestors.get(ancestor) else {
+                break;
+            };
+            if has_body || opted_out {
+                skip = true;
+                break;
+            }
+            next = parent.map(|p| p.get());
+        }
+        let mut e = commands.entity(entity);
+        e.remove::<AutoCollider>();
+        if skip {
+            continue;
+        }
+        debug!("Generating static collider for {:?}", entity);
+        e.insert(PhysicsBody {
+            kind: BodyKind::Static,
+            shape: ColliderShape::TriMesh,
+            mass: 0.0,
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_world_mesh_gets_static_body() {
+        let mut app = setup();
+        let entity = app
+            .world_mut()
+            .spawn((AutoCollider, Handle::<Mesh>::default()))
+            .id();
+        app.update();
+
+        let body = app.world().entity(entity).get::<PhysicsBody>().unwrap();
+        assert_eq!(body.kind, BodyKind::Static);
+        assert!(app.world().entity(entity).get::<AutoCollider>().is_none());
+    }
+
+    #[test]
+    fn test_opt_out_applies_to_children() {
+        let mut app = setup();
+        let parent = app.world_mut().spawn(NoAutoCollider).id();
+        let child = app
+            .world_mut()
+            .spawn((AutoCollider, Handle::<Mesh>::default()))
+            .set_parent(parent)
+            .id();
+        app.update();
+
+        assert!(app.world().entity(child).get::<PhysicsBody>().is_none());
+    }
+
+    #[test]
+    fn test_explicit_body_on_ancestor_wins() {
+        let mut app = setup();
+        let parent = app.world_mut().spawn(PhysicsBody::default()).id();
+        let child = app
+            .world_mut()
+            .spawn((AutoCollider, Handle::<Mesh>::default()))
+            .set_parent(parent)
+            .id();
+        app.update();
+
+        assert!(app.world().entity(child).get::<PhysicsBody>().is_none());
+    }
+
+    fn setup() -> App {
+        let mut app = App::new();
+        init(&mut app);
+        app
+    }
+}
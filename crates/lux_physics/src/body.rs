@@ -0,0 +1,220 @@
+use avian3d::prelude::{Collider, Mass, RigidBody};
+use bevy::{prelude::*, render::primitives::Aabb};
+use bevy_sync::SyncComponent;
+use lux_components::SmoothTransform;
+
+use crate::PhysicsRole;
+
+/// Physics description of a world object, synched to every peer.
+/// Each peer builds its own rigid body and colliders from this, so the
+/// avian components themselves never go through the network.
+#[derive(Component, Reflect, Default, Debug, Clone, PartialEq)]
+#[reflect(Component)]
+pub struct PhysicsBody {
+    pub kind: BodyKind,
+    pub shape: ColliderShape,
+    /// Mass in kg, zero to compute it from the collider volume.
+    pub mass: f32,
+}
+
+#[derive(Reflect, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BodyKind {
+    #[default]
+    Static,
+    Dynamic,
+    Kinematic,
+}
+
+#[derive(Reflect, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColliderShape {
+    /// Trimesh for static bodies, convex hull for moving ones.
+    #[default]
+    Auto,
+    TriMesh,
+    ConvexHull,
+    Box,
+}
+
+pub(crate) fn init(app: &mut App) {
+    app.register_type::<BodyKind>();
+    app.register_type::<ColliderShape>();
+    app.sync_component::<PhysicsBody>();
+    app.add_systems(Update, build_bodies);
+}
+
+/// Only the authority simulates, replicas move dynamic bodies
+/// kinematically along with the Transform they receive.
+pub(crate) fn rigid_body(kind: BodyKind, role: PhysicsRole) -> RigidBody {
+    match (kind, role) {
+        (BodyKind::Static, _) => RigidBody::Static,
+        (BodyKind::Kinematic, _) => RigidBody::Kinematic,
+        (BodyKind::Dynamic, PhysicsRole::Authority) => RigidBody::Dynamic,
+        (BodyKind::Dynamic, PhysicsRole::Replica) => RigidBody::Kinematic,
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn build_bodies(
+    mut commands: Commands,
+    role: Res<PhysicsRole>,
+    meshes: Res<Assets<Mesh>>,
+    bodies: Query<
+        (Entity, &PhysicsBody, Option<&Children>),
+        Or<(Changed<PhysicsBody>, Without<RigidBody>)>,
+    >,
+    sources: Query<(Option<&Handle<Mesh>>, Option<&Aabb>)>,
+) {
+    for (entity, body, children) in bodies.iter() {
+        // Colliders go on the meshes: the entity itself, or the primitives
+        // that glTF nodes spawn as children.
+        let mut targets = vec![entity];
+        if !matches!(sources.get(entity), Ok((Some(_), _))) {
+            targets = children
+                .map(|c| {
+                    c.iter()
+                        .copied()
+                        .filter(|c| matches!(sources.get(*c), Ok((Some(_), _))))
+                        .collect()
+                })
+                .unwrap_or_default();
+        }
+        if targets.is_empty() {
+            targets.push(entity);
+        }
+        let colliders: Option<Vec<(Entity, Collider)>> = targets
+            .into_iter()
+            .map(|target| {
+                let (mesh, aabb) = sources.get(target).ok()?;
+                let mesh = mesh.and_then(|h| meshes.get(h));
+                collider(body, mesh, aabb).map(|c| (target, c))
+            })
+            .collect();
+        // Not loaded yet, retry next frame as the body is still missing.
+        let Some(colliders) = colliders else {
+            continue;
+        };
+        for (target, collider) in colliders {
+            commands.entity(target).insert(collider);
+        }
+        let mut e = commands.entity(entity);
+        e.insert(rigid_body(body.kind, *role));
+        if body.mass > 0.0 {
+            e.insert(Mass(body.mass));
+        } else {
+            e.remove::<Mass>();
+        }
+        // Replicas show the host simulation smoothed, and never send it back.
+        if body.kind == BodyKind::Dynamic && *role == PhysicsRole::Replica {
+            e.insert(SmoothTransform);
+        }
+    }
+}
+
+fn collider(body: &PhysicsBody, mesh: Option<&Mesh>, aabb: Option<&Aabb>) -> Option<Collider> {
+    let shape = match (body.shape, body.kind) {
+        (ColliderShape::Auto, BodyKind::Static) => ColliderShape::TriMesh,
+        (ColliderShape::Auto, _) => ColliderShape::ConvexHull,
+        (shape, _) => shape,
+    };
+    match shape {
+        ColliderShape::TriMesh => Collider::trimesh_from_mesh(mesh?),
+        ColliderShape::ConvexHull => Collider::convex_hull_from_mesh(mesh?),
+        _ => {
+            let aabb = aabb.copied().or_else(|| mesh?.compute_aabb())?;
+            let size = Vec3::from(aabb.half_extents) * 2.0;
+            Some(Collider::compound(vec![(
+                Vec3::from(aabb.center),
+                Quat::IDENTITY,
+                Collider::cuboid(size.x, size.y, size.z),
+            )]))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_authority_simulates_dynamic() {
+        let body = rigid_body(BodyKind::Dynamic, PhysicsRole::Authority);
+        assert_eq!(body, RigidBody::Dynamic);
+    }
+
+    #[test]
+    fn test_replica_follows_dynamic_kinematically() {
+        let body = rigid_body(BodyKind::Dynamic, PhysicsRole::Replica);
+        assert_eq!(body, RigidBody::Kinematic);
+        let body = rigid_body(BodyKind::Static, PhysicsRole::Replica);
+        assert_eq!(body, RigidBody::Static);
+    }
+
+    #[test]
+    fn test_colliders_on_child_meshes() {
+        let mut app = setup(PhysicsRole::Replica);
+        let mesh = app
+            .world_mut()
+            .resource_mut::<Assets<Mesh>>()
+            .add(Cuboid::new(1.0, 1.0, 1.0));
+        let body = PhysicsBody {
+            kind: BodyKind::Dynamic,
+            mass: 2.0,
+            ..default()
+        };
+        let parent = app.world_mut().spawn(body).id();
+        let child = app.world_mut().spawn(mesh).set_parent(parent).id();
+        app.update();
+
+        let parent = app.world().entity(parent);
+        assert_eq!(parent.get::<RigidBody>(), Some(&RigidBody::Kinematic));
+        assert!(parent.get::<SmoothTransform>().is_some());
+        assert!(parent.get::<Mass>().is_some());
+        assert!(parent.get::<Collider>().is_none());
+        assert!(app.world().entity(child).get::<Collider>().is_some());
+    }
+
+    #[test]
+    fn test_waits_for_mesh_to_load() {
+        let mut app = setup(PhysicsRole::Authority);
+        let mesh = Handle::<Mesh>::default();
+        let entity = app
+            .world_mut()
+            .spawn((PhysicsBody::default(), mesh.clone()))
+            .id();
+        app.update();
+        assert!(app.world().entity(entity).get::<RigidBody>().is_none());
+
+        app.world_mut()
+            .resource_mut::<Assets<Mesh>>()
+            .insert(&mesh, Cuboid::new(1.0, 1.0, 1.0).into());
+        app.update();
+        assert_eq!(
+            app.world().entity(entity).get::<RigidBody>(),
+            Some(&RigidBody::Static)
+        );
+    }
+
+    #[test]
+    fn test_box_from_bounds_without_mesh() {
+        let mut app = setup(PhysicsRole::Authority);
+        let body = PhysicsBody {
+            shape: ColliderShape::Box,
+            ..default()
+        };
+        let entity = app
+            .world_mut()
+            .spawn((body, Aabb::from_min_max(-Vec3::ONE, Vec3::ONE)))
+            .id();
+        app.update();
+
+        assert!(app.world().entity(entity).get::<Collider>().is_some());
+    }
+
+    fn setup(role: PhysicsRole) -> App {
+        let mut app = App::new();
+        app.insert_resource(role);
+        app.init_resource::<Assets<Mesh>>();
+        init(&mut app);
+        app
+    }
+}
@@ -0,0 +1,159 @@
+use bevy::{gltf::GltfExtras, prelude::*};
+use serde_json::Value;
+
+use crate::{auto::NoAutoCollider, BodyKind, ColliderShape, PhysicsBody};
+
+/// Suffix of node names that are collision only geometry.
+const COLLISION_SUFFIX: &str = "_col";
+
+pub(crate) fn init(app: &mut App) {
+    app.add_systems(Update, bodies_from_gltf);
+}
+
+#[allow(clippy::type_complexity)]
+fn bodies_from_gltf(
+    mut commands: Commands,
+    nodes: Query<
+        (Entity, Option<&GltfExtras>, Option<&Name>),
+        (Without<PhysicsBody>, Or<(Added<GltfExtras>, Added<Name>)>),
+    >,
+) {
+    for (entity, extras, name) in nodes.iter() {
+        if extras.is_some_and(|e| collider_opt_out(&e.value)) {
+            debug!("Collider opt-out from extras on {:?}", entity);
+            commands.entity(entity).insert(NoAutoCollider);
+        } else if let Some(body) = extras.and_then(|e| body_from_extras(&e.value)) {
+            debug!("Physics body from extras on {:?}: {:?}", entity, body);
+            commands.entity(entity).insert(body);
+        } else if let Some(body) = name.and_then(|n| body_from_name(n.as_str())) {
+            debug!("Collision geometry from name on {:?}", entity);
+            commands.entity(entity).insert((body, Visibility::Hidden));
+        }
+    }
+}
+
+/// Reads the body from the glTF extras (custom properties in blender):
+/// `lux_body` static/dynamic/kinematic, `lux_collider` auto/trimesh/convex/box
+/// and `lux_mass` in kg. Either of the first two makes the node physical.
+pub fn body_from_extras(extras: &str) -> Option<PhysicsBody> {
+    let value: Value = serde_json::from_str(extras).ok()?;
+    let kind = value.get("lux_body").and_then(Value::as_str);
+    let shape = value.get("lux_collider").and_then(Value::as_str);
+    if kind.is_none() && shape.is_none() || collider_opt_out(extras) {
+        return None;
+    }
+    let kind = match kind.map(str::to_lowercase).as_deref() {
+        None | Some("static") => BodyKind::Static,
+        Some("dynamic") => BodyKind::Dynamic,
+        Some("kinematic") => BodyKind::Kinematic,
+        Some(other) => {
+            warn!("Unknown lux_body '{}', using static", other);
+            BodyKind::Static
+        }
+    };
+    let shape = match shape.map(str::to_lowercase).as_deref() {
+        None | Some("auto") => ColliderShape::Auto,
+        Some("trimesh") => ColliderShape::TriMesh,
+        Some("convex") => ColliderShape::ConvexHull,
+        Some("box") => ColliderShape::Box,
+        Some(other) => {
+            warn!("Unknown lux_collider '{}', using auto", other);
+            ColliderShape::Auto
+        }
+    };
+    let mass = value
+        .get("lux_mass")
+        .and_then(Value::as_f64)
+        .unwrap_or_default() as f32;
+    Some(PhysicsBody { kind, shape, mass })
+}
+
+/// `lux_collider: none` keeps the node and its children without colliders,
+/// for world geometry that should not be walked on or bumped into.
+pub fn collider_opt_out(extras: &str) -> bool {
+    serde_json::from_str::<Value>(extras)
+        .ok()
+        .and_then(|v| v.get("lux_collider")?.as_str().map(str::to_lowercase))
+        .is_some_and(|s| s == "none")
+}
+
+/// Nodes named like `Wall_col` are static, invisible collision geometry.
+pub fn body_from_name(name: &str) -> Option<PhysicsBody> {
+    let name = name.to_lowercase();
+    let stem = name.rsplit_once('.').map(|(s, _)| s).unwrap_or(&name);
+    if !stem.ends_with(COLLISION_SUFFIX) {
+        return None;
+    }
+    Some(PhysicsBody {
+        kind: BodyKind::Static,
+        shape: ColliderShape::TriMesh,
+        mass: 0.0,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_dynamic_body_from_extras() {
+        let body =
+            body_from_extras(r#"{"lux_body": "dynamic", "lux_collider": "box", "lux_mass": 2.5}"#);
+        assert_eq!(
+            body,
+            Some(PhysicsBody {
+                kind: BodyKind::Dynamic,
+                shape: ColliderShape::Box,
+                mass: 2.5,
+            })
+        );
+    }
+
+    #[test]
+    fn test_collider_only_extras_is_static() {
+        let body = body_from_extras(r#"{"lux_collider": "convex"}"#).unwrap();
+        assert_eq!(body.kind, BodyKind::Static);
+        assert_eq!(body.shape, ColliderShape::ConvexHull);
+    }
+
+    #[test]
+    fn test_unrelated_extras_are_ignored() {
+        assert_eq!(body_from_extras(r#"{"other": 1}"#), None);
+        assert_eq!(body_from_extras("not json"), None);
+    }
+
+    #[test]
+    fn test_opt_out_from_extras() {
+        assert!(collider_opt_out(r#"{"lux_collider": "None"}"#));
+        assert!(!collider_opt_out(r#"{"lux_collider": "box"}"#));
+        assert_eq!(body_from_extras(r#"{"lux_collider": "none"}"#), None);
+    }
+
+    #[test]
+    fn test_collision_name_convention() {
+        assert!(body_from_name("Wall_col").is_some());
+        assert!(body_from_name("wall_COL.001").is_some());
+        assert!(body_from_name("Wall").is_none());
+        assert!(body_from_name("collider").is_none());
+    }
+
+    #[test]
+    fn test_extras_win_over_name() {
+        let mut app = App::new();
+        init(&mut app);
+        let entity = app
+            .world_mut()
+            .spawn((
+                Name::new("Crate_col"),
+                GltfExtras {
+                    value: r#"{"lux_body": "dynamic"}"#.into(),
+                },
+            ))
+            .id();
+        app.update();
+
+        let body = app.world().entity(entity).get::<PhysicsBody>().unwrap();
+        assert_eq!(body.kind, BodyKind::Dynamic);
+        assert!(app.world().entity(entity).get::<Visibility>().is_none());
+    }
+}
@@ -0,0 +1,167 @@
+use avian3d::prelude::{Collider, RigidBody};
+use bevy::{prelude::*, render::primitives::Aabb};
+use lux_components::SpawnPoint;
+
+use crate::PhysicsBody;
+
+const GROUND_SIZE: f32 = 100.0;
+const GROUND_THICKNESS: f32 = 0.5;
+
+/// Invisible local ground, so avatars have something to stand on
+/// until the world colliders are there under the spawn point.
+#[derive(Component)]
+struct FallbackGround;
+
+pub(crate) fn init(app: &mut App) {
+    app.add_systems(Startup, setup_ground);
+    app.add_systems(Update, remove_ground_when_world_loads);
+}
+
+fn setup_ground(mut commands: Commands) {
+    commands.spawn((
+        SpatialBundle {
+            transform: Transform {
+                translation: Vec3::new(0.0, -GROUND_THICKNESS, 0.0),
+                ..default()
+            },
+            ..default()
+        },
+        RigidBody::Static,
+        Collider::cuboid(GROUND_SIZE, GROUND_THICKNESS, GROUND_SIZE),
+        FallbackGround,
+        Name::new("FallbackGround"),
+    ));
+}
+
+/// Other static bodies may load first, the ground stays until one lies under
+/// a spawn point, or under the origin for worlds without any.
+fn remove_ground_when_world_loads(
+    mut commands: Commands,
+    ground: Query<Entity, With<FallbackGround>>,
+    world: Query<(Entity, &RigidBody, Option<&Children>), With<PhysicsBody>>,
+    bounds: Query<(&Aabb, &GlobalTransform)>,
+    points: Query<&GlobalTransform, With<SpawnPoint>>,
+) {
+    if ground.is_empty() {
+        return;
+    }
+    let mut points: Vec<Vec3> = points.iter().map(|p| p.translation()).collect();
+    if points.is_empty() {
+        points.push(Vec3::ZERO);
+    }
+    let under_spawn = world
+        .iter()
+        .filter(|(_, body, _)| **body == RigidBody::Static)
+        .flat_map(|(entity, _, children)| {
+            std::iter::once(entity).chain(children.into_iter().flatten().copied())
+        })
+        .filter_map(|e| bounds.get(e).ok())
+        .any(|(aabb, gt)| points.iter().any(|p| is_under(aabb, gt, *p)));
+    if !under_spawn {
+        return;
+    }
+    for e in ground.iter() {
+        debug!("World colliders loaded under the spawn, removing fallback ground");
+        commands.entity(e).despawn_recursive();
+    }
+}
+
+/// Whether the bounds, in world space, are below `point` and around it.
+fn is_under(aabb: &Aabb, gt: &GlobalTransform, point: Vec3) -> bool {
+    let (center, half) = (Vec3::from(aabb.center), Vec3::from(aabb.half_extents));
+    let mut min = Vec3::INFINITY;
+    let mut max = Vec3::NEG_INFINITY;
+    for i in 0..8 {
+        let corner = Vec3::new(
+            if i & 1 == 0 { -1.0 } else { 1.0 },
+            if i & 2 == 0 { -1.0 } else { 1.0 },
+            if i & 4 == 0 { -1.0 } else { 1.0 },
+        );
+        let world = gt.transform_point(center + half * corner);
+        min = min.min(world);
+        max = max.max(world);
+    }
+    point.x >= min.x && point.x <= max.x && point.z >= min.z && point.z <= max.z && min.y <= point.y
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_ground_stays_without_world() {
+        let mut app = setup();
+        app.world_mut()
+            .spawn((RigidBody::Dynamic, PhysicsBody::default()));
+        app.update();
+
+        assert_eq!(ground_count(&mut app), 1);
+    }
+
+    #[test]
+    fn test_ground_removed_when_world_loads() {
+        let mut app = setup();
+        assert_eq!(ground_count(&mut app), 1);
+        app.world_mut().spawn((
+            RigidBody::Static,
+            PhysicsBody::default(),
+            floor(Vec3::ZERO),
+            GlobalTransform::from_xyz(0.0, -0.1, 0.0),
+        ));
+        app.update();
+
+        assert_eq!(ground_count(&mut app), 0);
+    }
+
+    #[test]
+    fn test_ground_stays_until_under_spawn_point() {
+        let mut app = setup();
+        app.world_mut()
+            .spawn((SpawnPoint, GlobalTransform::from_xyz(20.0, 1.0, 0.0)));
+        let body = app
+            .world_mut()
+            .spawn((RigidBody::Static, PhysicsBody::default()))
+            .id();
+        app.world_mut()
+            .spawn((floor(Vec3::ZERO), GlobalTransform::IDENTITY))
+            .set_parent(body);
+        app.update();
+        assert_eq!(ground_count(&mut app), 1);
+
+        app.world_mut()
+            .spawn((floor(Vec3::ZERO), GlobalTransform::from_xyz(20.0, 0.0, 0.0)))
+            .set_parent(body);
+        app.update();
+        assert_eq!(ground_count(&mut app), 0);
+    }
+
+    #[test]
+    fn test_is_under() {
+        let aabb = floor(Vec3::ZERO);
+        let gt = GlobalTransform::from_xyz(0.0, 0.0, 0.0);
+        assert!(is_under(&aabb, &gt, Vec3::new(1.0, 2.0, -1.0)));
+        assert!(!is_under(&aabb, &gt, Vec3::new(3.0, 2.0, 0.0)));
+        assert!(!is_under(&aabb, &gt, Vec3::new(0.0, -2.0, 0.0)));
+    }
+
+    fn floor(center: Vec3) -> Aabb {
+        Aabb::from_min_max(
+            center - Vec3::new(2.0, 0.1, 2.0),
+            center + Vec3::new(2.0, 0.1, 2.0),
+        )
+    }
+
+    fn ground_count(app: &mut App) -> usize {
+        app.world_mut()
+            .query_filtered::<(), With<FallbackGround>>()
+            .iter(app.world())
+            .count()
+    }
+
+    fn setup() -> App {
+        let mut app = App::new();
+        init(&mut app);
+        app.update();
+        app
+    }
+}
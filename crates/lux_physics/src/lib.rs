@@ -0,0 +1,63 @@
+mod auto;
+mod body;
+mod extras;
+mod ground;
+
+use avian3d::PhysicsPlugins;
+use bevy::prelude::*;
+use lux_cli::{Args, Command};
+
+pub use auto::{AutoCollider, NoAutoCollider};
+pub use body::{BodyKind, ColliderShape, PhysicsBody};
+pub use extras::{body_from_extras, body_from_name, collider_opt_out};
+
+/// Who runs the simulation of world bodies.
+/// The host is the authority and its results reach the other peers through
+/// the regular Transform sync, replicas only follow along.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PhysicsRole {
+    Authority,
+    Replica,
+}
+
+impl PhysicsRole {
+    pub fn from_args(args: &Args) -> Self {
+        match args.command {
+            Some(Command::Join { .. }) => Self::Replica,
+            _ => Self::Authority,
+        }
+    }
+}
+
+pub fn init(args: &Args, app: &mut App) {
+    app.insert_resource(PhysicsRole::from_args(args));
+    app.add_plugins(PhysicsPlugins::default());
+    auto::init(app);
+    body::init(app);
+    extras::init(app);
+    ground::init(app);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use clap::Parser;
+
+    #[test]
+    fn test_host_is_authority() {
+        let args = Args::parse_from(["lux", "host", "world.glb"]);
+        assert_eq!(PhysicsRole::from_args(&args), PhysicsRole::Authority);
+    }
+
+    #[test]
+    fn test_offline_is_authority() {
+        let args = Args::parse_from(["lux"]);
+        assert_eq!(PhysicsRole::from_args(&args), PhysicsRole::Authority);
+    }
+
+    #[test]
+    fn test_join_is_replica() {
+        let args = Args::parse_from(["lux", "join", "127.0.0.1"]);
+        assert_eq!(PhysicsRole::from_args(&args), PhysicsRole::Replica);
+    }
+}
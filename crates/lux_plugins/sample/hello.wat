@@ -0,0 +1,58 @@
+;; Sample Lux plugin, host API v1.
+;; Adds a "spin" component to the entity named Cube, accumulating the elapsed
+;; time, and a panel with a click counter.
+(module
+  (import "lux_v1" "log" (func $log (param i32 i32)))
+  (import "lux_v1" "register_component" (func $register_component (param i32 i32) (result i32)))
+  (import "lux_v1" "register_system" (func $register_system (param i32 i32) (result i32)))
+  (import "lux_v1" "register_panel" (func $register_panel (param i32 i32 i32 i32) (result i32)))
+  (import "lux_v1" "find_entity" (func $find_entity (param i32 i32) (result i64)))
+  (import "lux_v1" "get_component" (func $get_component (param i64 i32 i32 i32) (result i32)))
+  (import "lux_v1" "set_component" (func $set_component (param i64 i32 i32 i32) (result i32)))
+  (import "lux_v1" "ui_label" (func $ui_label (param i32 i32)))
+  (import "lux_v1" "ui_button" (func $ui_button (param i32 i32) (result i32)))
+  (import "lux_v1" "read_file" (func $read_file (param i32 i32 i32 i32) (result i32)))
+
+  (memory (export "memory") 1)
+  (data (i32.const 0) "hello from a plugin")
+  (data (i32.const 32) "spin")
+  (data (i32.const 48) "spin_system")
+  (data (i32.const 64) "Cube")
+  (data (i32.const 80) "Hello")
+  (data (i32.const 96) "hello_panel")
+  (data (i32.const 112) "Clicks")
+  (data (i32.const 128) "Click me")
+  (data (i32.const 144) "config.txt")
+
+  (global $spin (mut i32) (i32.const -1))
+  (global $clicks (export "clicks") (mut i32) (i32.const 0))
+
+  (func (export "lux_api_version") (result i32)
+    (i32.const 1))
+
+  (func (export "lux_init")
+    (call $log (i32.const 0) (i32.const 19))
+    (global.set $spin (call $register_component (i32.const 32) (i32.const 4)))
+    (drop (call $register_system (i32.const 48) (i32.const 11)))
+    (drop (call $register_panel (i32.const 80) (i32.const 5) (i32.const 96) (i32.const 11))))
+
+  (func (export "spin_system") (param $dt f32)
+    (local $entity i64)
+    (local.set $entity (call $find_entity (i32.const 64) (i32.const 4)))
+    (if (i64.lt_s (local.get $entity) (i64.const 0))
+      (then (return)))
+    (if (i32.ne
+          (call $get_component (local.get $entity) (global.get $spin) (i32.const 1024) (i32.const 4))
+          (i32.const 4))
+      (then (f32.store (i32.const 1024) (f32.const 0))))
+    (f32.store (i32.const 1024) (f32.add (f32.load (i32.const 1024)) (local.get $dt)))
+    (drop (call $set_component (local.get $entity) (global.get $spin) (i32.const 1024) (i32.const 4))))
+
+  (func (export "hello_panel")
+    (call $ui_label (i32.const 112) (i32.const 6))
+    (if (call $ui_button (i32.const 128) (i32.const 8))
+      (then (global.set $clicks (i32.add (global.get $clicks) (i32.const 1))))))
+
+  ;; Needs the "files" grant, returns the bytes read or a negative error.
+  (func (export "read_config") (result i32)
+    (call $read_file (i32.const 144) (i32.const 10) (i32.const 2048) (i32.const 256))))
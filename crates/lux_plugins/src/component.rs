@@ -0,0 +1,42 @@
+use bevy::prelude::*;
+use bevy_sync::SyncComponent;
+
+/// Components registered by plugins on an entity, as raw bytes owned by the
+/// plugin. Synched so that peers without the plugin still carry them along.
+#[derive(Component, Default, Reflect, Debug, Clone, PartialEq)]
+#[reflect(Component)]
+pub struct PluginComponents {
+    pub entries: Vec<PluginComponent>,
+}
+
+/// Named `<plugin>/<component>`.
+#[derive(Reflect, Default, Debug, Clone, PartialEq)]
+pub struct PluginComponent {
+    pub name: String,
+    pub data: Vec<u8>,
+}
+
+impl PluginComponents {
+    pub fn get(&self, name: &str) -> Option<&[u8]> {
+        self.entries
+            .iter()
+            .find(|c| c.name == name)
+            .map(|c| c.data.as_slice())
+    }
+
+    pub fn set(&mut self, name: &str, data: Vec<u8>) {
+        match self.entries.iter_mut().find(|c| c.name == name) {
+            Some(entry) => entry.data = data,
+            None => self.entries.push(PluginComponent {
+                name: name.to_owned(),
+                data,
+            }),
+        }
+    }
+}
+
+pub(crate) fn init(app: &mut App) {
+    app.register_type::<PluginComponent>();
+    app.register_type::<Vec<PluginComponent>>();
+    app.sync_component::<PluginComponents>();
+}
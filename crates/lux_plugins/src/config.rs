@@ -0,0 +1,112 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde_json::Value;
+
+/// File listing the plugins to load, inside the plugins folder.
+pub const CONFIG_FILE: &str = "plugins.json";
+
+/// What a plugin may reach outside of its own sandbox.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Capability {
+    /// Reading files from the plugin own data folder.
+    Files,
+    /// Sending UDP datagrams.
+    Network,
+}
+
+impl Capability {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "files" => Some(Self::Files),
+            "network" => Some(Self::Network),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PluginEntry {
+    pub file: PathBuf,
+    pub grants: Vec<Capability>,
+}
+
+impl PluginEntry {
+    /// Name of the plugin, the file name without extension.
+    pub fn name(&self) -> String {
+        self.file
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+}
+
+/// Reads `plugins.json` from the folder, paths in it are relative to the folder.
+pub fn read_config(dir: &Path) -> Result<Vec<PluginEntry>, String> {
+    let path = dir.join(CONFIG_FILE);
+    let text = fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    parse_config(dir, &text)
+}
+
+fn parse_config(dir: &Path, text: &str) -> Result<Vec<PluginEntry>, String> {
+    let value: Value = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    let Some(plugins) = value.get("plugins").and_then(Value::as_array) else {
+        return Err("missing \"plugins\" list".to_owned());
+    };
+    let mut entries = vec![];
+    for plugin in plugins {
+        let Some(file) = plugin.get("file").and_then(Value::as_str) else {
+            return Err("plugin without \"file\"".to_owned());
+        };
+        let mut grants = vec![];
+        for grant in plugin
+            .get("grants")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            let name = grant.as_str().unwrap_or_default();
+            let Some(capability) = Capability::parse(name) else {
+                return Err(format!("unknown grant \"{}\" for {}", name, file));
+            };
+            grants.push(capability);
+        }
+        entries.push(PluginEntry {
+            file: dir.join(file),
+            grants,
+        });
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_sample_config() {
+        let entries = parse_config(Path::new("sample"), include_str!("../sample/plugins.json"));
+        assert_eq!(
+            entries,
+            Ok(vec![PluginEntry {
+                file: PathBuf::from("sample/hello.wat"),
+                grants: vec![Capability::Files],
+            }])
+        );
+        assert_eq!(entries.unwrap()[0].name(), "hello");
+    }
+
+    #[test]
+    fn test_no_grants() {
+        let entries = parse_config(Path::new("."), r#"{"plugins": [{"file": "a.wasm"}]}"#);
+        assert_eq!(entries.unwrap()[0].grants, vec![]);
+    }
+
+    #[test]
+    fn test_unknown_grant() {
+        let text = r#"{"plugins": [{"file": "a.wasm", "grants": ["root"]}]}"#;
+        assert!(parse_config(Path::new("."), text).is_err());
+    }
+}
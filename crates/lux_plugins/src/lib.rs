@@ -0,0 +1,184 @@
+mod component;
+mod config;
+mod runtime;
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::Path,
+};
+
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts, EguiUserTextures};
+use lux_cli::{Args, Command};
+
+pub use component::{PluginComponent, PluginComponents};
+pub use config::{read_config, Capability, PluginEntry, CONFIG_FILE};
+pub use runtime::{Panel, UiOp, WasmPlugin, WorldView, API_VERSION, DENIED, INVALID, MISSING};
+
+/// Plugins loaded at startup, in the order of the config.
+#[derive(Default)]
+pub struct LoadedPlugins(pub Vec<WasmPlugin>);
+
+/// Loads the plugins listed in `plugins.json` of the `--plugins` folder.
+/// Their systems only run on the host, like scripts, while panels are
+/// drawn by every peer that has them.
+pub fn init(args: &Args, app: &mut App) {
+    component::init(app);
+    let Some(dir) = &args.plugins_dir else {
+        return;
+    };
+    let plugins = load_plugins(Path::new(dir));
+    info!("Loaded {} plugins", plugins.0.len());
+    app.insert_non_send_resource(plugins);
+    if !matches!(args.command, Some(Command::Join { .. })) {
+        app.add_systems(Update, run_plugin_systems);
+    }
+    app.add_systems(
+        Update,
+        draw_plugin_panels.run_if(resource_exists::<EguiUserTextures>),
+    );
+}
+
+/// A plugin that fails to load is skipped, the others still load.
+pub fn load_plugins(dir: &Path) -> LoadedPlugins {
+    let entries = match read_config(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            error!("Plugins not loaded: {}", e);
+            return LoadedPlugins::default();
+        }
+    };
+    let mut plugins = vec![];
+    for entry in entries {
+        let name = entry.name();
+        let wasm = match entry.file.extension().and_then(|e| e.to_str()) {
+            Some("wat") => wat::parse_file(&entry.file).map_err(|e| e.to_string()),
+            _ => fs::read(&entry.file).map_err(|e| e.to_string()),
+        };
+        match wasm.and_then(|wasm| WasmPlugin::load(&name, &wasm, entry.grants, dir.join(&name))) {
+            Ok(plugin) => plugins.push(plugin),
+            Err(e) => error!("Plugin {} not loaded: {}", entry.file.display(), e),
+        }
+    }
+    LoadedPlugins(plugins)
+}
+
+fn run_plugin_systems(
+    mut commands: Commands,
+    mut plugins: NonSendMut<LoadedPlugins>,
+    time: Res<Time>,
+    names: Query<(Entity, &Name)>,
+    mut components: Query<&mut PluginComponents>,
+) {
+    let mut view = WorldView::default();
+    for (entity, name) in names.iter() {
+        view.names
+            .entry(name.to_string())
+            .or_insert(entity.to_bits());
+        let Ok(plugin_components) = components.get(entity) else {
+            continue;
+        };
+        for component in &plugin_components.entries {
+            view.components.insert(
+                (entity.to_bits(), component.name.clone()),
+                component.data.clone(),
+            );
+        }
+    }
+    for plugin in plugins.0.iter_mut() {
+        plugin.run_systems(time.delta_seconds(), &mut view);
+    }
+    let mut inserted = HashMap::<Entity, PluginComponents>::new();
+    for (entity, name, data) in view.changed {
+        let entity = Entity::from_bits(entity);
+        match components.get_mut(entity) {
+            Ok(mut existing) => existing.set(&name, data),
+            Err(_) => inserted.entry(entity).or_default().set(&name, data),
+        }
+    }
+    for (entity, components) in inserted {
+        if let Some(mut entity) = commands.get_entity(entity) {
+            entity.insert(components);
+        }
+    }
+}
+
+/// Buttons clicked are handed to the plugin the next time it draws the panel.
+fn draw_plugin_panels(
+    mut plugins: NonSendMut<LoadedPlugins>,
+    mut contexts: EguiContexts,
+    mut clicked: Local<HashMap<(usize, usize), HashSet<usize>>>,
+) {
+    let ctx = contexts.ctx_mut();
+    for (p, plugin) in plugins.0.iter_mut().enumerate() {
+        for i in 0..plugin.panels().len() {
+            let title = plugin.panels()[i].title.clone();
+            let ops = plugin.draw_panel(i, clicked.remove(&(p, i)).unwrap_or_default());
+            let mut now = HashSet::new();
+            egui::Window::new(title)
+                .id(egui::Id::new(("lux_plugin", p, i)))
+                .show(ctx, |ui| {
+                    for (n, op) in ops.iter().enumerate() {
+                        match op {
+                            UiOp::Label(text) => {
+                                ui.label(text);
+                            }
+                            UiOp::Button(text) => {
+                                if ui.button(text).clicked() {
+                                    now.insert(n);
+                                }
+                            }
+                        }
+                    }
+                });
+            if !now.is_empty() {
+                clicked.insert((p, i), now);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{path::PathBuf, time::Duration};
+
+    use super::*;
+    use crate::runtime::test::sample;
+
+    #[test]
+    fn test_loads_sample_folder() {
+        let plugins = load_plugins(&Path::new(env!("CARGO_MANIFEST_DIR")).join("sample"));
+        assert_eq!(plugins.0.len(), 1);
+        assert_eq!(plugins.0[0].name(), "hello");
+    }
+
+    #[test]
+    fn test_missing_folder_loads_nothing() {
+        let plugins = load_plugins(Path::new("does/not/exist"));
+        assert!(plugins.0.is_empty());
+    }
+
+    #[test]
+    fn test_systems_write_components() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins);
+        app.insert_non_send_resource(LoadedPlugins(vec![sample(vec![], PathBuf::new())]));
+        app.add_systems(Update, run_plugin_systems);
+        let cube = app.world_mut().spawn(Name::new("Cube")).id();
+        let other = app.world_mut().spawn(Name::new("Sphere")).id();
+
+        app.world_mut()
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_millis(500));
+        app.world_mut().run_schedule(Update);
+        app.world_mut()
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_millis(500));
+        app.world_mut().run_schedule(Update);
+
+        let spin = app.world().get::<PluginComponents>(cube).unwrap();
+        assert_eq!(spin.get("hello/spin"), Some(&1.0f32.to_le_bytes()[..]));
+        assert!(app.world().get::<PluginComponents>(other).is_none());
+    }
+}
@@ -0,0 +1,583 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::Read,
+    net::UdpSocket,
+    path::{Component, Path, PathBuf},
+};
+
+use bevy::prelude::*;
+use wasmi::{
+    AsContext, Caller, Config, Engine, Extern, Instance, Linker, Memory, Module, Store,
+    StoreLimits, StoreLimitsBuilder,
+};
+
+use crate::config::Capability;
+
+/// Version of the host API, plugins must export `lux_api_version` returning it.
+pub const API_VERSION: i32 = 1;
+/// Import module of the host functions, bumped with the API version.
+const API_MODULE: &str = "lux_v1";
+/// Instructions a plugin can run on each call before being stopped.
+const FUEL_PER_CALL: u64 = 10_000_000;
+/// Linear memory a plugin can grow to.
+const MEMORY_LIMIT: usize = 32 * 1024 * 1024;
+
+/// The capability was not granted to the plugin.
+pub const DENIED: i32 = -1;
+/// Bad arguments, such as out of bounds pointers or a buffer too small.
+pub const INVALID: i32 = -2;
+/// The entity, component or file does not exist.
+pub const MISSING: i32 = -3;
+
+/// Panel drawn by a plugin, the export is called every frame to fill it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Panel {
+    pub title: String,
+    export: String,
+}
+
+/// What a panel asked to draw, in order.
+#[derive(Debug, Clone, PartialEq)]
+pub enum UiOp {
+    Label(String),
+    Button(String),
+}
+
+/// The part of the world plugins can see while their systems run.
+#[derive(Debug, Clone, Default)]
+pub struct WorldView {
+    pub names: HashMap<String, u64>,
+    pub components: HashMap<(u64, String), Vec<u8>>,
+    pub changed: Vec<(u64, String, Vec<u8>)>,
+}
+
+impl WorldView {
+    fn has_entity(&self, entity: u64) -> bool {
+        self.names.values().any(|e| *e == entity)
+    }
+}
+
+struct State {
+    name: String,
+    grants: Vec<Capability>,
+    data_dir: PathBuf,
+    limits: StoreLimits,
+    components: Vec<String>,
+    systems: Vec<String>,
+    panels: Vec<Panel>,
+    view: WorldView,
+    ui: Vec<UiOp>,
+    clicked: HashSet<usize>,
+}
+
+impl State {
+    fn granted(&self, capability: Capability) -> bool {
+        if self.grants.contains(&capability) {
+            return true;
+        }
+        warn!("Plugin {} was denied {:?}", self.name, capability);
+        false
+    }
+
+    /// Components are namespaced by plugin so that they don't clash.
+    fn component(&self, id: i32) -> Option<&String> {
+        usize::try_from(id)
+            .ok()
+            .and_then(|id| self.components.get(id))
+    }
+}
+
+/// A loaded WASM module, sandboxed in its own store.
+pub struct WasmPlugin {
+    store: Store<State>,
+    instance: Instance,
+}
+
+impl WasmPlugin {
+    /// Instantiates the module and runs its `lux_init`, where it registers
+    /// its components, systems and panels.
+    /// Files it may read live in `data_dir`, if granted.
+    pub fn load(
+        name: &str,
+        wasm: &[u8],
+        grants: Vec<Capability>,
+        data_dir: PathBuf,
+    ) -> Result<Self, String> {
+        let mut config = Config::default();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config);
+        let module = Module::new(&engine, wasm).map_err(|e| e.to_string())?;
+        let state = State {
+            name: name.to_owned(),
+            grants,
+            data_dir,
+            limits: StoreLimitsBuilder::new().memory_size(MEMORY_LIMIT).build(),
+            components: vec![],
+            systems: vec![],
+            panels: vec![],
+            view: WorldView::default(),
+            ui: vec![],
+            clicked: HashSet::new(),
+        };
+        let mut store = Store::new(&engine, state);
+        store.limiter(|state| &mut state.limits);
+        store.set_fuel(FUEL_PER_CALL).map_err(|e| e.to_string())?;
+        let instance = host_api(&engine)?
+            .instantiate(&mut store, &module)
+            .and_then(|pre| pre.start(&mut store))
+            .map_err(|e| e.to_string())?;
+        let version = instance
+            .get_typed_func::<(), i32>(&store, "lux_api_version")
+            .and_then(|f| f.call(&mut store, ()))
+            .map_err(|e| format!("no lux_api_version: {}", e))?;
+        if version != API_VERSION {
+            return Err(format!(
+                "host API version {} is not supported, expected {}",
+                version, API_VERSION
+            ));
+        }
+        let mut plugin = Self { store, instance };
+        plugin.call("lux_init", ())?;
+        Ok(plugin)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.store.data().name
+    }
+
+    /// Full names of the registered components.
+    pub fn components(&self) -> &[String] {
+        &self.store.data().components
+    }
+
+    pub fn panels(&self) -> &[Panel] {
+        &self.store.data().panels
+    }
+
+    /// Runs every registered system, changes are applied to the view and
+    /// listed in its `changed`.
+    /// A failing system is logged and does not stop the others.
+    pub fn run_systems(&mut self, dt: f32, view: &mut WorldView) {
+        self.store.data_mut().view = std::mem::take(view);
+        for system in self.store.data().systems.clone() {
+            if let Err(e) = self.call(&system, dt) {
+                error!("Plugin {} system {}: {}", self.name(), system, e);
+            }
+        }
+        *view = std::mem::take(&mut self.store.data_mut().view);
+    }
+
+    /// Draws a panel, `clicked` are the buttons clicked when it was last shown.
+    pub fn draw_panel(&mut self, index: usize, clicked: HashSet<usize>) -> Vec<UiOp> {
+        let Some(panel) = self.panels().get(index).cloned() else {
+            return vec![];
+        };
+        let state = self.store.data_mut();
+        state.ui.clear();
+        state.clicked = clicked;
+        if let Err(e) = self.call(&panel.export, ()) {
+            error!("Plugin {} panel {}: {}", self.name(), panel.title, e);
+        }
+        std::mem::take(&mut self.store.data_mut().ui)
+    }
+
+    fn call<P: wasmi::WasmParams>(&mut self, export: &str, params: P) -> Result<(), String> {
+        self.store
+            .set_fuel(FUEL_PER_CALL)
+            .map_err(|e| e.to_string())?;
+        self.instance
+            .get_typed_func::<P, ()>(&self.store, export)
+            .and_then(|f| f.call(&mut self.store, params))
+            .map_err(|e| e.to_string())
+    }
+
+    #[cfg(test)]
+    fn global(&self, name: &str) -> Option<i32> {
+        self.instance
+            .get_global(&self.store, name)
+            .and_then(|g| g.get(&self.store).i32())
+    }
+}
+
+fn memory(caller: &Caller<'_, State>) -> Option<Memory> {
+    caller.get_export("memory").and_then(Extern::into_memory)
+}
+
+fn read_bytes(caller: &Caller<'_, State>, ptr: i32, len: i32) -> Option<Vec<u8>> {
+    let memory = memory(caller)?;
+    let len = usize::try_from(len).ok()?;
+    if len > memory.data(caller.as_context()).len() {
+        return None;
+    }
+    let mut buffer = vec![0; len];
+    memory.read(caller, ptr as u32 as usize, &mut buffer).ok()?;
+    Some(buffer)
+}
+
+fn read_str(caller: &Caller<'_, State>, ptr: i32, len: i32) -> Option<String> {
+    String::from_utf8(read_bytes(caller, ptr, len)?).ok()
+}
+
+/// Writes into the plugin buffer, returns the length written.
+fn write_bytes(caller: &mut Caller<'_, State>, ptr: i32, cap: i32, bytes: &[u8]) -> i32 {
+    let Some(memory) = memory(caller) else {
+        return INVALID;
+    };
+    if bytes.len() > cap.max(0) as usize {
+        return INVALID;
+    }
+    match memory.write(caller, ptr as u32 as usize, bytes) {
+        Ok(()) => bytes.len() as i32,
+        Err(_) => INVALID,
+    }
+}
+
+/// Files can only be read from the plugin own folder, also through symlinks.
+fn sandboxed_path(dir: &Path, path: &str) -> Option<PathBuf> {
+    let path = Path::new(path);
+    if !path.components().all(|c| matches!(c, Component::Normal(_))) {
+        return None;
+    }
+    let path = dir.join(path);
+    match (dir.canonicalize(), path.canonicalize()) {
+        (Ok(dir), Ok(path)) => path.starts_with(dir).then_some(path),
+        // Missing, which the read reports.
+        _ => Some(path),
+    }
+}
+
+/// None when the file is larger than `limit`, checked before reading it.
+fn read_limited(path: &Path, limit: usize) -> std::io::Result<Option<Vec<u8>>> {
+    let file = fs::File::open(path)?;
+    if file.metadata()?.len() > limit as u64 {
+        return Ok(None);
+    }
+    // Files that grow, or report no length, are still cut at the limit.
+    let mut data = vec![];
+    file.take(limit as u64 + 1).read_to_end(&mut data)?;
+    Ok((data.len() <= limit).then_some(data))
+}
+
+fn host_api(engine: &Engine) -> Result<Linker<State>, String> {
+    let mut linker = Linker::<State>::new(engine);
+    let error = |e: wasmi::errors::LinkerError| e.to_string();
+    linker
+        .func_wrap(
+            API_MODULE,
+            "log",
+            |caller: Caller<'_, State>, ptr: i32, len: i32| {
+                if let Some(text) = read_str(&caller, ptr, len) {
+                    info!("[{}] {}", caller.data().name, text);
+                }
+            },
+        )
+        .map_err(error)?;
+    linker
+        .func_wrap(
+            API_MODULE,
+            "register_component",
+            |mut caller: Caller<'_, State>, ptr: i32, len: i32| -> i32 {
+                let Some(name) = read_str(&caller, ptr, len) else {
+                    return INVALID;
+                };
+                let state = caller.data_mut();
+                let full = format!("{}/{}", state.name, name);
+                if let Some(id) = state.components.iter().position(|c| *c == full) {
+                    return id as i32;
+                }
+                state.components.push(full);
+                state.components.len() as i32 - 1
+            },
+        )
+        .map_err(error)?;
+    linker
+        .func_wrap(
+            API_MODULE,
+            "register_system",
+            |mut caller: Caller<'_, State>, ptr: i32, len: i32| -> i32 {
+                let Some(export) = read_str(&caller, ptr, len) else {
+                    return INVALID;
+                };
+                let state = caller.data_mut();
+                state.systems.push(export);
+                state.systems.len() as i32 - 1
+            },
+        )
+        .map_err(error)?;
+    linker
+        .func_wrap(
+            API_MODULE,
+            "register_panel",
+            |mut caller: Caller<'_, State>,
+             title_ptr: i32,
+             title_len: i32,
+             ptr: i32,
+             len: i32|
+             -> i32 {
+                let (Some(title), Some(export)) = (
+                    read_str(&caller, title_ptr, title_len),
+                    read_str(&caller, ptr, len),
+                ) else {
+                    return INVALID;
+                };
+                let state = caller.data_mut();
+                state.panels.push(Panel { title, export });
+                state.panels.len() as i32 - 1
+            },
+        )
+        .map_err(error)?;
+    linker
+        .func_wrap(
+            API_MODULE,
+            "find_entity",
+            |caller: Caller<'_, State>, ptr: i32, len: i32| -> i64 {
+                read_str(&caller, ptr, len)
+                    .and_then(|name| caller.data().view.names.get(&name).copied())
+                    .map(|entity| entity as i64)
+                    .unwrap_or(-1)
+            },
+        )
+        .map_err(error)?;
+    linker
+        .func_wrap(
+            API_MODULE,
+            "get_component",
+            |mut caller: Caller<'_, State>, entity: i64, id: i32, ptr: i32, cap: i32| -> i32 {
+                let state = caller.data();
+                let Some(name) = state.component(id) else {
+                    return INVALID;
+                };
+                let Some(data) = state.view.components.get(&(entity as u64, name.clone())) else {
+                    return MISSING;
+                };
+                let data = data.clone();
+                write_bytes(&mut caller, ptr, cap, &data)
+            },
+        )
+        .map_err(error)?;
+    linker
+        .func_wrap(
+            API_MODULE,
+            "set_component",
+            |mut caller: Caller<'_, State>, entity: i64, id: i32, ptr: i32, len: i32| -> i32 {
+                let Some(data) = read_bytes(&caller, ptr, len) else {
+                    return INVALID;
+                };
+                let state = caller.data_mut();
+                let Some(name) = state.component(id).cloned() else {
+                    return INVALID;
+                };
+                let entity = entity as u64;
+                if !state.view.has_entity(entity) {
+                    return MISSING;
+                }
+                let key = (entity, name.clone());
+                if state.view.components.get(&key) != Some(&data) {
+                    state.view.components.insert(key, data.clone());
+                    state.view.changed.push((entity, name, data));
+                }
+                0
+            },
+        )
+        .map_err(error)?;
+    linker
+        .func_wrap(
+            API_MODULE,
+            "ui_label",
+            |mut caller: Caller<'_, State>, ptr: i32, len: i32| {
+                if let Some(text) = read_str(&caller, ptr, len) {
+                    caller.data_mut().ui.push(UiOp::Label(text));
+                }
+            },
+        )
+        .map_err(error)?;
+    linker
+        .func_wrap(
+            API_MODULE,
+            "ui_button",
+            |mut caller: Caller<'_, State>, ptr: i32, len: i32| -> i32 {
+                let Some(text) = read_str(&caller, ptr, len) else {
+                    return INVALID;
+                };
+                let state = caller.data_mut();
+                let clicked = state.clicked.contains(&state.ui.len());
+                state.ui.push(UiOp::Button(text));
+                clicked as i32
+            },
+        )
+        .map_err(error)?;
+    linker
+        .func_wrap(
+            API_MODULE,
+            "read_file",
+            |mut caller: Caller<'_, State>, ptr: i32, len: i32, out: i32, cap: i32| -> i32 {
+                if !caller.data().granted(Capability::Files) {
+                    return DENIED;
+                }
+                let Some(path) = read_str(&caller, ptr, len)
+                    .and_then(|path| sandboxed_path(&caller.data().data_dir, &path))
+                else {
+                    return INVALID;
+                };
+                match read_limited(&path, cap.max(0) as usize) {
+                    Ok(Some(data)) => write_bytes(&mut caller, out, cap, &data),
+                    Ok(None) => INVALID,
+                    Err(_) => MISSING,
+                }
+            },
+        )
+        .map_err(error)?;
+    linker
+        .func_wrap(
+            API_MODULE,
+            "udp_send",
+            |caller: Caller<'_, State>, ptr: i32, len: i32, data: i32, data_len: i32| -> i32 {
+                if !caller.data().granted(Capability::Network) {
+                    return DENIED;
+                }
+                let (Some(addr), Some(data)) = (
+                    read_str(&caller, ptr, len),
+                    read_bytes(&caller, data, data_len),
+                ) else {
+                    return INVALID;
+                };
+                UdpSocket::bind("0.0.0.0:0")
+                    .and_then(|socket| socket.send_to(&data, addr))
+                    .map(|sent| sent as i32)
+                    .unwrap_or(INVALID)
+            },
+        )
+        .map_err(error)?;
+    Ok(linker)
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+
+    pub(crate) fn sample(grants: Vec<Capability>, data_dir: PathBuf) -> WasmPlugin {
+        let wasm = wat::parse_str(include_str!("../sample/hello.wat")).unwrap();
+        WasmPlugin::load("hello", &wasm, grants, data_dir).unwrap()
+    }
+
+    fn read_config(plugin: &mut WasmPlugin) -> i32 {
+        plugin
+            .instance
+            .get_typed_func::<(), i32>(&plugin.store, "read_config")
+            .unwrap()
+            .call(&mut plugin.store, ())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_registers_on_init() {
+        let plugin = sample(vec![], PathBuf::new());
+        assert_eq!(plugin.name(), "hello");
+        assert_eq!(plugin.components(), ["hello/spin"]);
+        assert_eq!(plugin.store.data().systems, ["spin_system"]);
+        assert_eq!(plugin.panels()[0].title, "Hello");
+    }
+
+    #[test]
+    fn test_rejects_other_api_versions() {
+        let wasm = wat::parse_str(
+            r#"(module
+                (func (export "lux_api_version") (result i32) (i32.const 2))
+                (func (export "lux_init")))"#,
+        )
+        .unwrap();
+        assert!(WasmPlugin::load("old", &wasm, vec![], PathBuf::new()).is_err());
+    }
+
+    #[test]
+    fn test_unknown_imports_fail() {
+        let wasm = wat::parse_str(
+            r#"(module
+                (import "env" "system" (func))
+                (func (export "lux_api_version") (result i32) (i32.const 1))
+                (func (export "lux_init")))"#,
+        )
+        .unwrap();
+        assert!(WasmPlugin::load("evil", &wasm, vec![], PathBuf::new()).is_err());
+    }
+
+    #[test]
+    fn test_runaway_plugin_is_stopped() {
+        let wasm = wat::parse_str(
+            r#"(module
+                (func (export "lux_api_version") (result i32) (i32.const 1))
+                (func (export "lux_init") (loop (br 0))))"#,
+        )
+        .unwrap();
+        assert!(WasmPlugin::load("loop", &wasm, vec![], PathBuf::new()).is_err());
+    }
+
+    #[test]
+    fn test_system_sets_component() {
+        let mut plugin = sample(vec![], PathBuf::new());
+        let mut view = WorldView::default();
+        view.names.insert("Cube".to_owned(), 7);
+        plugin.run_systems(0.5, &mut view);
+        plugin.run_systems(0.25, &mut view);
+        let spin = view.components[&(7, "hello/spin".to_owned())].clone();
+        assert_eq!(spin, 0.75f32.to_le_bytes());
+        assert_eq!(view.changed.len(), 2);
+    }
+
+    #[test]
+    fn test_system_without_entity() {
+        let mut plugin = sample(vec![], PathBuf::new());
+        let mut view = WorldView::default();
+        plugin.run_systems(0.5, &mut view);
+        assert!(view.changed.is_empty());
+    }
+
+    #[test]
+    fn test_panel_buttons() {
+        let mut plugin = sample(vec![], PathBuf::new());
+        let ops = plugin.draw_panel(0, HashSet::new());
+        assert_eq!(
+            ops,
+            [
+                UiOp::Label("Clicks".to_owned()),
+                UiOp::Button("Click me".to_owned())
+            ]
+        );
+        plugin.draw_panel(0, HashSet::from([1]));
+        assert_eq!(plugin.global("clicks"), Some(1));
+    }
+
+    #[test]
+    fn test_files_need_grant() {
+        let dir = std::env::temp_dir().join(format!("lux_plugin_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("config.txt"), "speed=2").unwrap();
+        let mut denied = sample(vec![], dir.clone());
+        assert_eq!(read_config(&mut denied), DENIED);
+        let mut granted = sample(vec![Capability::Files], dir.clone());
+        assert_eq!(read_config(&mut granted), 7);
+        fs::write(dir.join("config.txt"), [b'x'; 4096]).unwrap();
+        assert_eq!(read_config(&mut granted), INVALID);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_files_stay_in_sandbox() {
+        let root = std::env::temp_dir().join(format!("lux_sandbox_{}", std::process::id()));
+        let dir = root.join("hello");
+        fs::create_dir_all(dir.join("a")).unwrap();
+        fs::write(dir.join("a/b.txt"), "b").unwrap();
+        fs::write(root.join("secret"), "s").unwrap();
+        let dir = dir.canonicalize().unwrap();
+        assert_eq!(sandboxed_path(&dir, "a/b.txt"), Some(dir.join("a/b.txt")));
+        assert_eq!(sandboxed_path(&dir, "../secret"), None);
+        assert_eq!(sandboxed_path(&dir, "/etc/passwd"), None);
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(root.join("secret"), dir.join("link")).unwrap();
+            assert_eq!(sandboxed_path(&dir, "link"), None);
+        }
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,253 @@
+use std::path::PathBuf;
+
+use avian3d::prelude::CollisionStarted;
+use bevy::prelude::*;
+use lux_components::{CustomComponents, User};
+
+use crate::{
+    runtime::{EntityState, Handler, ScriptRuntime},
+    Script, ScriptClicks,
+};
+
+/// How often script files are checked for changes.
+const RELOAD_INTERVAL_SECONDS: f32 = 1.0;
+
+/// Folder of the world package, where script paths start from.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct ScriptRoot(pub PathBuf);
+
+pub(crate) fn init(app: &mut App) {
+    match ScriptRuntime::new() {
+        Ok(runtime) => {
+            app.insert_non_send_resource(runtime);
+            app.add_event::<CollisionStarted>();
+            app.add_systems(Update, run_scripts);
+        }
+        Err(e) => error!("Scripting is not available: {}", e),
+    }
+}
+
+type ScriptedQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static mut Transform,
+        Option<&'static mut Name>,
+        Option<&'static mut Visibility>,
+        Option<&'static mut CustomComponents>,
+    ),
+    With<Script>,
+>;
+
+#[allow(clippy::too_many_arguments)]
+fn run_scripts(
+    mut runtime: NonSendMut<ScriptRuntime>,
+    root: Res<ScriptRoot>,
+    time: Res<Time>,
+    mut reload: Local<Option<Timer>>,
+    attached: Query<(Entity, &Script), Changed<Script>>,
+    mut detached: RemovedComponents<Script>,
+    mut collisions: EventReader<CollisionStarted>,
+    clicks: Query<(Entity, &ScriptClicks), Changed<ScriptClicks>>,
+    users: Query<Entity, Added<User>>,
+    parents: Query<&Parent>,
+    mut scripted: ScriptedQuery,
+) {
+    for entity in detached.read() {
+        runtime.unload(entity);
+    }
+    let mut calls = vec![];
+    for (entity, script) in attached.iter() {
+        match runtime.load(entity, &root.0, &script.path) {
+            Ok(_) => calls.push((entity, Handler::Start)),
+            Err(e) => warn!("Script {} failed to load: {}", script.path, e),
+        }
+    }
+    let reload = reload
+        .get_or_insert_with(|| Timer::from_seconds(RELOAD_INTERVAL_SECONDS, TimerMode::Repeating));
+    if reload.tick(time.delta()).just_finished() {
+        for (entity, result) in runtime.reload_changed() {
+            match result {
+                Ok(_) => {
+                    info!("Reloaded script of {:?}", entity);
+                    calls.push((entity, Handler::Start));
+                }
+                Err(e) => warn!("Script of {:?} failed to reload: {}", entity, e),
+            }
+        }
+    }
+
+    let scripted_ancestor = |mut entity: Entity| loop {
+        if runtime.is_loaded(entity) {
+            return Some(entity);
+        }
+        entity = parents.get(entity).ok()?.get();
+    };
+    for CollisionStarted(a, b) in collisions.read() {
+        if let Some(script) = scripted_ancestor(*a) {
+            calls.push((script, Handler::Touch(b.to_bits())));
+        }
+        if let Some(script) = scripted_ancestor(*b) {
+            calls.push((script, Handler::Touch(a.to_bits())));
+        }
+    }
+    for (entity, clicks) in clicks.iter() {
+        if clicks.count > 0 {
+            calls.push((entity, Handler::Click(clicks.peer)));
+        }
+    }
+    let joined: Vec<Entity> = users.iter().collect();
+    for (entity, name) in runtime.tick_timers(time.delta()) {
+        calls.push((entity, Handler::Timer(name)));
+    }
+
+    let dt = time.delta_seconds();
+    for entity in runtime.loaded() {
+        for user in joined.iter() {
+            calls.push((entity, Handler::UserJoin(user.to_bits())));
+        }
+        calls.push((entity, Handler::Update(dt)));
+    }
+
+    for (entity, handler) in calls {
+        let Ok((mut transform, mut name, mut visibility, mut components)) =
+            scripted.get_mut(entity)
+        else {
+            continue;
+        };
+        let before = EntityState {
+            id: entity.to_bits(),
+            transform: *transform,
+            name: name.as_ref().map(|n| n.as_str().to_owned()),
+            visible: visibility
+                .as_ref()
+                .map(|v| **v != Visibility::Hidden)
+                .unwrap_or(true),
+            components: components.as_deref().cloned().unwrap_or_default(),
+        };
+        let mut after = before.clone();
+        if let Err(e) = runtime.call(entity, &handler, &mut after) {
+            warn!("Script of {:?} failed in {:?}: {}", entity, handler, e);
+            continue;
+        }
+        // Write only what changed, not to send unchanged data to the peers.
+        if after.transform != before.transform {
+            *transform = after.transform;
+        }
+        if after.name != before.name {
+            if let (Some(name), Some(new)) = (name.as_mut(), after.name) {
+                name.set(new);
+            }
+        }
+        if after.visible != before.visible {
+            if let Some(visibility) = visibility.as_mut() {
+                **visibility = if after.visible {
+                    Visibility::Inherited
+                } else {
+                    Visibility::Hidden
+                };
+            }
+        }
+        if after.components != before.components {
+            if let Some(components) = components.as_mut() {
+                **components = after.components;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::runtime::test::TempScript;
+
+    #[test]
+    fn test_script_starts_when_attached() {
+        let script = TempScript::new("function on_start(self) self.position.x = 2 end");
+        let mut app = setup();
+        let entity = attach(&mut app, &script);
+        app.update();
+
+        let transform = app.world().entity(entity).get::<Transform>().unwrap();
+        assert_eq!(transform.translation.x, 2.0);
+    }
+
+    #[test]
+    fn test_click_reaches_script() {
+        let script =
+            TempScript::new("function on_click(self, peer) self.name = 'clicked by ' .. peer end");
+        let mut app = setup();
+        let entity = attach(&mut app, &script);
+        app.update();
+        app.world_mut()
+            .entity_mut(entity)
+            .insert(ScriptClicks { count: 1, peer: 7 });
+        app.update();
+
+        let name = app.world().entity(entity).get::<Name>().unwrap();
+        assert_eq!(name.as_str(), "clicked by 7");
+    }
+
+    #[test]
+    fn test_user_join_reaches_script() {
+        let script = TempScript::new("function on_user_join(self, user) self.visible = false end");
+        let mut app = setup();
+        let entity = attach(&mut app, &script);
+        app.update();
+        app.world_mut().spawn(User);
+        app.update();
+
+        let visibility = app.world().entity(entity).get::<Visibility>().unwrap();
+        assert_eq!(*visibility, Visibility::Hidden);
+    }
+
+    #[test]
+    fn test_touch_reaches_scripted_parent() {
+        let script = TempScript::new("function on_touch(self, other) self.scale.y = 3 end");
+        let mut app = setup();
+        let entity = attach(&mut app, &script);
+        let collider = app.world_mut().spawn_empty().set_parent(entity).id();
+        let other = app.world_mut().spawn_empty().id();
+        app.update();
+        app.world_mut()
+            .send_event(CollisionStarted(other, collider));
+        app.update();
+
+        let transform = app.world().entity(entity).get::<Transform>().unwrap();
+        assert_eq!(transform.scale.y, 3.0);
+    }
+
+    #[test]
+    fn test_broken_script_does_not_stop_others() {
+        let broken = TempScript::new("function on_start(self) error('boom') end");
+        let working = TempScript::new("function on_start(self) self.position.z = 1 end");
+        let mut app = setup();
+        attach(&mut app, &broken);
+        let entity = attach(&mut app, &working);
+        app.update();
+
+        let transform = app.world().entity(entity).get::<Transform>().unwrap();
+        assert_eq!(transform.translation.z, 1.0);
+    }
+
+    fn setup() -> App {
+        let mut app = App::new();
+        app.init_resource::<Time>();
+        app.insert_resource(ScriptRoot(std::env::temp_dir()));
+        init(&mut app);
+        app
+    }
+
+    fn attach(app: &mut App, script: &TempScript) -> Entity {
+        app.world_mut()
+            .spawn((
+                Script {
+                    path: script.name(),
+                },
+                Transform::default(),
+                Name::new("scripted"),
+                Visibility::default(),
+            ))
+            .id()
+    }
+}
@@ -0,0 +1,55 @@
+mod host;
+mod runtime;
+mod script;
+
+use std::path::{Path, PathBuf};
+
+use bevy::{asset::io::file::FileAssetReader, prelude::*};
+use lux_cli::{Args, Command};
+
+pub use host::ScriptRoot;
+pub use script::{Script, ScriptClicks};
+
+/// Scripts are synched to everybody but only run on the host,
+/// their effects reach the others through the synched components.
+pub fn init(args: &Args, app: &mut App) {
+    script::init(app);
+    if matches!(args.command, Some(Command::Join { .. })) {
+        return;
+    }
+    let assets = FileAssetReader::get_base_path().join("assets");
+    app.insert_resource(ScriptRoot(script_root(args, &assets)));
+    host::init(app);
+}
+
+/// Scripts ship next to the world file, in the assets folder.
+fn script_root(args: &Args, assets: &Path) -> PathBuf {
+    let world_dir = match &args.command {
+        Some(Command::Host { world_file, .. }) => Path::new(world_file).parent(),
+        _ => None,
+    };
+    match world_dir {
+        Some(dir) => assets.join(dir),
+        None => assets.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use clap::Parser;
+
+    #[test]
+    fn test_scripts_next_to_world_file() {
+        let args = Args::parse_from(["lux", "host", "worlds/park/park.glb"]);
+        let root = script_root(&args, Path::new("assets"));
+        assert_eq!(root, Path::new("assets/worlds/park"));
+    }
+
+    #[test]
+    fn test_scripts_in_assets_without_world() {
+        let args = Args::parse_from(["lux"]);
+        let root = script_root(&args, Path::new("assets"));
+        assert_eq!(root, Path::new("assets"));
+    }
+}
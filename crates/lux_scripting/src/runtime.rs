@@ -0,0 +1,807 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::Read,
+    path::{Component, Path, PathBuf},
+    time::SystemTime,
+};
+
+use bevy::prelude::*;
+use lux_components::{CustomComponents, CustomValue};
+use mlua::{Function, HookTriggers, Lua, LuaOptions, RegistryKey, StdLib, Table, Value};
+
+/// Scripts can not allocate more than this.
+const MEMORY_LIMIT: usize = 16 * 1024 * 1024;
+/// Largest script file that is loaded.
+const MAX_SCRIPT_BYTES: usize = 1024 * 1024;
+/// Instructions a single handler call can run before being stopped.
+const INSTRUCTION_BUDGET: u32 = 1_000_000;
+/// How often the budget is checked, in instructions.
+const HOOK_INTERVAL: u32 = 1000;
+/// Shortest and longest timers, shorter ones would fire in a busy loop.
+const MIN_TIMER_SECONDS: f32 = 0.01;
+const MAX_TIMER_SECONDS: f32 = 24.0 * 60.0 * 60.0;
+/// Most times a timer fires in one tick, when the frame was long.
+const MAX_TIMER_FIRES: u32 = 4;
+
+/// Globals that could load code from outside the world package, or bytecode.
+const UNSAFE_GLOBALS: [&str; 4] = ["load", "loadfile", "dofile", "collectgarbage"];
+
+/// The callbacks a script can define.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Handler {
+    Start,
+    Update(f32),
+    Touch(u64),
+    Click(u64),
+    Timer(String),
+    UserJoin(u64),
+}
+
+impl Handler {
+    fn function_name(&self) -> &'static str {
+        match self {
+            Handler::Start => "on_start",
+            Handler::Update(_) => "on_update",
+            Handler::Touch(_) => "on_touch",
+            Handler::Click(_) => "on_click",
+            Handler::Timer(_) => "on_timer",
+            Handler::UserJoin(_) => "on_user_join",
+        }
+    }
+}
+
+/// What a script sees and can change of its entity, as the `self` argument.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct EntityState {
+    pub id: u64,
+    pub transform: Transform,
+    pub name: Option<String>,
+    pub visible: bool,
+    pub components: CustomComponents,
+}
+
+struct ScriptTimer {
+    name: String,
+    timer: Timer,
+}
+
+struct ScriptInstance {
+    root: PathBuf,
+    script: String,
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    env: RegistryKey,
+    timers: Vec<ScriptTimer>,
+}
+
+/// Requests from the `lux` API, applied after each call.
+enum Op {
+    SetTimer {
+        name: String,
+        seconds: f32,
+        repeating: bool,
+    },
+    CancelTimer(String),
+}
+
+#[derive(Default)]
+struct Ops(Vec<Op>);
+
+struct Budget(u32);
+
+/// One Lua state for all the scripts, each script has its own environment.
+pub struct ScriptRuntime {
+    lua: Lua,
+    instances: HashMap<Entity, ScriptInstance>,
+}
+
+impl ScriptRuntime {
+    pub fn new() -> mlua::Result<Self> {
+        let lua = Lua::new_with(
+            StdLib::TABLE | StdLib::STRING | StdLib::MATH | StdLib::UTF8 | StdLib::COROUTINE,
+            LuaOptions::new(),
+        )?;
+        lua.set_memory_limit(MEMORY_LIMIT)?;
+        for name in UNSAFE_GLOBALS {
+            lua.globals().set(name, Value::Nil)?;
+        }
+        {
+            let string: Table = lua.globals().get("string")?;
+            string.set("dump", Value::Nil)?;
+        }
+        // Strings reach the shared string table through their metatable.
+        lua.load("getmetatable('').__metatable = false").exec()?;
+        lua.set_app_data(Ops::default());
+        lua.set_app_data(Budget(INSTRUCTION_BUDGET));
+        lua.set_hook(
+            HookTriggers::new().every_nth_instruction(HOOK_INTERVAL),
+            |lua, _| {
+                let Some(mut budget) = lua.app_data_mut::<Budget>() else {
+                    return Ok(());
+                };
+                budget.0 = budget.0.saturating_sub(HOOK_INTERVAL);
+                if budget.0 == 0 {
+                    return Err(mlua::Error::runtime("script ran for too long"));
+                }
+                Ok(())
+            },
+        );
+        lua.globals().set("lux", lux_api(&lua)?)?;
+        Ok(Self {
+            lua,
+            instances: HashMap::new(),
+        })
+    }
+
+    pub fn is_loaded(&self, entity: Entity) -> bool {
+        self.instances.contains_key(&entity)
+    }
+
+    pub fn loaded(&self) -> Vec<Entity> {
+        self.instances.keys().copied().collect()
+    }
+
+    /// Loads the script at `script` in `root` and runs its top level,
+    /// replacing what the entity had. Paths out of `root` are refused.
+    pub fn load(&mut self, entity: Entity, root: &Path, script: &str) -> Result<(), String> {
+        self.unload(entity);
+        let path = sandboxed_path(root, script)
+            .ok_or_else(|| format!("{} is not in the world folder", script))?;
+        let source = read_script(&path)?;
+        let modified = modified(&path);
+        let env = self.load_source(&source, script)?;
+        self.instances.insert(
+            entity,
+            ScriptInstance {
+                root: root.to_owned(),
+                script: script.to_owned(),
+                path,
+                modified,
+                env,
+                timers: vec![],
+            },
+        );
+        Ok(())
+    }
+
+    fn load_source(&self, source: &str, chunk_name: &str) -> Result<RegistryKey, String> {
+        let env = self.env().map_err(|e| e.to_string())?;
+        self.reset_budget();
+        self.lua
+            .load(source)
+            .set_name(chunk_name)
+            .set_mode(mlua::ChunkMode::Text)
+            .set_environment(env.clone())
+            .exec()
+            .map_err(|e| e.to_string())?;
+        self.lua
+            .create_registry_value(env)
+            .map_err(|e| e.to_string())
+    }
+
+    /// Globals of a script: its own, falling back to read only views of the
+    /// shared ones, so that a script can not change them for the others.
+    fn env(&self) -> mlua::Result<Table<'_>> {
+        let env = self.lua.create_table()?;
+        let shared = self.lua.create_table()?;
+        for pair in self.lua.globals().pairs::<Value, Value>() {
+            let (key, value) = pair?;
+            let value = match value {
+                Value::Table(table) => Value::Table(read_only(&self.lua, table)?),
+                value => value,
+            };
+            shared.raw_set(key, value)?;
+        }
+        shared.raw_set("_G", env.clone())?;
+        let meta = self.lua.create_table()?;
+        meta.set("__index", shared)?;
+        env.set_metatable(Some(meta));
+        Ok(env)
+    }
+
+    pub fn unload(&mut self, entity: Entity) {
+        if let Some(instance) = self.instances.remove(&entity) {
+            let _ = self.lua.remove_registry_value(instance.env);
+        }
+    }
+
+    /// Reloads the scripts whose file changed on disk, returning their entities.
+    pub fn reload_changed(&mut self) -> Vec<(Entity, Result<(), String>)> {
+        let changed: Vec<(Entity, PathBuf, String)> = self
+            .instances
+            .iter()
+            .filter(|(_, i)| modified(&i.path) != i.modified)
+            .map(|(e, i)| (*e, i.root.clone(), i.script.clone()))
+            .collect();
+        changed
+            .into_iter()
+            .map(|(entity, root, script)| (entity, self.load(entity, &root, &script)))
+            .collect()
+    }
+
+    /// Advances the timers of all the scripts, returning the ones that fired.
+    pub fn tick_timers(&mut self, delta: std::time::Duration) -> Vec<(Entity, String)> {
+        let mut fired = vec![];
+        for (entity, instance) in self.instances.iter_mut() {
+            for timer in instance.timers.iter_mut() {
+                timer.timer.tick(delta);
+                let times = timer.timer.times_finished_this_tick();
+                for _ in 0..times.min(MAX_TIMER_FIRES) {
+                    fired.push((*entity, timer.name.clone()));
+                }
+            }
+            instance
+                .timers
+                .retain(|t| t.timer.mode() == TimerMode::Repeating || !t.timer.finished());
+        }
+        fired
+    }
+
+    /// Calls the handler if the script defines it. The state is updated
+    /// with what the script changed on `self`.
+    pub fn call(
+        &mut self,
+        entity: Entity,
+        handler: &Handler,
+        state: &mut EntityState,
+    ) -> Result<(), String> {
+        let Some(instance) = self.instances.get(&entity) else {
+            return Ok(());
+        };
+        let (result, ops) = {
+            let env: Table = self
+                .lua
+                .registry_value(&instance.env)
+                .map_err(|e| e.to_string())?;
+            let Some(function) = env
+                .raw_get::<_, Option<Function>>(handler.function_name())
+                .map_err(|e| e.to_string())?
+            else {
+                return Ok(());
+            };
+            let this = state_to_table(&self.lua, state).map_err(|e| e.to_string())?;
+            self.reset_budget();
+            let result = match handler {
+                Handler::Start => function.call::<_, ()>(this.clone()),
+                Handler::Update(dt) => function.call::<_, ()>((this.clone(), *dt)),
+                Handler::Touch(id) | Handler::Click(id) | Handler::UserJoin(id) => {
+                    function.call::<_, ()>((this.clone(), *id))
+                }
+                Handler::Timer(name) => function.call::<_, ()>((this.clone(), name.clone())),
+            };
+            let result = result.and_then(|_| table_to_state(&this, state));
+            (result.map_err(|e| e.to_string()), take_ops(&self.lua))
+        };
+        self.apply_ops(entity, ops);
+        result
+    }
+
+    fn reset_budget(&self) {
+        if let Some(mut budget) = self.lua.app_data_mut::<Budget>() {
+            budget.0 = INSTRUCTION_BUDGET;
+        }
+    }
+
+    fn apply_ops(&mut self, entity: Entity, ops: Vec<Op>) {
+        let Some(instance) = self.instances.get_mut(&entity) else {
+            return;
+        };
+        for op in ops {
+            match op {
+                Op::SetTimer {
+                    name,
+                    seconds,
+                    repeating,
+                } => {
+                    instance.timers.retain(|t| t.name != name);
+                    let mode = if repeating {
+                        TimerMode::Repeating
+                    } else {
+                        TimerMode::Once
+                    };
+                    instance.timers.push(ScriptTimer {
+                        name,
+                        timer: Timer::from_seconds(
+                            seconds.clamp(MIN_TIMER_SECONDS, MAX_TIMER_SECONDS),
+                            mode,
+                        ),
+                    });
+                }
+                Op::CancelTimer(name) => instance.timers.retain(|t| t.name != name),
+            }
+        }
+    }
+}
+
+/// Reads go to `table`, writes fail and its metatable is hidden.
+fn read_only<'lua>(lua: &'lua Lua, table: Table<'lua>) -> mlua::Result<Table<'lua>> {
+    let proxy = lua.create_table()?;
+    let meta = lua.create_table()?;
+    meta.set("__index", table)?;
+    meta.set(
+        "__newindex",
+        lua.create_function(|_, _: mlua::MultiValue| -> mlua::Result<()> {
+            Err(mlua::Error::runtime("shared libraries are read only"))
+        })?,
+    )?;
+    meta.set(
+        "__pairs",
+        lua.create_function(|lua, proxy: Table| {
+            let table: Table = match proxy.get_metatable() {
+                Some(meta) => meta.raw_get("__index")?,
+                None => proxy,
+            };
+            let next: Function = lua.globals().get("next")?;
+            Ok((next, table, Value::Nil))
+        })?,
+    )?;
+    meta.set("__metatable", false)?;
+    proxy.set_metatable(Some(meta));
+    Ok(proxy)
+}
+
+fn take_ops(lua: &Lua) -> Vec<Op> {
+    lua.app_data_mut::<Ops>()
+        .map(|mut ops| std::mem::take(&mut ops.0))
+        .unwrap_or_default()
+}
+
+/// `path` inside `root`, None when it would leave it, also through links.
+fn sandboxed_path(root: &Path, path: &str) -> Option<PathBuf> {
+    let path = Path::new(path);
+    if !path.components().all(|c| matches!(c, Component::Normal(_))) {
+        return None;
+    }
+    let path = root.join(path);
+    match (root.canonicalize(), path.canonicalize()) {
+        (Ok(root), Ok(path)) => path.starts_with(root).then_some(path),
+        // Missing, which the read reports.
+        _ => Some(path),
+    }
+}
+
+/// The source, refused when larger than MAX_SCRIPT_BYTES.
+fn read_script(path: &Path) -> Result<String, String> {
+    let too_large = || format!("script is larger than {} bytes", MAX_SCRIPT_BYTES);
+    let file = fs::File::open(path).map_err(|e| e.to_string())?;
+    let len = file.metadata().map_err(|e| e.to_string())?.len();
+    if len > MAX_SCRIPT_BYTES as u64 {
+        return Err(too_large());
+    }
+    // Files that grow, or report no length, are still cut at the limit.
+    let mut data = vec![];
+    file.take(MAX_SCRIPT_BYTES as u64 + 1)
+        .read_to_end(&mut data)
+        .map_err(|e| e.to_string())?;
+    if data.len() > MAX_SCRIPT_BYTES {
+        return Err(too_large());
+    }
+    String::from_utf8(data).map_err(|e| e.to_string())
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// The `lux` global: logging and timers.
+fn lux_api(lua: &Lua) -> mlua::Result<Table<'_>> {
+    let api = lua.create_table()?;
+    api.set(
+        "log",
+        lua.create_function(|_, message: String| {
+            info!("[script] {}", message);
+            Ok(())
+        })?,
+    )?;
+    api.set(
+        "timer",
+        lua.create_function(
+            |lua, (name, seconds, repeating): (String, f32, Option<bool>)| {
+                if seconds.is_nan() {
+                    return Err(mlua::Error::runtime("timer seconds must be a number"));
+                }
+                if let Some(mut ops) = lua.app_data_mut::<Ops>() {
+                    ops.0.push(Op::SetTimer {
+                        name,
+                        seconds,
+                        repeating: repeating.unwrap_or(false),
+                    });
+                }
+                Ok(())
+            },
+        )?,
+    )?;
+    api.set(
+        "cancel_timer",
+        lua.create_function(|lua, name: String| {
+            if let Some(mut ops) = lua.app_data_mut::<Ops>() {
+                ops.0.push(Op::CancelTimer(name));
+            }
+            Ok(())
+        })?,
+    )?;
+    Ok(api)
+}
+
+fn state_to_table<'lua>(lua: &'lua Lua, state: &EntityState) -> mlua::Result<Table<'lua>> {
+    let t = &state.transform;
+    let this = lua.create_table()?;
+    this.set("id", state.id)?;
+    this.set("position", vec_to_table(lua, &t.translation.to_array())?)?;
+    this.set("rotation", vec_to_table(lua, &t.rotation.to_array())?)?;
+    this.set("scale", vec_to_table(lua, &t.scale.to_array())?)?;
+    this.set("name", state.name.clone())?;
+    this.set("visible", state.visible)?;
+    this.set("components", components_to_table(lua, &state.components)?)?;
+    Ok(this)
+}
+
+fn table_to_state(this: &Table, state: &mut EntityState) -> mlua::Result<()> {
+    let position: [f32; 3] = table_to_vec(&this.get("position")?)?;
+    let rotation: [f32; 4] = table_to_vec(&this.get("rotation")?)?;
+    let scale: [f32; 3] = table_to_vec(&this.get("scale")?)?;
+    state.transform = Transform {
+        translation: Vec3::from_array(position),
+        rotation: Quat::from_array(rotation).normalize(),
+        scale: Vec3::from_array(scale),
+    };
+    state.name = this.get("name")?;
+    state.visible = this.get("visible")?;
+    table_to_components(&this.get("components")?, &mut state.components)
+}
+
+/// Custom components as `self.components.Door.open`.
+fn components_to_table<'lua>(
+    lua: &'lua Lua,
+    components: &CustomComponents,
+) -> mlua::Result<Table<'lua>> {
+    let table = lua.create_table()?;
+    for component in &components.components {
+        let fields = lua.create_table()?;
+        for field in &component.fields {
+            match &field.value {
+                CustomValue::Bool(v) => fields.set(field.name.as_str(), *v)?,
+                CustomValue::Int(v) => fields.set(field.name.as_str(), *v)?,
+                CustomValue::Float(v) => fields.set(field.name.as_str(), *v)?,
+                CustomValue::Text(v) => fields.set(field.name.as_str(), v.as_str())?,
+                CustomValue::Vec3(v) => {
+                    fields.set(field.name.as_str(), vec_to_table(lua, &v.to_array())?)?
+                }
+            }
+        }
+        table.set(component.name.as_str(), fields)?;
+    }
+    Ok(table)
+}
+
+/// Scripts can change the fields, but not add new ones or change their type.
+fn table_to_components(table: &Table, components: &mut CustomComponents) -> mlua::Result<()> {
+    for component in components.components.iter_mut() {
+        let Some(fields) = table.get::<_, Option<Table>>(component.name.as_str())? else {
+            continue;
+        };
+        for field in component.fields.iter_mut() {
+            let name = field.name.as_str();
+            field.value = match &field.value {
+                CustomValue::Bool(_) => CustomValue::Bool(fields.get(name)?),
+                CustomValue::Int(_) => CustomValue::Int(fields.get(name)?),
+                CustomValue::Float(_) => CustomValue::Float(fields.get(name)?),
+                CustomValue::Text(_) => CustomValue::Text(fields.get(name)?),
+                CustomValue::Vec3(_) => {
+                    CustomValue::Vec3(Vec3::from_array(table_to_vec(&fields.get(name)?)?))
+                }
+            };
+        }
+    }
+    Ok(())
+}
+
+const AXES: [&str; 4] = ["x", "y", "z", "w"];
+
+fn vec_to_table<'lua>(lua: &'lua Lua, values: &[f32]) -> mlua::Result<Table<'lua>> {
+    let table = lua.create_table()?;
+    for (axis, value) in AXES.iter().zip(values) {
+        table.set(*axis, *value)?;
+    }
+    Ok(table)
+}
+
+fn table_to_vec<const N: usize>(table: &Table) -> mlua::Result<[f32; N]> {
+    let mut values = [0.0; N];
+    for (axis, value) in AXES.iter().zip(values.iter_mut()) {
+        *value = table.get(*axis)?;
+    }
+    Ok(values)
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_start_moves_entity() {
+        let (mut runtime, entity, _file) = load(
+            r#"
+            function on_start(self)
+                self.position.y = self.position.y + 1
+                self.name = "moved"
+            end
+            "#,
+        );
+        let mut state = EntityState::default();
+        runtime.call(entity, &Handler::Start, &mut state).unwrap();
+
+        assert_eq!(state.transform.translation, Vec3::Y);
+        assert_eq!(state.name.as_deref(), Some("moved"));
+    }
+
+    #[test]
+    fn test_custom_components() {
+        let (mut runtime, entity, _file) = load(
+            r#"
+            function on_click(self, peer)
+                self.components.Door.open = not self.components.Door.open
+                self.components.Door.speed = self.components.Door.speed * 2
+            end
+            "#,
+        );
+        let mut state = EntityState {
+            components: CustomComponents {
+                components: vec![lux_components::CustomComponent {
+                    name: "Door".to_owned(),
+                    fields: vec![
+                        lux_components::CustomField {
+                            name: "open".to_owned(),
+                            value: CustomValue::Bool(false),
+                        },
+                        lux_components::CustomField {
+                            name: "speed".to_owned(),
+                            value: CustomValue::Float(1.5),
+                        },
+                    ],
+                }],
+            },
+            ..default()
+        };
+        runtime
+            .call(entity, &Handler::Click(1), &mut state)
+            .unwrap();
+
+        assert_eq!(
+            state.components.get("Door", "open"),
+            Some(&CustomValue::Bool(true))
+        );
+        assert_eq!(
+            state.components.get("Door", "speed"),
+            Some(&CustomValue::Float(3.0))
+        );
+    }
+
+    #[test]
+    fn test_missing_handler_is_ignored() {
+        let (mut runtime, entity, _file) = load("x = 1");
+        let mut state = EntityState::default();
+        runtime
+            .call(entity, &Handler::Click(3), &mut state)
+            .unwrap();
+        assert_eq!(state, EntityState::default());
+    }
+
+    #[test]
+    fn test_scripts_have_own_globals() {
+        let (mut runtime, a, _fa) =
+            load("count = 1 function on_start(self) self.name = tostring(count) end");
+        let b = Entity::from_raw(99);
+        let file = TempScript::new("count = 2");
+        file.load(&mut runtime, b).unwrap();
+        let mut state = EntityState::default();
+        runtime.call(a, &Handler::Start, &mut state).unwrap();
+
+        assert_eq!(state.name.as_deref(), Some("1"));
+    }
+
+    #[test]
+    fn test_sandbox_has_no_system_access() {
+        let (mut runtime, entity, _file) = load(
+            r#"
+            function on_start(self)
+                self.visible = io == nil and os == nil and require == nil and load == nil
+            end
+            "#,
+        );
+        let mut state = EntityState::default();
+        runtime.call(entity, &Handler::Start, &mut state).unwrap();
+        assert!(state.visible);
+    }
+
+    #[test]
+    fn test_scripts_can_not_change_shared_libraries() {
+        let (mut runtime, a, _fa) = load(
+            r#"
+            function on_start(self)
+                local failed = not pcall(function() string.upper = nil end)
+                    and not pcall(function() lux.log = nil end)
+                    and not pcall(function() getmetatable("").__index.upper = nil end)
+                rawset(math, "pi", 3)
+                _G.table = nil
+                self.visible = failed and math.pi == 3
+            end
+            "#,
+        );
+        let b = Entity::from_raw(99);
+        let file = TempScript::new(
+            r#"
+            function on_start(self)
+                local count = 0
+                for _ in pairs(math) do count = count + 1 end
+                self.visible = string.upper("a") == "A" and math.pi > 3.14
+                    and lux.log ~= nil and table.insert ~= nil and count > 10
+            end
+            "#,
+        );
+        file.load(&mut runtime, b).unwrap();
+        let mut state = EntityState::default();
+        runtime.call(a, &Handler::Start, &mut state).unwrap();
+        assert!(state.visible);
+
+        let mut state = EntityState::default();
+        runtime.call(b, &Handler::Start, &mut state).unwrap();
+        assert!(state.visible);
+    }
+
+    #[test]
+    fn test_runaway_script_is_stopped() {
+        let (mut runtime, entity, _file) = load("function on_start(self) while true do end end");
+        let mut state = EntityState::default();
+        let result = runtime.call(entity, &Handler::Start, &mut state);
+        assert!(result.unwrap_err().contains("too long"));
+
+        let result = runtime.call(entity, &Handler::Start, &mut state);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_timers_fire() {
+        let (mut runtime, entity, _file) = load(
+            r#"
+            function on_start(self)
+                lux.timer("once", 1.0)
+                lux.timer("every", 0.5, true)
+            end
+            "#,
+        );
+        let mut state = EntityState::default();
+        runtime.call(entity, &Handler::Start, &mut state).unwrap();
+
+        let fired = runtime.tick_timers(Duration::from_secs_f32(0.6));
+        assert_eq!(fired, vec![(entity, "every".to_string())]);
+        let mut fired = runtime.tick_timers(Duration::from_secs_f32(0.6));
+        fired.sort_by(|a, b| a.1.cmp(&b.1));
+        assert_eq!(
+            fired,
+            vec![(entity, "every".to_string()), (entity, "once".to_string())]
+        );
+        let fired = runtime.tick_timers(Duration::from_secs_f32(1.0));
+        assert_eq!(fired.len(), 2);
+        assert!(fired.iter().all(|(_, name)| name == "every"));
+    }
+
+    #[test]
+    fn test_zero_timers_do_not_flood() {
+        let (mut runtime, entity, _file) = load(
+            r#"
+            function on_start(self)
+                lux.timer("zero", 0, true)
+                lux.timer("tiny", 1e-9, true)
+                lux.timer("once", 0)
+            end
+            "#,
+        );
+        let mut state = EntityState::default();
+        runtime.call(entity, &Handler::Start, &mut state).unwrap();
+
+        let fired = runtime.tick_timers(Duration::from_secs(1));
+        let count = |name: &str| fired.iter().filter(|(_, n)| n == name).count();
+        assert_eq!(count("zero"), MAX_TIMER_FIRES as usize);
+        assert_eq!(count("tiny"), MAX_TIMER_FIRES as usize);
+        assert_eq!(count("once"), 1);
+        assert!(runtime
+            .tick_timers(Duration::from_secs_f32(MIN_TIMER_SECONDS / 2.0))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_hot_reload() {
+        let (mut runtime, entity, file) = load("function on_start(self) self.name = 'old' end");
+        assert!(runtime.reload_changed().is_empty());
+
+        file.write("function on_start(self) self.name = 'new' end");
+        std::fs::File::options()
+            .write(true)
+            .open(&file.0)
+            .unwrap()
+            .set_modified(SystemTime::now() + Duration::from_secs(1))
+            .unwrap();
+        let reloaded = runtime.reload_changed();
+        assert_eq!(reloaded.len(), 1);
+
+        let mut state = EntityState::default();
+        runtime.call(entity, &Handler::Start, &mut state).unwrap();
+        assert_eq!(state.name.as_deref(), Some("new"));
+    }
+
+    #[test]
+    fn test_scripts_stay_in_the_world_folder() {
+        let mut runtime = ScriptRuntime::new().unwrap();
+        let entity = Entity::from_raw(1);
+        let root = std::env::temp_dir().join(format!("lux_world_{}", bevy_sync::Uuid::new_v4()));
+        fs::create_dir_all(root.join("scripts")).unwrap();
+        fs::write(root.join("scripts/door.lua"), "x = 1").unwrap();
+        let outside = TempScript::new("x = 2");
+
+        assert!(runtime.load(entity, &root, "scripts/door.lua").is_ok());
+        for path in [
+            format!("../{}", outside.name()),
+            format!("scripts/../../{}", outside.name()),
+            outside.0.to_string_lossy().to_string(),
+            "/dev/zero".to_owned(),
+        ] {
+            let error = runtime.load(entity, &root, &path).unwrap_err();
+            assert!(error.contains("not in the world folder"), "{}", error);
+        }
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(&outside.0, root.join("link.lua")).unwrap();
+            assert!(runtime.load(entity, &root, "link.lua").is_err());
+        }
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_large_scripts_are_refused() {
+        let mut runtime = ScriptRuntime::new().unwrap();
+        let file = TempScript::new(&"-".repeat(MAX_SCRIPT_BYTES + 1));
+        let error = file.load(&mut runtime, Entity::from_raw(1)).unwrap_err();
+        assert!(error.contains("larger"), "{}", error);
+    }
+
+    /// Script file removed when the test ends.
+    pub(crate) struct TempScript(pub PathBuf);
+
+    impl TempScript {
+        pub fn new(source: &str) -> Self {
+            let name = format!("lux_script_{}.lua", bevy_sync::Uuid::new_v4());
+            let file = Self(std::env::temp_dir().join(name));
+            file.write(source);
+            file
+        }
+
+        pub fn write(&self, source: &str) {
+            std::fs::write(&self.0, source).unwrap();
+        }
+
+        pub fn name(&self) -> String {
+            self.0.file_name().unwrap().to_string_lossy().to_string()
+        }
+
+        fn load(&self, runtime: &mut ScriptRuntime, entity: Entity) -> Result<(), String> {
+            runtime.load(entity, &std::env::temp_dir(), &self.name())
+        }
+    }
+
+    impl Drop for TempScript {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    fn load(source: &str) -> (ScriptRuntime, Entity, TempScript) {
+        let mut runtime = ScriptRuntime::new().unwrap();
+        let entity = Entity::from_raw(1);
+        let file = TempScript::new(source);
+        file.load(&mut runtime, entity).unwrap();
+        (runtime, entity, file)
+    }
+}
@@ -0,0 +1,74 @@
+use bevy::{gltf::GltfExtras, prelude::*};
+use bevy_sync::SyncComponent;
+use lux_components::{LocalPeer, PickResult};
+use serde_json::Value;
+
+/// Script attached to an entity, the path is relative to the world file.
+/// Synched so that every peer knows, but only the host runs it.
+#[derive(Component, Default, Reflect, Debug, Clone, PartialEq)]
+#[reflect(Component)]
+pub struct Script {
+    pub path: String,
+}
+
+/// Clicks on a scripted entity, counted by any peer and sent to the host.
+#[derive(Component, Default, Reflect, Debug, Clone, PartialEq)]
+#[reflect(Component)]
+pub struct ScriptClicks {
+    pub count: u32,
+    pub peer: u64,
+}
+
+pub(crate) fn init(app: &mut App) {
+    app.sync_component::<Script>();
+    app.sync_component::<ScriptClicks>();
+    app.add_systems(Update, (scripts_from_gltf, count_clicks));
+}
+
+fn scripts_from_gltf(
+    mut commands: Commands,
+    nodes: Query<(Entity, &GltfExtras), Added<GltfExtras>>,
+) {
+    for (entity, extras) in nodes.iter() {
+        let Ok(value) = serde_json::from_str::<Value>(&extras.value) else {
+            continue;
+        };
+        if let Some(path) = value.get("lux_script").and_then(Value::as_str) {
+            debug!("Script {} on {:?}", path, entity);
+            commands.entity(entity).insert((
+                Script {
+                    path: path.to_owned(),
+                },
+                ScriptClicks::default(),
+            ));
+        }
+    }
+}
+
+/// Picks that are not selecting anything are interactions.
+fn count_clicks(
+    peer: Res<LocalPeer>,
+    mut picks: EventReader<PickResult>,
+    parents: Query<&Parent>,
+    mut clicks: Query<&mut ScriptClicks>,
+) {
+    for pick in picks.read() {
+        if pick.select.is_some() {
+            continue;
+        }
+        let Some(mut entity) = pick.entity else {
+            continue;
+        };
+        loop {
+            if let Ok(mut clicks) = clicks.get_mut(entity) {
+                clicks.count = clicks.count.wrapping_add(1);
+                clicks.peer = peer.id;
+                break;
+            }
+            match parents.get(entity) {
+                Ok(parent) => entity = parent.get(),
+                Err(_) => break,
+            }
+        }
+    }
+}
@@ -2,20 +2,35 @@ use std::time::Duration;
 
 use bevy::{app::AppExit, prelude::*, time::common_conditions::on_timer};
 use bevy_sync::Uuid;
-use lux_cli::{Args, Command};
+use lux_cli::{Args, Command, DEFAULT_PORT};
 use lux_world::{import_audio, init};
 
 fn main() {
     let mut app = App::new();
     app.add_plugins(DefaultPlugins);
-    app.add_plugins(bevy_editor_pls::EditorPlugin::default());
     app.insert_resource(Args {
         xr_enabled: false,
+        name: None,
+        plugins_dir: None,
+        interest_radius: None,
+        sim_latency: None,
+        sim_jitter: None,
+        sim_loss: None,
+        sim_bandwidth: None,
+        record: None,
+        log_level: None,
+        log_filter: None,
+        log_file: None,
+        log_json: false,
+        height: None,
         command: Some(Command::Host {
             world_file: "cube.glb".to_string(),
             headless: false,
             ip: None,
+            port: DEFAULT_PORT,
             avatar_file: None,
+            metrics: None,
+            metrics_ip: None,
         }),
     });
     init(&mut app);
@@ -0,0 +1,189 @@
+use std::path::Path;
+
+use bevy::{asset::io::file::FileAssetReader, gltf::GltfExtras, prelude::*};
+use lux_cli::{Args, Command};
+use lux_components::{
+    CustomComponentDef, CustomComponentDefs, CustomComponents, CustomValue, FieldType,
+};
+use serde_json::Value;
+
+/// Declarations of the custom components, next to the world file.
+const COMPONENTS_FILE: &str = "components.json";
+
+pub(crate) fn init(app: &mut App) {
+    app.add_systems(Startup, load_component_defs.run_if(resource_exists::<Args>));
+    app.add_systems(Update, custom_components_from_gltf);
+}
+
+/// Only the host reads the declarations, peers get the values through sync.
+fn load_component_defs(args: Res<Args>, mut defs: ResMut<CustomComponentDefs>) {
+    let Some(Command::Host { world_file, .. }) = &args.command else {
+        return;
+    };
+    let dir = Path::new(world_file).parent().unwrap_or(Path::new(""));
+    let path = FileAssetReader::get_base_path()
+        .join("assets")
+        .join(dir)
+        .join(COMPONENTS_FILE);
+    let Ok(text) = std::fs::read_to_string(&path) else {
+        return;
+    };
+    match parse_defs(&text) {
+        Ok(parsed) => {
+            info!("Loaded {} custom components", parsed.len());
+            defs.0 = parsed;
+        }
+        Err(e) => error!("{}: {}", path.display(), e),
+    }
+}
+
+/// `{"components": {"Door": {"open": "bool", "speed": "float"}}}`
+fn parse_defs(text: &str) -> Result<Vec<CustomComponentDef>, String> {
+    let value: Value = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    let Some(components) = value.get("components").and_then(Value::as_object) else {
+        return Err("missing \"components\"".to_owned());
+    };
+    let mut defs = vec![];
+    for (name, fields) in components {
+        let Some(fields) = fields.as_object() else {
+            return Err(format!("fields of {} are not an object", name));
+        };
+        let mut def = CustomComponentDef {
+            name: name.clone(),
+            fields: vec![],
+        };
+        for (field, ty) in fields {
+            let Some(ty) = ty.as_str().and_then(FieldType::parse) else {
+                return Err(format!("{}.{} has an unknown type {}", name, field, ty));
+            };
+            def.fields.push((field.clone(), ty));
+        }
+        defs.push(def);
+    }
+    Ok(defs)
+}
+
+fn value_from_json(ty: FieldType, value: &Value) -> Option<CustomValue> {
+    match ty {
+        FieldType::Bool => value.as_bool().map(CustomValue::Bool),
+        FieldType::Int => value.as_i64().map(CustomValue::Int),
+        FieldType::Float => value.as_f64().map(|v| CustomValue::Float(v as f32)),
+        FieldType::Text => value.as_str().map(|v| CustomValue::Text(v.to_owned())),
+        FieldType::Vec3 => {
+            let array = value.as_array()?;
+            let mut v = [0.0; 3];
+            for (i, item) in array.iter().enumerate().take(3) {
+                v[i] = item.as_f64()? as f32;
+            }
+            (array.len() == 3).then(|| CustomValue::Vec3(Vec3::from_array(v)))
+        }
+    }
+}
+
+/// Nodes attach components with `{"lux_components": {"Door": {"open": true}}}`,
+/// fields that are not given keep their default.
+fn components_from_extras(defs: &CustomComponentDefs, extras: &Value) -> Option<CustomComponents> {
+    let attached = match extras.get("lux_components")? {
+        Value::String(text) => serde_json::from_str::<Value>(text).ok()?,
+        value => value.clone(),
+    };
+    let mut components = CustomComponents::default();
+    for (name, values) in attached.as_object()? {
+        let Some(def) = defs.get(name) else {
+            warn!("Custom component {} is not declared", name);
+            continue;
+        };
+        let mut component = def.instance();
+        for field in component.fields.iter_mut() {
+            let Some(value) = values.get(&field.name) else {
+                continue;
+            };
+            match value_from_json(field.value.field_type(), value) {
+                Some(value) => field.value = value,
+                None => warn!("{}.{} has a wrong value {}", name, field.name, value),
+            }
+        }
+        components.components.push(component);
+    }
+    Some(components)
+}
+
+fn custom_components_from_gltf(
+    mut commands: Commands,
+    defs: Res<CustomComponentDefs>,
+    nodes: Query<(Entity, &GltfExtras), Added<GltfExtras>>,
+) {
+    for (entity, extras) in nodes.iter() {
+        let Ok(value) = serde_json::from_str::<Value>(&extras.value) else {
+            continue;
+        };
+        if let Some(components) = components_from_extras(&defs, &value) {
+            commands.entity(entity).insert(components);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const DEFS: &str =
+        r#"{"components": {"Door": {"open": "bool", "speed": "float", "hinge": "vec3"}}}"#;
+
+    #[test]
+    fn test_parse_defs() {
+        let defs = parse_defs(DEFS).unwrap();
+        assert_eq!(defs.len(), 1);
+        assert_eq!(defs[0].name, "Door");
+        assert!(defs[0]
+            .fields
+            .contains(&("speed".to_owned(), FieldType::Float)));
+    }
+
+    #[test]
+    fn test_unknown_field_type() {
+        assert!(parse_defs(r#"{"components": {"Door": {"open": "maybe"}}}"#).is_err());
+    }
+
+    #[test]
+    fn test_extras_override_defaults() {
+        let defs = CustomComponentDefs(parse_defs(DEFS).unwrap());
+        let extras = serde_json::json!({
+            "lux_components": {"Door": {"open": true, "hinge": [1, 2, 3]}, "Lamp": {}}
+        });
+        let components = components_from_extras(&defs, &extras).unwrap();
+        assert_eq!(components.components.len(), 1);
+        assert_eq!(
+            components.get("Door", "open"),
+            Some(&CustomValue::Bool(true))
+        );
+        assert_eq!(
+            components.get("Door", "speed"),
+            Some(&CustomValue::Float(0.0))
+        );
+        assert_eq!(
+            components.get("Door", "hinge"),
+            Some(&CustomValue::Vec3(Vec3::new(1.0, 2.0, 3.0)))
+        );
+    }
+
+    #[test]
+    fn test_extras_as_text() {
+        let defs = CustomComponentDefs(parse_defs(DEFS).unwrap());
+        let extras = serde_json::json!({"lux_components": "{\"Door\": {\"speed\": 2}}"});
+        let components = components_from_extras(&defs, &extras).unwrap();
+        assert_eq!(
+            components.get("Door", "speed"),
+            Some(&CustomValue::Float(2.0))
+        );
+    }
+
+    #[test]
+    fn test_wrong_value_keeps_default() {
+        assert_eq!(value_from_json(FieldType::Bool, &Value::from(1)), None);
+        assert_eq!(
+            value_from_json(FieldType::Vec3, &serde_json::json!([1, 2])),
+            None
+        );
+    }
+}
@@ -1,19 +1,7 @@
 use bevy::prelude::*;
 use bevy_sync::SyncMark;
-use bevy_sync::Uuid;
-
-trait AddByUuid<A: Asset> {
-    fn addu(&mut self, asset: A) -> Handle<A>;
-}
-impl<A: Asset> AddByUuid<A> for Assets<A> {
-    fn addu(&mut self, asset: A) -> Handle<A> {
-        let id = AssetId::Uuid {
-            uuid: Uuid::new_v4(),
-        };
-        self.insert(id, asset);
-        Handle::<A>::Weak(id)
-    }
-}
+use lux_components::AddByUuid;
+use lux_physics::AutoCollider;
 
 pub fn spawn_empty_world(
     mut meshes: ResMut<Assets<Mesh>>,
@@ -27,6 +15,7 @@ pub fn spawn_empty_world(
             ..default()
         },
         SyncMark,
+        AutoCollider,
         Name::new("Ground"),
     ));
     commands.spawn((
@@ -37,6 +26,7 @@ pub fn spawn_empty_world(
             ..default()
         },
         SyncMark,
+        AutoCollider,
         Name::new("Cube"),
     ));
     commands.spawn((
@@ -1,11 +1,18 @@
 use bevy::{prelude::*, scene::SceneInstance};
 use bevy_sync::{SyncEntity, SyncMark, Uuid};
 use bevy_vr_controller::player::PlayerSettings;
-use lux_avatar_generic::AvatarGeneric;
-use lux_components::LocalUser;
+use lux_avatar_generic::{
+    calibration::{AvatarFile, UserMeasures},
+    AvatarGeneric,
+};
+use lux_components::{AssetSources, LocalUser, User};
+use lux_physics::AutoCollider;
+
+use crate::spawning::{PendingSpawn, Respawnable, DEFAULT_SPAWN};
 
 pub(crate) fn init(app: &mut App) {
     app.add_systems(Update, (propagate, cleanup).chain());
+    app.init_resource::<AssetSources>();
     app.add_systems(Update, (handle_mesh, cleanup_mesh).chain());
     app.add_systems(Update, (handle_material, cleanup_material).chain());
     app.add_systems(Update, (handle_audio, cleanup_audio).chain());
@@ -25,17 +32,27 @@ pub fn import_gltf(file_name: &str, commands: &mut Commands, assets: &AssetServe
         LoadedSceneItem,
         LoadedSceneItemHandleMesh,
         LoadedSceneItemHandleMaterial,
+        AutoCollider,
     ));
 }
 
-pub fn import_avatar(file_name: &str, commands: &mut Commands, assets: &AssetServer) {
+pub fn import_avatar(
+    file_name: &str,
+    display_name: Option<&str>,
+    height: Option<f32>,
+    commands: &mut Commands,
+    assets: &AssetServer,
+) {
     let is_vrm = file_name.ends_with(".vrm");
-    let name = strip_file_name(file_name);
+    let name = display_name
+        .map(str::to_owned)
+        .unwrap_or_else(|| strip_file_name(file_name));
     if is_vrm {
         let avatar_id = PlayerSettings {
             animations: None,
-            spawn: Vec3::new(0.0, 3.0, 0.0),
-            void_level: Some(-20.0),
+            spawn: DEFAULT_SPAWN,
+            // Falling is handled by the world spawn points instead.
+            void_level: None,
             vrm: Some(assets.load(file_name.to_owned())),
             ..default()
         }
@@ -46,21 +63,35 @@ pub fn import_avatar(file_name: &str, commands: &mut Commands, assets: &AssetSer
             LoadedSceneItem,
             LoadedSceneItemHandleMesh,
             LoadedSceneItemHandleMaterial,
+            User,
+            Respawnable,
+            PendingSpawn::default(),
         ));
     } else {
         let scene = assets.load(file_name.to_owned() + "#Scene0");
         debug!("Loading SceneBundle: {:?}", scene);
-        commands.spawn((
-            Name::new(name),
-            SceneBundle {
-                scene,
-                ..Default::default()
-            },
-            LoadAvatar,
-            LoadedSceneItem,
-            LoadedSceneItemHandleMesh,
-            LoadedSceneItemHandleMaterial,
-        ));
+        let avatar_id = commands
+            .spawn((
+                Name::new(name),
+                SceneBundle {
+                    scene,
+                    ..Default::default()
+                },
+                LoadAvatar,
+                AvatarFile(file_name.to_owned()),
+                User,
+                Respawnable,
+                PendingSpawn::default(),
+                LoadedSceneItem,
+                LoadedSceneItemHandleMesh,
+                LoadedSceneItemHandleMaterial,
+            ))
+            .id();
+        if let Some(height) = height {
+            commands
+                .entity(avatar_id)
+                .insert(UserMeasures::from_height(height));
+        }
     }
 }
 
@@ -113,8 +144,11 @@ fn after_spawn_load_avatar(
     }
 }
 
-fn propagate(query: Query<(Entity, &Children), With<LoadedSceneItem>>, mut commands: Commands) {
-    for (e, childs) in query.iter() {
+fn propagate(
+    query: Query<(Entity, &Children, Has<AutoCollider>), With<LoadedSceneItem>>,
+    mut commands: Commands,
+) {
+    for (e, childs, auto_collider) in query.iter() {
         debug!("Propagating entity {:?}", e);
         commands
             .get_entity(e)
@@ -131,6 +165,9 @@ fn propagate(query: Query<(Entity, &Children), With<LoadedSceneItem>>, mut comma
                 .insert(LoadedSceneItemHandleMesh)
                 .insert(LoadedSceneItemHandleMaterial)
                 .insert(SyncMark);
+            if auto_collider {
+                commands.get_entity(*c).unwrap().insert(AutoCollider);
+            }
         }
     }
 }
@@ -194,6 +231,7 @@ fn handle_mesh(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut images: ResMut<Assets<Image>>,
+    mut sources: ResMut<AssetSources>,
     query: Query<(Entity, &Handle<Mesh>), Added<LoadedSceneItemHandleMesh>>,
 ) {
     for (e, h) in query.iter() {
@@ -213,6 +251,9 @@ fn handle_mesh(
             }
         }
         meshes.insert(id, asset);
+        if let Some(path) = h.path() {
+            sources.insert(id, path);
+        }
         debug!("Reassigned mesh to uuid {:?}", id);
         commands
             .get_entity(e)
@@ -237,6 +278,7 @@ fn handle_material(
     mut commands: Commands,
     mut materials: ResMut<Assets<StandardMaterial>>,
     mut images: ResMut<Assets<Image>>,
+    mut sources: ResMut<AssetSources>,
     query: Query<(Entity, &Handle<StandardMaterial>), Added<LoadedSceneItemHandleMaterial>>,
 ) {
     for (e, h) in query.iter() {
@@ -248,6 +290,9 @@ fn handle_material(
         };
         handle_images(images.as_mut(), &mut asset);
         materials.insert(id, asset);
+        if let Some(path) = h.path() {
+            sources.insert(id, path);
+        }
         debug!("Reassigned material to uuid {:?}", id);
         commands
             .get_entity(e)
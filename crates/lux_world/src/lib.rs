@@ -1,5 +1,8 @@
+mod custom_components;
 mod empty_world;
 mod importer;
+mod portals;
+mod spawning;
 
 use bevy::prelude::*;
 use empty_world::spawn_empty_world;
@@ -7,6 +10,7 @@ use lux_cli::{Args, Command};
 
 pub use importer::import_audio;
 pub use importer::import_gltf;
+pub use portals::{next_args, PortalTransition};
 
 pub fn init(app: &mut App) {
     app.add_systems(
@@ -19,6 +23,9 @@ pub fn init(app: &mut App) {
     );
 
     importer::init(app);
+    custom_components::init(app);
+    spawning::init(app);
+    portals::init(app);
 }
 
 fn load_world_from_args(
@@ -33,11 +40,16 @@ fn load_world_from_args(
             world_file,
             headless: _,
             ip: _,
+            port: _,
             avatar_file: _,
+            metrics: _,
+            metrics_ip: _,
         }) => importer::import_gltf(world_file, &mut commands, &assets),
         Some(Command::Join {
             ip: _,
+            port: _,
             avatar_file: _,
+            web_port: _,
         }) => (),
         _ => spawn_empty_world(meshes, materials, commands),
     }
@@ -49,18 +61,32 @@ fn load_avatar_from_args(args: Res<Args>, assets: Res<AssetServer>, mut commands
             world_file: _,
             headless,
             ip: _,
+            port: _,
             avatar_file,
+            metrics: _,
+            metrics_ip: _,
         }) => {
             if *headless {
                 return;
             }
             avatar_file
         }
-        Some(Command::Join { ip: _, avatar_file }) => avatar_file,
+        Some(Command::Join {
+            ip: _,
+            port: _,
+            avatar_file,
+            web_port: _,
+        }) => avatar_file,
         _ => &None,
     }
     .to_owned();
     if let Some(avatar_file) = avatar_file {
-        importer::import_avatar(avatar_file.as_str(), &mut commands, &assets);
+        importer::import_avatar(
+            avatar_file.as_str(),
+            args.name.as_deref(),
+            args.height,
+            &mut commands,
+            &assets,
+        );
     }
 }
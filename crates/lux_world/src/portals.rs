@@ -0,0 +1,396 @@
+use std::net::{IpAddr, SocketAddr};
+
+use bevy::{gltf::GltfExtras, prelude::*, render::primitives::Aabb};
+use lux_cli::{Args, Command, DEFAULT_PORT, SESSION_PORTS};
+use lux_components::Portal;
+use serde_json::Value;
+
+use crate::spawning::Respawnable;
+
+/// Reach of portals that have no bounds, like glTF empties.
+const PORTAL_RADIUS: f32 = 1.0;
+
+/// The local user entered a portal, these are the arguments to continue with.
+#[derive(Event, Debug, Clone, PartialEq)]
+pub struct PortalTransition {
+    pub args: Args,
+}
+
+pub(crate) fn init(app: &mut App) {
+    app.add_event::<PortalTransition>();
+    app.add_systems(
+        Update,
+        (
+            portals_from_gltf,
+            enter_portals.run_if(resource_exists::<Args>),
+            relaunch,
+        )
+            .chain(),
+    );
+}
+
+fn portals_from_gltf(
+    mut commands: Commands,
+    nodes: Query<(Entity, &GltfExtras), Added<GltfExtras>>,
+) {
+    for (entity, extras) in nodes.iter() {
+        let Ok(value) = serde_json::from_str::<Value>(&extras.value) else {
+            continue;
+        };
+        if let Some(target) = value.get("lux_portal").and_then(Value::as_str) {
+            debug!("Portal {:?} to {}", entity, target);
+            commands.entity(entity).insert(Portal {
+                target: target.to_owned(),
+            });
+        }
+    }
+}
+
+/// Hosts stay, leaving would end the session of everybody joined.
+fn enter_portals(
+    mut leaving: Local<bool>,
+    args: Res<Args>,
+    portals: Query<(Entity, &Portal, &GlobalTransform)>,
+    children: Query<&Children>,
+    bounds: Query<(&Aabb, &GlobalTransform)>,
+    avatars: Query<&GlobalTransform, With<Respawnable>>,
+    mut transitions: EventWriter<PortalTransition>,
+) {
+    if *leaving {
+        return;
+    }
+    for avatar in avatars.iter() {
+        let point = avatar.translation();
+        let Some((_, portal, _)) = portals.iter().find(|(entity, _, gt)| {
+            // Imported meshes have their bounds on the primitives below the node.
+            let parts = std::iter::once(*entity)
+                .chain(children.get(*entity).into_iter().flatten().copied())
+                .filter_map(|e| bounds.get(e).ok());
+            is_inside(point, gt, parts)
+        }) else {
+            continue;
+        };
+        if let Some(Command::Host { .. }) = &args.command {
+            warn!("Hosting, portal to {} is not taken", portal.target);
+            *leaving = true;
+            return;
+        }
+        let Some(args) = next_args(&args, &portal.target) else {
+            warn!("Portal target '{}' is not valid", portal.target);
+            continue;
+        };
+        info!("Entering portal to {}", portal.target);
+        transitions.send(PortalTransition { args });
+        *leaving = true;
+        return;
+    }
+}
+
+/// Inside any of the bounds of the portal, or near it when it has none.
+fn is_inside<'a>(
+    point: Vec3,
+    gt: &GlobalTransform,
+    bounds: impl Iterator<Item = (&'a Aabb, &'a GlobalTransform)>,
+) -> bool {
+    let mut bounded = false;
+    for (aabb, gt) in bounds {
+        bounded = true;
+        let local = gt.affine().inverse().transform_point3(point);
+        let min = Vec3::from(aabb.min());
+        let max = Vec3::from(aabb.max());
+        if local.cmpge(min).all() && local.cmple(max).all() {
+            return true;
+        }
+    }
+    !bounded && gt.translation().distance(point) <= PORTAL_RADIUS
+}
+
+/// Arguments for the session behind the portal, keeping who the user is:
+/// avatar, display name, plugins and XR mode carry over.
+pub fn next_args(current: &Args, target: &str) -> Option<Args> {
+    let avatar_file = current.avatar_file().map(str::to_owned);
+    let command = if let Ok(addr) = target.parse::<SocketAddr>() {
+        Command::Join {
+            ip: addr.ip(),
+            port: addr.port(),
+            avatar_file,
+            web_port: None,
+        }
+    } else if let Ok(ip) = target.parse::<IpAddr>() {
+        Command::Join {
+            ip,
+            port: DEFAULT_PORT,
+            avatar_file,
+            web_port: None,
+        }
+    } else if target.ends_with(".glb") || target.ends_with(".gltf") {
+        // The session being left still holds its ports while the next one
+        // starts, so the next host takes the ones after them.
+        let (ip, port) = match &current.command {
+            Some(Command::Host { ip, port, .. }) => (*ip, port.saturating_add(SESSION_PORTS)),
+            _ => (None, DEFAULT_PORT),
+        };
+        Command::Host {
+            world_file: target.to_owned(),
+            headless: false,
+            ip,
+            port,
+            avatar_file,
+            metrics: None,
+            metrics_ip: None,
+        }
+    } else {
+        return None;
+    };
+    Some(Args {
+        xr_enabled: current.xr_enabled,
+        name: current.name.clone(),
+        plugins_dir: current.plugins_dir.clone(),
+        interest_radius: current.interest_radius,
+        sim_latency: current.sim_latency,
+        sim_jitter: current.sim_jitter,
+        sim_loss: current.sim_loss,
+        sim_bandwidth: current.sim_bandwidth,
+        // Would overwrite the recording of this session.
+        record: None,
+        log_level: current.log_level,
+        log_filter: current.log_filter.clone(),
+        log_file: current.log_file.clone(),
+        log_json: current.log_json,
+        height: current.height,
+        command: Some(command),
+    })
+}
+
+/// Sessions can not be swapped in place, so the app starts again
+/// with the new arguments and this one closes.
+fn relaunch(mut transitions: EventReader<PortalTransition>, mut exit: EventWriter<AppExit>) {
+    let Some(transition) = transitions.read().last() else {
+        return;
+    };
+    let exe = match std::env::current_exe() {
+        Ok(exe) => exe,
+        Err(e) => {
+            error!("Cannot find own executable to go through portal: {}", e);
+            return;
+        }
+    };
+    match std::process::Command::new(exe)
+        .args(transition.args.to_cli_args())
+        .spawn()
+    {
+        Ok(_) => {
+            exit.send(AppExit::Success);
+        }
+        Err(e) => error!("Cannot go through portal: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bevy_sync::SyncMark;
+    use clap::Parser;
+    use std::net::{TcpListener, UdpSocket};
+
+    #[test]
+    fn test_portal_to_host_address() {
+        let current = Args::parse_from([
+            "lux", "--name", "Ada", "join", "10.0.0.1", "--avatar", "me.vrm",
+        ]);
+        let next = next_args(&current, "10.0.0.2:5000").unwrap();
+        let expected = Args::parse_from([
+            "lux", "--name", "Ada", "join", "10.0.0.2", "--port", "5000", "--avatar", "me.vrm",
+        ]);
+        assert_eq!(next, expected);
+    }
+
+    #[test]
+    fn test_portal_to_world_file() {
+        let current = Args::parse_from(["lux", "--xr", "join", "10.0.0.1", "--avatar", "me.vrm"]);
+        let next = next_args(&current, "b.glb").unwrap();
+        let expected = Args::parse_from(["lux", "--xr", "host", "b.glb", "--avatar", "me.vrm"]);
+        assert_eq!(next, expected);
+    }
+
+    #[test]
+    fn test_portal_from_host_to_world_file() {
+        let current = Args::parse_from(["lux", "host", "a.glb", "10.0.0.1"]);
+        let next = next_args(&current, "b.glb").unwrap();
+        let expected = Args::parse_from(["lux", "host", "b.glb", "10.0.0.1", "--port", "4004"]);
+        assert_eq!(next, expected);
+    }
+
+    #[test]
+    fn test_portal_invalid_target() {
+        let current = Args::parse_from(["lux"]);
+        assert_eq!(next_args(&current, "somewhere"), None);
+    }
+
+    #[test]
+    fn test_portal_from_extras() {
+        let mut app = portals(&["join", "127.0.0.1"]);
+        let entity = app
+            .world_mut()
+            .spawn(GltfExtras {
+                value: r#"{"lux_portal": "127.0.0.1:4102"}"#.into(),
+            })
+            .id();
+        app.update();
+
+        let portal = app.world().entity(entity).get::<Portal>().unwrap();
+        assert_eq!(portal.target, "127.0.0.1:4102");
+    }
+
+    #[test]
+    fn test_portal_bounds_on_children() {
+        let mut app = portals(&["join", "127.0.0.1"]);
+        let at = GlobalTransform::from_translation(Vec3::new(5.0, 0.0, 0.0));
+        let portal = app
+            .world_mut()
+            .spawn((
+                Portal {
+                    target: "127.0.0.1:4102".into(),
+                },
+                at,
+            ))
+            .id();
+        app.world_mut()
+            .spawn((at, Aabb::from_min_max(-Vec3::ONE * 2.0, Vec3::ONE * 2.0)))
+            .set_parent(portal);
+        let avatar = app
+            .world_mut()
+            .spawn((Respawnable, GlobalTransform::default()))
+            .id();
+        app.update();
+        assert!(transitions(&mut app).is_empty());
+
+        // Out of the default reach, inside the bounds of the primitive.
+        *app.world_mut().get_mut::<GlobalTransform>(avatar).unwrap() =
+            GlobalTransform::from_translation(Vec3::new(6.5, 0.0, 0.0));
+        app.update();
+        assert_eq!(transitions(&mut app).len(), 1);
+    }
+
+    #[test]
+    fn test_hosts_do_not_leave() {
+        let mut app = portals(&["host", "a.glb"]);
+        app.world_mut().spawn((
+            Portal {
+                target: "b.glb".into(),
+            },
+            GlobalTransform::default(),
+        ));
+        app.world_mut()
+            .spawn((Respawnable, GlobalTransform::default()));
+        app.update();
+        assert!(transitions(&mut app).is_empty());
+    }
+
+    #[test]
+    fn test_walk_from_one_host_to_the_other() {
+        let port = free_port().to_string();
+        let target = format!("127.0.0.1:{}", port);
+        let mut a = portals(&["join", "127.0.0.1", "--avatar", "me.vrm"]);
+        let mut b = networked(Args::parse_from([
+            "lux",
+            "host",
+            "b.glb",
+            "127.0.0.1",
+            "--port",
+            &port,
+        ]));
+        a.world_mut().spawn((
+            Portal {
+                target: target.clone(),
+            },
+            GlobalTransform::from_translation(Vec3::new(5.0, 0.0, 0.0)),
+            Aabb::from_min_max(-Vec3::ONE, Vec3::ONE),
+        ));
+        let avatar = a
+            .world_mut()
+            .spawn((Respawnable, GlobalTransform::default()))
+            .id();
+        a.update();
+        assert!(transitions(&mut a).is_empty());
+
+        *a.world_mut().get_mut::<GlobalTransform>(avatar).unwrap() =
+            GlobalTransform::from_translation(Vec3::new(5.5, 0.0, 0.0));
+        a.update();
+        let next = transitions(&mut a);
+        assert_eq!(next.len(), 1);
+        let Some(Command::Join { avatar_file, .. }) = &next[0].args.command else {
+            panic!("portal does not join");
+        };
+        assert_eq!(avatar_file.as_deref(), Some("me.vrm"));
+        a.update();
+        assert!(transitions(&mut a).is_empty());
+
+        // The relaunched app, with the arguments of the portal, joins b.
+        let mut client = networked(next[0].args.clone());
+        client
+            .world_mut()
+            .spawn((SyncMark, Name::new("Walker"), Transform::default()));
+        let mut joined = false;
+        for _ in 0..600 {
+            client.update();
+            b.update();
+            joined = b
+                .world_mut()
+                .query::<&Name>()
+                .iter(b.world())
+                .any(|n| n.as_str() == "Walker");
+            if joined {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+        assert!(joined, "the client never joined b");
+    }
+
+    fn networked(args: Args) -> App {
+        let mut app = App::new();
+        lux_headless::init(&mut app);
+        lux_networking::init(&args, &mut app);
+        app.insert_resource(args);
+        app
+    }
+
+    fn portals(cli: &[&str]) -> App {
+        let mut app = App::new();
+        app.insert_resource(Args::parse_from(
+            std::iter::once("lux").chain(cli.iter().copied()),
+        ));
+        app.add_event::<PortalTransition>();
+        app.add_systems(Update, (portals_from_gltf, enter_portals).chain());
+        app
+    }
+
+    /// A port given by the system, with the ones after it free for the web
+    /// servers of the session.
+    fn free_port() -> u16 {
+        loop {
+            let port = UdpSocket::bind("127.0.0.1:0")
+                .and_then(|s| s.local_addr())
+                .unwrap()
+                .port();
+            let web = (1..SESSION_PORTS).all(|i| {
+                port.checked_add(i)
+                    .is_some_and(|p| TcpListener::bind(("127.0.0.1", p)).is_ok())
+            });
+            let transforms = port
+                .checked_add(1)
+                .is_some_and(|p| UdpSocket::bind(("127.0.0.1", p)).is_ok());
+            if web && transforms {
+                return port;
+            }
+        }
+    }
+
+    fn transitions(app: &mut App) -> Vec<PortalTransition> {
+        app.world_mut()
+            .resource_mut::<Events<PortalTransition>>()
+            .drain()
+            .collect()
+    }
+}
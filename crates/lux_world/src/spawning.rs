@@ -0,0 +1,268 @@
+use avian3d::prelude::LinearVelocity;
+use bevy::{gltf::GltfExtras, prelude::*};
+use lux_components::{pick_free_spawn, SpawnPoint, User, VoidLevel, DEFAULT_VOID_LEVEL};
+use serde_json::Value;
+
+/// Where users appear when the world has no spawn points.
+pub(crate) const DEFAULT_SPAWN: Vec3 = Vec3::new(0.0, 3.0, 0.0);
+/// Physical users are dropped from a bit above the spawn point.
+const SPAWN_CLEARANCE: f32 = 1.0;
+/// Spawn points with somebody closer than this are considered taken.
+const FREE_RADIUS: f32 = 1.0;
+/// How long to wait for the world spawn points before staying where we are.
+const SPAWN_WAIT_SECONDS: f32 = 10.0;
+
+/// The local avatar, moved to a spawn point when falling into the void.
+#[derive(Component, Default)]
+pub(crate) struct Respawnable;
+
+/// The local avatar is waiting for the world spawn points to be known,
+/// as the world could still be loading or arriving from the host.
+#[derive(Component)]
+pub(crate) struct PendingSpawn {
+    timer: Timer,
+}
+
+impl Default for PendingSpawn {
+    fn default() -> Self {
+        Self {
+            timer: Timer::from_seconds(SPAWN_WAIT_SECONDS, TimerMode::Once),
+        }
+    }
+}
+
+pub(crate) fn init(app: &mut App) {
+    app.add_systems(
+        Update,
+        (spawn_points_from_gltf, place_pending, respawn_from_void).chain(),
+    );
+}
+
+#[allow(clippy::type_complexity)]
+fn spawn_points_from_gltf(
+    mut commands: Commands,
+    nodes: Query<
+        (Entity, Option<&Name>, Option<&GltfExtras>),
+        Or<(Added<Name>, Added<GltfExtras>)>,
+    >,
+) {
+    for (entity, name, extras) in nodes.iter() {
+        let extras = extras.and_then(|e| serde_json::from_str::<Value>(&e.value).ok());
+        if is_spawn_point(name.map(|n| n.as_str()), extras.as_ref()) {
+            debug!("Spawn point {:?}", entity);
+            commands.entity(entity).insert(SpawnPoint);
+        }
+        if let Some(y) = extras.as_ref().and_then(void_level) {
+            debug!("Void level {} from {:?}", y, entity);
+            commands.entity(entity).insert(VoidLevel { y });
+        }
+    }
+}
+
+/// Nodes named `SpawnPoint*` or with the `lux_spawn` extra set.
+fn is_spawn_point(name: Option<&str>, extras: Option<&Value>) -> bool {
+    let by_name = name.is_some_and(|n| n.starts_with("SpawnPoint"));
+    let by_extras = extras
+        .and_then(|e| e.get("lux_spawn"))
+        .and_then(Value::as_bool)
+        .unwrap_or(false);
+    by_name || by_extras
+}
+
+fn void_level(extras: &Value) -> Option<f32> {
+    extras
+        .get("lux_void_level")
+        .and_then(Value::as_f64)
+        .map(|y| y as f32)
+}
+
+type Teleported<'w, 's> =
+    Query<'w, 's, (&'static mut Transform, Option<&'static mut LinearVelocity>)>;
+
+fn place_pending(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut pending: Query<(Entity, &mut PendingSpawn)>,
+    points: Query<&GlobalTransform, With<SpawnPoint>>,
+    users: Query<(Entity, &GlobalTransform), With<User>>,
+    parents: Query<&Parent>,
+    mut transforms: Teleported,
+) {
+    for (entity, mut wait) in pending.iter_mut() {
+        let Some(point) = free_spawn(entity, &points, &users) else {
+            if wait.timer.tick(time.delta()).just_finished() {
+                debug!("No spawn points in the world, staying at default");
+                commands.entity(entity).remove::<PendingSpawn>();
+            }
+            continue;
+        };
+        teleport(root_of(entity, &parents), point, &mut transforms);
+        commands.entity(entity).remove::<PendingSpawn>();
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn respawn_from_void(
+    avatars: Query<(Entity, &GlobalTransform), (With<Respawnable>, Without<PendingSpawn>)>,
+    levels: Query<&VoidLevel>,
+    points: Query<&GlobalTransform, With<SpawnPoint>>,
+    users: Query<(Entity, &GlobalTransform), With<User>>,
+    parents: Query<&Parent>,
+    mut transforms: Teleported,
+) {
+    let void = levels
+        .iter()
+        .map(|l| l.y)
+        .reduce(f32::min)
+        .unwrap_or(DEFAULT_VOID_LEVEL);
+    for (entity, gt) in avatars.iter() {
+        if gt.translation().y >= void {
+            continue;
+        }
+        debug!("Avatar {:?} fell in the void, respawning", entity);
+        let point = free_spawn(entity, &points, &users).unwrap_or(DEFAULT_SPAWN);
+        teleport(root_of(entity, &parents), point, &mut transforms);
+    }
+}
+
+fn free_spawn(
+    myself: Entity,
+    points: &Query<&GlobalTransform, With<SpawnPoint>>,
+    users: &Query<(Entity, &GlobalTransform), With<User>>,
+) -> Option<Vec3> {
+    let points: Vec<Vec3> = points.iter().map(|p| p.translation()).collect();
+    let users: Vec<Vec3> = users
+        .iter()
+        .filter(|(e, _)| *e != myself)
+        .map(|(_, gt)| gt.translation())
+        .collect();
+    pick_free_spawn(&points, &users, FREE_RADIUS)
+}
+
+/// Avatars can be nested in their physics body, that is what has to move.
+fn root_of(entity: Entity, parents: &Query<&Parent>) -> Entity {
+    let mut root = entity;
+    while let Ok(parent) = parents.get(root) {
+        root = parent.get();
+    }
+    root
+}
+
+fn teleport(entity: Entity, point: Vec3, transforms: &mut Teleported) {
+    let Ok((mut transform, velocity)) = transforms.get_mut(entity) else {
+        return;
+    };
+    match velocity {
+        Some(mut velocity) => {
+            transform.translation = point + Vec3::Y * SPAWN_CLEARANCE;
+            velocity.0 = Vec3::ZERO;
+        }
+        None => transform.translation = point,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_spawn_point_by_name_or_extras() {
+        assert!(is_spawn_point(Some("SpawnPoint.001"), None));
+        assert!(!is_spawn_point(Some("Chair"), None));
+        let extras: Value = serde_json::from_str(r#"{"lux_spawn": true}"#).unwrap();
+        assert!(is_spawn_point(Some("Chair"), Some(&extras)));
+    }
+
+    #[test]
+    fn test_void_level_from_extras() {
+        let extras: Value = serde_json::from_str(r#"{"lux_void_level": -5}"#).unwrap();
+        assert_eq!(void_level(&extras), Some(-5.0));
+    }
+
+    #[test]
+    fn test_pending_avatar_moves_to_spawn_point() {
+        let mut app = setup();
+        spawn_point(&mut app, Vec3::new(10.0, 0.0, 0.0));
+        let body = app
+            .world_mut()
+            .spawn((Transform::default(), LinearVelocity(Vec3::Y)))
+            .id();
+        app.world_mut()
+            .spawn((
+                Respawnable,
+                PendingSpawn::default(),
+                GlobalTransform::default(),
+            ))
+            .set_parent(body);
+        app.update();
+
+        let (transform, velocity) = body_state(&app, body);
+        assert_eq!(transform.translation, Vec3::new(10.0, SPAWN_CLEARANCE, 0.0));
+        assert_eq!(velocity, Vec3::ZERO);
+    }
+
+    #[test]
+    fn test_respawn_below_world_void_level() {
+        let mut app = setup();
+        spawn_point(&mut app, Vec3::new(0.0, 0.0, 4.0));
+        app.world_mut().spawn(VoidLevel { y: -5.0 });
+        let body = app
+            .world_mut()
+            .spawn((
+                Transform::from_xyz(0.0, -6.0, 0.0),
+                LinearVelocity(-Vec3::Y),
+            ))
+            .id();
+        app.world_mut()
+            .spawn((
+                Respawnable,
+                GlobalTransform::from_translation(Vec3::new(0.0, -6.0, 0.0)),
+            ))
+            .set_parent(body);
+        app.update();
+
+        let (transform, velocity) = body_state(&app, body);
+        assert_eq!(transform.translation, Vec3::new(0.0, SPAWN_CLEARANCE, 4.0));
+        assert_eq!(velocity, Vec3::ZERO);
+    }
+
+    #[test]
+    fn test_no_respawn_above_void_level() {
+        let mut app = setup();
+        let body = app
+            .world_mut()
+            .spawn(Transform::from_xyz(0.0, -6.0, 0.0))
+            .id();
+        app.world_mut()
+            .spawn((
+                Respawnable,
+                GlobalTransform::from_translation(Vec3::new(0.0, -6.0, 0.0)),
+            ))
+            .set_parent(body);
+        app.update();
+
+        let (transform, _) = body_state(&app, body);
+        assert_eq!(transform.translation.y, -6.0);
+    }
+
+    fn setup() -> App {
+        let mut app = App::new();
+        app.init_resource::<Time>();
+        init(&mut app);
+        app
+    }
+
+    fn spawn_point(app: &mut App, at: Vec3) {
+        app.world_mut()
+            .spawn((SpawnPoint, GlobalTransform::from_translation(at)));
+    }
+
+    fn body_state(app: &App, body: Entity) -> (Transform, Vec3) {
+        let entity = app.world().entity(body);
+        let velocity = entity
+            .get::<LinearVelocity>()
+            .map(|v| v.0)
+            .unwrap_or_default();
+        (*entity.get::<Transform>().unwrap(), velocity)
+    }
+}
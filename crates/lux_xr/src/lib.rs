@@ -1,5 +1,6 @@
 mod flightcam;
 mod naming;
+mod picking;
 
 use bevy::prelude::*;
 use bevy_mod_openxr::add_xr_plugins;
@@ -14,6 +15,7 @@ pub fn init<G: PluginGroup>(app: &mut App, g: G) {
         .add_systems(PostUpdate, quit_xr_on_app_exit);
     naming::init(app);
     flightcam::init(app);
+    picking::init(app);
     lux_xr_avatar_generic::init(app);
 }
 
@@ -0,0 +1,102 @@
+use bevy::prelude::*;
+use bevy_mod_xr::hands::{HandBone, RightHand};
+use bevy_xr_utils::xr_utils_actions::{
+    ActiveSet, XRUtilsAction, XRUtilsActionSet, XRUtilsActionState, XRUtilsActionSystemSet,
+    XRUtilsBinding,
+};
+use lux_components::{PickRequest, PickSelect, PickSource};
+
+/// Length of the pointer drawn while the trigger is held.
+const POINTER_LENGTH: f32 = 5.0;
+/// Pull of the trigger that counts as a press, Touch triggers are analog only.
+const TRIGGER_PRESSED: f32 = 0.8;
+
+pub fn init(app: &mut App) {
+    app.add_systems(
+        Startup,
+        create_action_entities.before(XRUtilsActionSystemSet::CreateEvents),
+    )
+    .add_systems(Update, handle_select_input);
+}
+
+#[derive(Component)]
+struct SelectAction;
+
+fn create_action_entities(mut commands: Commands) {
+    let set = commands
+        .spawn((
+            XRUtilsActionSet {
+                name: "picking".into(),
+                pretty_name: "pretty picking set".into(),
+                priority: u32::MIN,
+            },
+            ActiveSet,
+        ))
+        .id();
+    let action = commands
+        .spawn((
+            XRUtilsAction {
+                action_name: "select".into(),
+                localized_name: "select_localized".into(),
+                action_type: bevy_mod_xr::actions::ActionType::Float,
+            },
+            SelectAction,
+        ))
+        .id();
+    let binding_index = commands
+        .spawn(XRUtilsBinding {
+            profile: "/interaction_profiles/valve/index_controller".into(),
+            binding: "/user/hand/right/input/trigger/value".into(),
+        })
+        .id();
+    let binding_touch = commands
+        .spawn(XRUtilsBinding {
+            profile: "/interaction_profiles/oculus/touch_controller".into(),
+            binding: "/user/hand/right/input/trigger/value".into(),
+        })
+        .id();
+    commands.entity(action).add_child(binding_index);
+    commands.entity(action).add_child(binding_touch);
+    commands.entity(set).add_child(action);
+}
+
+fn handle_select_input(
+    mut gizmos: Gizmos,
+    action_query: Query<&XRUtilsActionState, With<SelectAction>>,
+    hand: Query<(&GlobalTransform, &HandBone), With<RightHand>>,
+    mut requests: EventWriter<PickRequest>,
+    mut was_pressed: Local<bool>,
+) {
+    let Some(palm) = hand
+        .iter()
+        .find(|(_, b)| matches!(b, HandBone::Palm))
+        .map(|(gt, _)| gt)
+    else {
+        return;
+    };
+    let ray = Ray3d {
+        origin: palm.translation(),
+        direction: palm.forward(),
+    };
+    for state in action_query.iter() {
+        let XRUtilsActionState::Float(state) = state else {
+            continue;
+        };
+        let pressed = state.current_state >= TRIGGER_PRESSED;
+        if pressed {
+            gizmos.line(
+                ray.origin,
+                ray.get_point(POINTER_LENGTH),
+                Color::srgb(0.9, 0.9, 0.9),
+            );
+        }
+        if pressed && !*was_pressed {
+            requests.send(PickRequest {
+                ray,
+                source: PickSource::RightHand,
+                select: Some(PickSelect::Replace),
+            });
+        }
+        *was_pressed = pressed;
+    }
+}
@@ -6,7 +6,12 @@ use bevy_mod_xr::{
     hands::{HandBone, LeftHand, RightHand},
     session::XrTrackingRoot,
 };
-use lux_avatar_generic::{bones::*, AvatarGeneric};
+use lux_avatar_generic::{
+    bones::*,
+    calibration::{Calibration, UserMeasures},
+    fingers::{HandPose, TrackedHand},
+    AvatarGeneric,
+};
 use lux_components::LocalUser;
 
 pub fn init(app: &mut App) {
@@ -16,8 +21,26 @@ pub fn init(app: &mut App) {
     );
     app.add_systems(
         Update,
-        (copy_head, copy_transform_hand_l, copy_transform_hand_r).chain(),
+        (
+            copy_head,
+            copy_transform_hand_l,
+            copy_transform_hand_r,
+            track_fingers,
+        )
+            .chain(),
     );
+    app.add_systems(Update, capture_t_pose);
+}
+
+/// Seconds the T-pose is held before the avatar is calibrated to it.
+const T_POSE_SECONDS: f32 = 2.0;
+
+/// Tracking is in real sizes, the scaled avatar takes them shrunk by its scale.
+fn avatar_scale(avatars: &Query<&Calibration, With<LocalUser>>) -> f32 {
+    avatars
+        .get_single()
+        .map(|calibration| calibration.scale)
+        .unwrap_or(1.0)
 }
 
 fn local_user_enters_root(
@@ -48,11 +71,13 @@ fn local_user_exits_root(
 fn copy_transform_hand_l(
     src: Query<(&Transform, &HandBone), With<LeftHand>>,
     mut dst: Query<&mut Transform, (Without<LeftHand>, With<Target<HandL>>, With<LocalUser>)>,
+    avatars: Query<&Calibration, With<LocalUser>>,
 ) {
+    let scale = avatar_scale(&avatars);
     for mut tfd in dst.iter_mut() {
         for (tfs, b) in src.iter() {
             if let HandBone::Palm = b {
-                tfd.translation = tfs.translation;
+                tfd.translation = tfs.translation / scale;
                 tfd.rotation = tfs.rotation;
             }
         }
@@ -62,21 +87,87 @@ fn copy_transform_hand_l(
 fn copy_transform_hand_r(
     src: Query<(&Transform, &HandBone), With<RightHand>>,
     mut dst: Query<&mut Transform, (Without<RightHand>, With<Target<HandR>>, With<LocalUser>)>,
+    avatars: Query<&Calibration, With<LocalUser>>,
 ) {
+    let scale = avatar_scale(&avatars);
     for mut tfd in dst.iter_mut() {
         for (tfs, b) in src.iter() {
             if let HandBone::Palm = b {
-                tfd.translation = tfs.translation;
+                tfd.translation = tfs.translation / scale;
                 tfd.rotation = tfs.rotation;
             }
         }
     }
 }
 
+fn track_fingers(
+    mut cmd: Commands,
+    left: Query<(&Transform, &HandBone), With<LeftHand>>,
+    right: Query<(&Transform, &HandBone), (With<RightHand>, Without<LeftHand>)>,
+    mut avatars: Query<(Entity, Option<&mut HandPose>), (With<AvatarGeneric>, With<LocalUser>)>,
+) {
+    let left = tracked_hand(left.iter());
+    let right = tracked_hand(right.iter());
+    if left.is_none() && right.is_none() {
+        return;
+    }
+    for (avatar, pose) in avatars.iter_mut() {
+        let Some(mut pose) = pose else {
+            cmd.entity(avatar).try_insert(HandPose::default());
+            continue;
+        };
+        let mut next = pose.clone();
+        if let Some(left) = left {
+            next.left = left.retarget();
+        }
+        if let Some(right) = right {
+            next.right = right.retarget();
+        }
+        pose.set_if_neq(next);
+    }
+}
+
+/// Joints of a hand, if all the ones needed are tracked.
+fn tracked_hand<'a>(
+    joints: impl Iterator<Item = (&'a Transform, &'a HandBone)>,
+) -> Option<TrackedHand> {
+    let mut tracked = TrackedHand::default();
+    let mut found = 0;
+    for (transform, bone) in joints {
+        let slot = match bone {
+            HandBone::ThumbMetacarpal => &mut tracked.thumb[0],
+            HandBone::ThumbProximal => &mut tracked.thumb[1],
+            HandBone::ThumbDistal => &mut tracked.thumb[2],
+            HandBone::IndexMetacarpal => &mut tracked.fingers[0][0],
+            HandBone::IndexProximal => &mut tracked.fingers[0][1],
+            HandBone::IndexIntermediate => &mut tracked.fingers[0][2],
+            HandBone::IndexDistal => &mut tracked.fingers[0][3],
+            HandBone::MiddleMetacarpal => &mut tracked.fingers[1][0],
+            HandBone::MiddleProximal => &mut tracked.fingers[1][1],
+            HandBone::MiddleIntermediate => &mut tracked.fingers[1][2],
+            HandBone::MiddleDistal => &mut tracked.fingers[1][3],
+            HandBone::RingMetacarpal => &mut tracked.fingers[2][0],
+            HandBone::RingProximal => &mut tracked.fingers[2][1],
+            HandBone::RingIntermediate => &mut tracked.fingers[2][2],
+            HandBone::RingDistal => &mut tracked.fingers[2][3],
+            HandBone::LittleMetacarpal => &mut tracked.fingers[3][0],
+            HandBone::LittleProximal => &mut tracked.fingers[3][1],
+            HandBone::LittleIntermediate => &mut tracked.fingers[3][2],
+            HandBone::LittleDistal => &mut tracked.fingers[3][3],
+            _ => continue,
+        };
+        *slot = transform.rotation;
+        found += 1;
+    }
+    (found == 19).then_some(tracked)
+}
+
 fn copy_head(
     src: Query<(&Transform, &XrCamera)>,
     mut dst: Query<&mut Transform, (Without<XrCamera>, With<Target<Head>>, With<LocalUser>)>,
+    avatars: Query<&Calibration, With<LocalUser>>,
 ) {
+    let scale = avatar_scale(&avatars);
     let roty = Quat::from_euler(EulerRot::XYZ, 0.0, f32::to_radians(180.0), 0.0);
     for mut tfd in dst.iter_mut() {
         let mut pos = vec3(0.0, 0.0, 0.0);
@@ -88,6 +179,133 @@ fn copy_head(
             pos += tfs.translation;
             ct += vec3(1.0, 1.0, 1.0);
         }
-        tfd.translation = pos / ct;
+        tfd.translation = pos / ct / scale;
+    }
+}
+
+/// Arms open and level at the shoulders, hands as far apart as the user is
+/// about tall.
+fn is_t_pose(head: Vec3, left: Vec3, right: Vec3) -> bool {
+    let shoulders = head.y - 0.5..head.y - 0.05;
+    let level = (left.y - right.y).abs() < 0.1;
+    let span = vec3(left.x - right.x, 0.0, left.z - right.z).length();
+    shoulders.contains(&left.y) && shoulders.contains(&right.y) && level && span > head.y * 0.8
+}
+
+/// Holding the T-pose measures the user, the avatar is then calibrated to it.
+fn capture_t_pose(
+    mut cmd: Commands,
+    time: Res<Time>,
+    mut held: Local<f32>,
+    camera: Query<(&Transform, &XrCamera)>,
+    left: Query<(&Transform, &HandBone), With<LeftHand>>,
+    right: Query<(&Transform, &HandBone), (With<RightHand>, Without<LeftHand>)>,
+    avatars: Query<Entity, (With<AvatarGeneric>, With<LocalUser>)>,
+) {
+    let palm = |(tf, bone): (&Transform, &HandBone)| match bone {
+        HandBone::Palm => Some(tf.translation),
+        _ => None,
+    };
+    let head = camera
+        .iter()
+        .find(|(_, c)| c.0 == 0)
+        .map(|(tf, _)| tf.translation);
+    let (Some(head), Some(left), Some(right)) = (
+        head,
+        left.iter().find_map(palm),
+        right.iter().find_map(palm),
+    ) else {
+        *held = 0.0;
+        return;
+    };
+    if !is_t_pose(head, left, right) {
+        *held = 0.0;
+        return;
+    }
+    let before = *held;
+    *held += time.delta_seconds();
+    if before >= T_POSE_SECONDS || *held < T_POSE_SECONDS {
+        return;
+    }
+    let measures = UserMeasures {
+        eye_height: head.y,
+        arm_span: Some(left.distance(right)),
+    };
+    info!(
+        "T-pose captured, eyes at {:.2} and arms span {:.2}",
+        head.y,
+        left.distance(right)
+    );
+    for avatar in avatars.iter() {
+        cmd.entity(avatar).try_insert(measures);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_is_t_pose() {
+        let head = vec3(0.0, 1.6, 0.0);
+        assert!(is_t_pose(head, vec3(-0.8, 1.4, 0.0), vec3(0.8, 1.4, 0.0)));
+        // Arms down, one arm up, hands together.
+        assert!(!is_t_pose(head, vec3(-0.3, 0.8, 0.0), vec3(0.3, 0.8, 0.0)));
+        assert!(!is_t_pose(head, vec3(-0.8, 1.4, 0.0), vec3(0.8, 1.55, 0.0)));
+        assert!(!is_t_pose(head, vec3(-0.2, 1.4, 0.0), vec3(0.2, 1.4, 0.0)));
+    }
+
+    #[test]
+    fn test_t_pose_held_measures_the_user() {
+        let mut app = App::new();
+        app.init_resource::<Time>();
+        app.add_systems(Update, capture_t_pose);
+        app.world_mut()
+            .spawn((Transform::from_xyz(0.0, 1.6, 0.0), XrCamera(0)));
+        let left = app
+            .world_mut()
+            .spawn((
+                Transform::from_xyz(-0.8, 1.4, 0.0),
+                HandBone::Palm,
+                LeftHand,
+            ))
+            .id();
+        app.world_mut().spawn((
+            Transform::from_xyz(0.8, 1.4, 0.0),
+            HandBone::Palm,
+            RightHand,
+        ));
+        let avatar = app
+            .world_mut()
+            .spawn((AvatarGeneric::default(), LocalUser))
+            .id();
+
+        let advance = |app: &mut App, seconds: f32| {
+            app.world_mut()
+                .resource_mut::<Time>()
+                .advance_by(Duration::from_secs_f32(seconds));
+            app.update();
+        };
+        advance(&mut app, 1.5);
+        // Dropping the arms starts over.
+        app.world_mut()
+            .get_mut::<Transform>(left)
+            .unwrap()
+            .translation
+            .y = 0.8;
+        advance(&mut app, 1.0);
+        app.world_mut()
+            .get_mut::<Transform>(left)
+            .unwrap()
+            .translation
+            .y = 1.4;
+        advance(&mut app, 1.5);
+        assert!(app.world().get::<UserMeasures>(avatar).is_none());
+
+        advance(&mut app, 1.0);
+        let measures = *app.world().get::<UserMeasures>(avatar).unwrap();
+        assert_eq!(measures.eye_height, 1.6);
+        assert_eq!(measures.arm_span, Some(1.6));
     }
 }